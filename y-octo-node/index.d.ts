@@ -11,10 +11,17 @@ export declare class Doc {
   createArray(): YArray
   createText(): YText
   createMap(): YMap
+  getOrCreateXmlFragment(key: string): YXmlFragment
+  createXmlElement(tag: string): YXmlElement
+  createXmlText(): YXmlText
   applyUpdate(update: Buffer): void
   encodeStateAsUpdateV1(state?: Buffer | undefined | null): Buffer
   gc(): void
-  onUpdate(callback: (result: Uint8Array) => void): void
+  onUpdate(callback: (result: Uint8Array) => void): Subscription
+}
+
+export declare class Subscription {
+  unsubscribe(): void
 }
 
 export declare class YArray {
@@ -46,3 +53,39 @@ export declare class YText {
   get length(): number
   toString(): string
 }
+
+export declare class YXmlElement {
+  constructor()
+  get tag(): string
+  get length(): number
+  get isEmpty(): boolean
+  getAttribute<T = unknown>(key: string): T
+  insertAttribute(key: string, value: boolean | number | string | Record<string, any> | null | undefined): void
+  removeAttribute(key: string): void
+  get(index: number): YXmlElement | YXmlText | null
+  insert(index: number, child: YXmlElement | YXmlText): void
+  push(child: YXmlElement | YXmlText): void
+  remove(index: number, len: number): void
+  toString(): string
+}
+
+export declare class YXmlFragment {
+  constructor()
+  get length(): number
+  get isEmpty(): boolean
+  get(index: number): YXmlElement | YXmlText | null
+  insert(index: number, child: YXmlElement | YXmlText): void
+  push(child: YXmlElement | YXmlText): void
+  remove(index: number, len: number): void
+  toString(): string
+}
+
+export declare class YXmlText {
+  constructor()
+  get length(): number
+  get isEmpty(): boolean
+  insert(index: number, str: string): void
+  remove(index: number, len: number): void
+  format(index: number, len: number, attrs: Record<string, any>): void
+  toString(): string
+}
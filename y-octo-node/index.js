@@ -577,6 +577,10 @@ if (!nativeBinding) {
 
 module.exports = nativeBinding
 module.exports.Doc = nativeBinding.Doc
+module.exports.Subscription = nativeBinding.Subscription
 module.exports.YArray = nativeBinding.YArray
 module.exports.YMap = nativeBinding.YMap
 module.exports.YText = nativeBinding.YText
+module.exports.YXmlElement = nativeBinding.YXmlElement
+module.exports.YXmlFragment = nativeBinding.YXmlFragment
+module.exports.YXmlText = nativeBinding.YXmlText
@@ -2,13 +2,40 @@ use napi::{
     bindgen_prelude::{Buffer as JsBuffer, JsFunction},
     threadsafe_function::{ErrorStrategy, ThreadsafeFunction, ThreadsafeFunctionCallMode},
 };
-use y_octo::{CrdtRead, Doc as YDoc, History, RawDecoder, StateVector};
+use std::{
+    collections::HashMap,
+    sync::{Arc, Mutex, Weak},
+};
+
+use y_octo::{CrdtRead, Doc as YDoc, RawDecoder, StateVector, Subscription as YSubscription};
 
 use super::*;
 
+type Subscriptions = Mutex<HashMap<u32, YSubscription>>;
+
 #[napi]
 pub struct Doc {
     doc: YDoc,
+    subscriptions: Arc<Subscriptions>,
+    next_subscription_id: u32,
+}
+
+/// The handle of a callback registered on the doc, the callback is kept
+/// until `unsubscribe` is called or the doc is released.
+#[napi]
+pub struct Subscription {
+    id: u32,
+    subscriptions: Weak<Subscriptions>,
+}
+
+#[napi]
+impl Subscription {
+    #[napi]
+    pub fn unsubscribe(&self) {
+        if let Some(subscriptions) = self.subscriptions.upgrade() {
+            subscriptions.lock().unwrap().remove(&self.id);
+        }
+    }
 }
 
 #[napi]
@@ -21,6 +48,8 @@ impl Doc {
             } else {
                 YDoc::default()
             },
+            subscriptions: Arc::default(),
+            next_subscription_id: 0,
         }
     }
 
@@ -84,6 +113,30 @@ impl Doc {
         self.doc.create_map().map(YMap::inner_new).map_err(anyhow::Error::from)
     }
 
+    #[napi]
+    pub fn get_or_create_xml_fragment(&self, key: String) -> Result<YXmlFragment> {
+        self.doc
+            .get_or_create_xml_fragment(key)
+            .map(YXmlFragment::inner_new)
+            .map_err(anyhow::Error::from)
+    }
+
+    #[napi]
+    pub fn create_xml_element(&self, tag: String) -> Result<YXmlElement> {
+        self.doc
+            .create_xml_element(tag)
+            .map(YXmlElement::inner_new)
+            .map_err(anyhow::Error::from)
+    }
+
+    #[napi]
+    pub fn create_xml_text(&self) -> Result<YXmlText> {
+        self.doc
+            .create_xml_text()
+            .map(YXmlText::inner_new)
+            .map_err(anyhow::Error::from)
+    }
+
     #[napi]
     pub fn apply_update(&mut self, update: JsBuffer) -> Result<()> {
         self.doc.apply_update_from_binary_v1(update)?;
@@ -111,15 +164,22 @@ impl Doc {
     }
 
     #[napi(ts_args_type = "callback: (result: Uint8Array) => void")]
-    pub fn on_update(&mut self, callback: JsFunction) -> Result<()> {
+    pub fn on_update(&mut self, callback: JsFunction) -> Result<Subscription> {
         let tsfn: ThreadsafeFunction<JsBuffer, ErrorStrategy::Fatal> =
             callback.create_threadsafe_function(0, |ctx| Ok(vec![ctx.value]))?;
 
-        let callback = move |update: &[u8], _h: &[History]| {
+        let subscription = self.doc.observe_update_v1(move |update, _origin| {
             tsfn.call(JsBuffer::from(update.to_vec()), ThreadsafeFunctionCallMode::Blocking);
-        };
-        self.doc.subscribe(Box::new(callback));
-        Ok(())
+        });
+
+        let id = self.next_subscription_id;
+        self.next_subscription_id += 1;
+        self.subscriptions.lock().unwrap().insert(id, subscription);
+
+        Ok(Subscription {
+            id,
+            subscriptions: Arc::downgrade(&self.subscriptions),
+        })
     }
 }
 
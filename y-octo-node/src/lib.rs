@@ -6,12 +6,14 @@ mod doc;
 mod map;
 mod text;
 mod utils;
+mod xml;
 
 pub use array::YArray;
-pub use doc::Doc;
+pub use doc::{Doc, Subscription};
 pub use map::YMap;
 pub use text::YText;
 use utils::{
     MixedRefYType, MixedYType, get_any_from_js_object, get_any_from_js_unknown, get_js_unknown_from_any,
     get_js_unknown_from_value,
 };
+pub use xml::{YXmlElement, YXmlFragment, YXmlText};
@@ -0,0 +1,247 @@
+use napi::{
+    Env, JsObject, JsUnknown,
+    bindgen_prelude::{Either, Either3},
+};
+use y_octo::{Any, TextAttributes, Value, XMLElement, XMLFragment, XMLText};
+
+use super::*;
+
+pub type XmlChild = Either3<YXmlElement, YXmlText, JsUnknown>;
+pub type XmlChildRef<'a> = Either<&'a YXmlElement, &'a YXmlText>;
+
+fn get_xml_child_from_value(env: Env, value: Option<Value>) -> Result<XmlChild> {
+    match value {
+        Some(Value::XMLElement(element)) => Ok(XmlChild::A(YXmlElement::inner_new(element))),
+        Some(Value::XMLText(text)) => Ok(XmlChild::B(YXmlText::inner_new(text))),
+        _ => Ok(XmlChild::C(env.get_null()?.into_unknown())),
+    }
+}
+
+fn get_value_from_xml_child(child: XmlChildRef) -> Value {
+    match child {
+        XmlChildRef::A(element) => Value::XMLElement(element.element.clone()),
+        XmlChildRef::B(text) => Value::XMLText(text.text.clone()),
+    }
+}
+
+#[napi]
+pub struct YXmlFragment {
+    pub(crate) fragment: XMLFragment,
+}
+
+#[napi]
+impl YXmlFragment {
+    #[allow(clippy::new_without_default)]
+    #[napi(constructor)]
+    pub fn new() -> Self {
+        unimplemented!()
+    }
+
+    pub(crate) fn inner_new(fragment: XMLFragment) -> Self {
+        Self { fragment }
+    }
+
+    #[napi(getter)]
+    pub fn length(&self) -> i64 {
+        self.fragment.len() as i64
+    }
+
+    #[napi(getter)]
+    pub fn is_empty(&self) -> bool {
+        self.fragment.is_empty()
+    }
+
+    #[napi(ts_return_type = "YXmlElement | YXmlText | null")]
+    pub fn get(&self, env: Env, index: i64) -> Result<XmlChild> {
+        get_xml_child_from_value(env, self.fragment.get(index as u64))
+    }
+
+    #[napi(ts_args_type = "index: number, child: YXmlElement | YXmlText")]
+    pub fn insert(&mut self, index: i64, child: XmlChildRef) -> Result<()> {
+        self.fragment
+            .insert(index as u64, get_value_from_xml_child(child))
+            .map_err(anyhow::Error::from)
+    }
+
+    #[napi(ts_args_type = "child: YXmlElement | YXmlText")]
+    pub fn push(&mut self, child: XmlChildRef) -> Result<()> {
+        self.fragment
+            .push(get_value_from_xml_child(child))
+            .map_err(anyhow::Error::from)
+    }
+
+    #[napi]
+    pub fn remove(&mut self, index: i64, len: i64) -> Result<()> {
+        self.fragment
+            .remove(index as u64, len as u64)
+            .map_err(anyhow::Error::from)
+    }
+
+    #[allow(clippy::inherent_to_string)]
+    #[napi]
+    pub fn to_string(&self) -> String {
+        self.fragment.to_string()
+    }
+}
+
+#[napi]
+pub struct YXmlElement {
+    pub(crate) element: XMLElement,
+}
+
+#[napi]
+impl YXmlElement {
+    #[allow(clippy::new_without_default)]
+    #[napi(constructor)]
+    pub fn new() -> Self {
+        unimplemented!()
+    }
+
+    pub(crate) fn inner_new(element: XMLElement) -> Self {
+        Self { element }
+    }
+
+    #[napi(getter)]
+    pub fn tag(&self) -> String {
+        self.element.tag()
+    }
+
+    #[napi(getter)]
+    pub fn length(&self) -> i64 {
+        self.element.len() as i64
+    }
+
+    #[napi(getter)]
+    pub fn is_empty(&self) -> bool {
+        self.element.is_empty()
+    }
+
+    #[napi(ts_generic_types = "T = unknown", ts_return_type = "T")]
+    pub fn get_attribute(&self, env: Env, key: String) -> Result<JsUnknown> {
+        match self.element.get_attribute(&key) {
+            Some(value) => get_js_unknown_from_value(env, value),
+            None => env.get_null().map(|v| v.into_unknown()),
+        }
+        .map_err(anyhow::Error::from)
+    }
+
+    #[napi(ts_args_type = "key: string, value: boolean | number | string | Record<string, any> | null | undefined")]
+    pub fn insert_attribute(&mut self, key: String, value: JsUnknown) -> Result<()> {
+        let value = get_any_from_js_unknown(value)?;
+        self.element.insert_attribute(key, value).map_err(anyhow::Error::from)
+    }
+
+    #[napi]
+    pub fn remove_attribute(&mut self, key: String) {
+        self.element.remove_attribute(&key)
+    }
+
+    #[napi(ts_return_type = "YXmlElement | YXmlText | null")]
+    pub fn get(&self, env: Env, index: i64) -> Result<XmlChild> {
+        get_xml_child_from_value(env, self.element.get(index as u64))
+    }
+
+    #[napi(ts_args_type = "index: number, child: YXmlElement | YXmlText")]
+    pub fn insert(&mut self, index: i64, child: XmlChildRef) -> Result<()> {
+        self.element
+            .insert(index as u64, get_value_from_xml_child(child))
+            .map_err(anyhow::Error::from)
+    }
+
+    #[napi(ts_args_type = "child: YXmlElement | YXmlText")]
+    pub fn push(&mut self, child: XmlChildRef) -> Result<()> {
+        self.element
+            .push(get_value_from_xml_child(child))
+            .map_err(anyhow::Error::from)
+    }
+
+    #[napi]
+    pub fn remove(&mut self, index: i64, len: i64) -> Result<()> {
+        self.element
+            .remove(index as u64, len as u64)
+            .map_err(anyhow::Error::from)
+    }
+
+    #[allow(clippy::inherent_to_string)]
+    #[napi]
+    pub fn to_string(&self) -> String {
+        self.element.to_string()
+    }
+}
+
+#[napi]
+pub struct YXmlText {
+    pub(crate) text: XMLText,
+}
+
+#[napi]
+impl YXmlText {
+    #[allow(clippy::new_without_default)]
+    #[napi(constructor)]
+    pub fn new() -> Self {
+        unimplemented!()
+    }
+
+    pub(crate) fn inner_new(text: XMLText) -> Self {
+        Self { text }
+    }
+
+    #[napi(getter)]
+    pub fn length(&self) -> i64 {
+        self.text.len() as i64
+    }
+
+    #[napi(getter)]
+    pub fn is_empty(&self) -> bool {
+        self.text.is_empty()
+    }
+
+    #[napi]
+    pub fn insert(&mut self, index: i64, str: String) -> Result<()> {
+        self.text.insert(index as u64, str).map_err(anyhow::Error::from)
+    }
+
+    #[napi]
+    pub fn remove(&mut self, index: i64, len: i64) -> Result<()> {
+        self.text.remove(index as u64, len as u64).map_err(anyhow::Error::from)
+    }
+
+    #[napi(ts_args_type = "index: number, len: number, attrs: Record<string, any>")]
+    pub fn format(&mut self, index: i64, len: i64, attrs: JsObject) -> Result<()> {
+        let attrs = match get_any_from_js_object(attrs)? {
+            Any::Object(map) => map.into_iter().collect::<TextAttributes>(),
+            _ => TextAttributes::new(),
+        };
+
+        self.text
+            .format(index as u64, len as u64, attrs)
+            .map_err(anyhow::Error::from)
+    }
+
+    #[allow(clippy::inherent_to_string)]
+    #[napi]
+    pub fn to_string(&self) -> String {
+        self.text.to_string()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_xml_edit() {
+        let doc = Doc::new(None);
+        let mut fragment = doc.get_or_create_xml_fragment("prosemirror".into()).unwrap();
+        let mut paragraph = doc.create_xml_element("paragraph".into()).unwrap();
+        let mut text = doc.create_xml_text().unwrap();
+
+        fragment.push(XmlChildRef::A(&paragraph)).unwrap();
+        paragraph.push(XmlChildRef::B(&text)).unwrap();
+        text.insert(0, "hello".into()).unwrap();
+
+        assert_eq!(paragraph.tag(), "paragraph");
+        assert_eq!(fragment.length(), 1);
+        assert_eq!(fragment.to_string(), "<paragraph>hello</paragraph>");
+    }
+}
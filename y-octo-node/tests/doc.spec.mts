@@ -96,4 +96,21 @@ test("doc test", { concurrency: false }, async (t) => {
     equal(map.get("d"), "hello world");
     equal(text.toString(), "abc");
   });
+
+  await t.test("update callback should be removed after unsubscribe", async () => {
+    let updates = 0;
+    let subscription = doc.onUpdate(() => {
+      updates += 1;
+    });
+    let text = doc.getOrCreateText("text");
+
+    text.insert(0, "a");
+    await new Promise((resolve) => setTimeout(resolve, 10));
+    equal(updates, 1);
+
+    subscription.unsubscribe();
+    text.insert(1, "b");
+    await new Promise((resolve) => setTimeout(resolve, 10));
+    equal(updates, 1);
+  });
 });
@@ -0,0 +1,64 @@
+import assert, { equal } from "node:assert";
+import { test } from "node:test";
+
+import { Doc, YXmlElement } from "../index";
+import * as Y from "yjs";
+
+test("xml test", { concurrency: false }, async (t) => {
+  let client_id: number;
+  let doc: Doc;
+  t.beforeEach(async () => {
+    client_id = (Math.random() * 100000) | 0;
+    doc = new Doc(client_id);
+  });
+
+  t.afterEach(async () => {
+    client_id = -1;
+    // @ts-ignore - doc must not null in next range
+    doc = null;
+  });
+
+  await t.test("xml fragment should be created", () => {
+    let fragment = doc.getOrCreateXmlFragment("prosemirror");
+    equal(fragment.length, 0);
+    equal(fragment.toString(), "");
+  });
+
+  await t.test("xml element editing", () => {
+    let fragment = doc.getOrCreateXmlFragment("prosemirror");
+    let paragraph = doc.createXmlElement("paragraph");
+    let text = doc.createXmlText();
+
+    fragment.push(paragraph);
+    paragraph.insertAttribute("align", "left");
+    paragraph.push(text);
+    text.insert(0, "hello world");
+    text.format(0, 5, { bold: true });
+
+    equal(paragraph.tag, "paragraph");
+    equal(paragraph.getAttribute("align"), "left");
+    equal(fragment.toString(), '<paragraph align="left"><bold>hello</bold> world</paragraph>');
+
+    paragraph.removeAttribute("align");
+    equal(fragment.toString(), "<paragraph><bold>hello</bold> world</paragraph>");
+  });
+
+  await t.test("yjs xml fragment should be apply", () => {
+    let doc2 = new Y.Doc();
+    let fragment2 = doc2.getXmlFragment("prosemirror");
+    let paragraph2 = new Y.XmlElement("paragraph");
+    let text2 = new Y.XmlText("hello");
+    fragment2.insert(0, [paragraph2]);
+    paragraph2.setAttribute("align", "left");
+    paragraph2.insert(0, [text2]);
+
+    doc.applyUpdate(Buffer.from(Y.encodeStateAsUpdate(doc2)));
+
+    let fragment = doc.getOrCreateXmlFragment("prosemirror");
+    equal(fragment.length, 1);
+    let paragraph = fragment.get(0) as YXmlElement;
+    assert(paragraph);
+    equal(paragraph.tag, "paragraph");
+    equal(fragment.toString(), fragment2.toString());
+  });
+});
@@ -137,7 +137,7 @@ impl Any {
     }
 
     pub(crate) fn read_multiple<R: CrdtReader>(reader: &mut R) -> JwstCodecResult<Vec<Any>> {
-        let len = reader.read_var_u64()? as usize;
+        let len = reader.read_len()? as usize;
         let mut vec = Vec::with_capacity(len);
         for _ in 0..len {
             vec.push(Any::read(reader)?);
@@ -147,7 +147,7 @@ impl Any {
     }
 
     pub(crate) fn write_multiple<W: CrdtWriter>(writer: &mut W, any: &[Any]) -> JwstCodecResult {
-        writer.write_var_u64(any.len() as u64)?;
+        writer.write_len(any.len() as u64)?;
         for value in any {
             value.write(writer)?;
         }
@@ -297,6 +297,42 @@ impl TryFrom<Any> for bool {
     }
 }
 
+impl TryFrom<Any> for i64 {
+    type Error = JwstCodecError;
+
+    fn try_from(value: Any) -> Result<Self, Self::Error> {
+        let float = match value {
+            Any::Integer(i) => return Ok(i.into()),
+            Any::BigInt64(i) => return Ok(i),
+            // yjs encodes the integers out of the range of i32 as float64
+            Any::Float32(f) => f.0.into(),
+            Any::Float64(f) => f.0,
+            _ => return Err(JwstCodecError::UnexpectedType("Integer")),
+        };
+
+        // `i64::MAX as f64` is rounded up to 2^63
+        if float.fract() == 0.0 && float >= i64::MIN as f64 && float < i64::MAX as f64 {
+            Ok(float as i64)
+        } else {
+            Err(JwstCodecError::UnexpectedType("Integer"))
+        }
+    }
+}
+
+impl TryFrom<Any> for f64 {
+    type Error = JwstCodecError;
+
+    fn try_from(value: Any) -> Result<Self, Self::Error> {
+        match value {
+            Any::Integer(i) => Ok(i.into()),
+            Any::BigInt64(i) => Ok(i as f64),
+            Any::Float32(f) => Ok(f.0.into()),
+            Any::Float64(f) => Ok(f.0),
+            _ => Err(JwstCodecError::UnexpectedType("Number")),
+        }
+    }
+}
+
 impl FromIterator<Any> for Any {
     fn from_iter<I: IntoIterator<Item = Any>>(iter: I) -> Self {
         Self::Array(iter.into_iter().collect())
@@ -370,6 +406,25 @@ impl From<serde_json::Value> for Any {
     }
 }
 
+#[cfg(feature = "serde_json")]
+impl From<Any> for serde_json::Value {
+    fn from(value: Any) -> Self {
+        match value {
+            Any::Null | Any::Undefined => Self::Null,
+            Any::True => Self::Bool(true),
+            Any::False => Self::Bool(false),
+            Any::Float32(value) => serde_json::Number::from_f64(value.0 as f64).map_or(Self::Null, Self::Number),
+            Any::Float64(value) => serde_json::Number::from_f64(value.0).map_or(Self::Null, Self::Number),
+            Any::Integer(value) => Self::Number(value.into()),
+            Any::BigInt64(value) => Self::Number(value.into()),
+            Any::String(value) => Self::String(value),
+            Any::Array(values) => Self::Array(values.into_iter().map(|value| value.into()).collect()),
+            Any::Object(entries) => Self::Object(entries.into_iter().map(|(key, value)| (key, value.into())).collect()),
+            Any::Binary(buf) => Self::Array(buf.into_iter().map(|byte| byte.into()).collect()),
+        }
+    }
+}
+
 impl<'de> serde::Deserialize<'de> for Any {
     fn deserialize<D>(deserializer: D) -> Result<Self, D::Error>
     where
@@ -0,0 +1,636 @@
+use std::fmt::Display;
+
+use serde::{
+    Serialize,
+    de::{
+        self, DeserializeOwned, IntoDeserializer, Visitor,
+        value::{MapAccessDeserializer, MapDeserializer, SeqDeserializer},
+    },
+    ser,
+};
+
+use super::*;
+
+impl ser::Error for JwstCodecError {
+    fn custom<T: Display>(msg: T) -> Self {
+        JwstCodecError::Serde(msg.to_string())
+    }
+}
+
+impl de::Error for JwstCodecError {
+    fn custom<T: Display>(msg: T) -> Self {
+        JwstCodecError::Serde(msg.to_string())
+    }
+}
+
+/// Serialize the value into [Any], structs and maps are serialized into
+/// [Any::Object], and sequences into [Any::Array].
+///
+/// The result can be inserted with [Map::insert_prelim](crate::Map::insert_prelim)
+/// to make every level of it collaboratively editable.
+///
+/// ```
+/// use serde::{Deserialize, Serialize};
+/// use y_octo::{Doc, from_value, to_value};
+///
+/// #[derive(Debug, PartialEq, Serialize, Deserialize)]
+/// struct Config {
+///     name: String,
+///     tags: Vec<String>,
+/// }
+///
+/// let doc = Doc::default();
+/// let mut map = doc.get_or_create_map("map").unwrap();
+/// let config = Config {
+///     name: "workspace".into(),
+///     tags: vec!["a".into()],
+/// };
+/// map.insert_prelim("config".into(), to_value(&config).unwrap()).unwrap();
+///
+/// let saved = map.get("config").unwrap().to_map().unwrap();
+/// assert_eq!(from_value::<Config>(&saved).unwrap(), config);
+/// ```
+pub fn to_value<T: Serialize + ?Sized>(value: &T) -> JwstCodecResult<Any> {
+    value.serialize(AnySerializer)
+}
+
+/// Deserialize the value from [Any]
+pub fn from_any<T: DeserializeOwned>(any: Any) -> JwstCodecResult<T> {
+    T::deserialize(any)
+}
+
+/// Deserialize the value from the content of the [Map](crate::Map), nested
+/// types are converted by [Value::to_json](crate::Value::to_json) first.
+pub fn from_value<T: DeserializeOwned>(map: &Map) -> JwstCodecResult<T> {
+    from_any(Value::Map(map.clone()).to_json())
+}
+
+/// The [serde::Serializer] that serializes values into [Any]
+pub struct AnySerializer;
+
+impl ser::Serializer for AnySerializer {
+    type Ok = Any;
+    type Error = JwstCodecError;
+
+    type SerializeSeq = SerializeArray;
+    type SerializeTuple = SerializeArray;
+    type SerializeTupleStruct = SerializeArray;
+    type SerializeTupleVariant = SerializeVariant<SerializeArray>;
+    type SerializeMap = SerializeObject;
+    type SerializeStruct = SerializeObject;
+    type SerializeStructVariant = SerializeVariant<SerializeObject>;
+
+    fn serialize_bool(self, v: bool) -> JwstCodecResult<Any> {
+        Ok(v.into())
+    }
+
+    fn serialize_i8(self, v: i8) -> JwstCodecResult<Any> {
+        self.serialize_i32(v.into())
+    }
+
+    fn serialize_i16(self, v: i16) -> JwstCodecResult<Any> {
+        self.serialize_i32(v.into())
+    }
+
+    fn serialize_i32(self, v: i32) -> JwstCodecResult<Any> {
+        Ok(Any::Integer(v))
+    }
+
+    fn serialize_i64(self, v: i64) -> JwstCodecResult<Any> {
+        Ok(i32::try_from(v).map_or(Any::BigInt64(v), Any::Integer))
+    }
+
+    fn serialize_u8(self, v: u8) -> JwstCodecResult<Any> {
+        self.serialize_i32(v.into())
+    }
+
+    fn serialize_u16(self, v: u16) -> JwstCodecResult<Any> {
+        self.serialize_i32(v.into())
+    }
+
+    fn serialize_u32(self, v: u32) -> JwstCodecResult<Any> {
+        self.serialize_i64(v.into())
+    }
+
+    fn serialize_u64(self, v: u64) -> JwstCodecResult<Any> {
+        match i64::try_from(v) {
+            Ok(v) => self.serialize_i64(v),
+            Err(_) => self.serialize_f64(v as f64),
+        }
+    }
+
+    fn serialize_f32(self, v: f32) -> JwstCodecResult<Any> {
+        Ok(Any::Float32(v.into()))
+    }
+
+    fn serialize_f64(self, v: f64) -> JwstCodecResult<Any> {
+        Ok(Any::Float64(v.into()))
+    }
+
+    fn serialize_char(self, v: char) -> JwstCodecResult<Any> {
+        Ok(Any::String(v.to_string()))
+    }
+
+    fn serialize_str(self, v: &str) -> JwstCodecResult<Any> {
+        Ok(Any::String(v.to_owned()))
+    }
+
+    fn serialize_bytes(self, v: &[u8]) -> JwstCodecResult<Any> {
+        Ok(Any::Binary(v.to_vec()))
+    }
+
+    fn serialize_none(self) -> JwstCodecResult<Any> {
+        Ok(Any::Null)
+    }
+
+    fn serialize_some<T: Serialize + ?Sized>(self, value: &T) -> JwstCodecResult<Any> {
+        value.serialize(self)
+    }
+
+    fn serialize_unit(self) -> JwstCodecResult<Any> {
+        Ok(Any::Null)
+    }
+
+    fn serialize_unit_struct(self, _name: &'static str) -> JwstCodecResult<Any> {
+        self.serialize_unit()
+    }
+
+    fn serialize_unit_variant(self, _name: &'static str, _index: u32, variant: &'static str) -> JwstCodecResult<Any> {
+        self.serialize_str(variant)
+    }
+
+    fn serialize_newtype_struct<T: Serialize + ?Sized>(self, _name: &'static str, value: &T) -> JwstCodecResult<Any> {
+        value.serialize(self)
+    }
+
+    fn serialize_newtype_variant<T: Serialize + ?Sized>(
+        self,
+        _name: &'static str,
+        _index: u32,
+        variant: &'static str,
+        value: &T,
+    ) -> JwstCodecResult<Any> {
+        Ok(variant_object(variant, to_value(value)?))
+    }
+
+    fn serialize_seq(self, len: Option<usize>) -> JwstCodecResult<SerializeArray> {
+        Ok(SerializeArray(Vec::with_capacity(len.unwrap_or_default())))
+    }
+
+    fn serialize_tuple(self, len: usize) -> JwstCodecResult<SerializeArray> {
+        self.serialize_seq(Some(len))
+    }
+
+    fn serialize_tuple_struct(self, _name: &'static str, len: usize) -> JwstCodecResult<SerializeArray> {
+        self.serialize_seq(Some(len))
+    }
+
+    fn serialize_tuple_variant(
+        self,
+        _name: &'static str,
+        _index: u32,
+        variant: &'static str,
+        len: usize,
+    ) -> JwstCodecResult<SerializeVariant<SerializeArray>> {
+        Ok(SerializeVariant {
+            variant,
+            inner: self.serialize_seq(Some(len))?,
+        })
+    }
+
+    fn serialize_map(self, _len: Option<usize>) -> JwstCodecResult<SerializeObject> {
+        Ok(SerializeObject {
+            entries: HashMap::new(),
+            key: None,
+        })
+    }
+
+    fn serialize_struct(self, _name: &'static str, len: usize) -> JwstCodecResult<SerializeObject> {
+        self.serialize_map(Some(len))
+    }
+
+    fn serialize_struct_variant(
+        self,
+        _name: &'static str,
+        _index: u32,
+        variant: &'static str,
+        len: usize,
+    ) -> JwstCodecResult<SerializeVariant<SerializeObject>> {
+        Ok(SerializeVariant {
+            variant,
+            inner: self.serialize_map(Some(len))?,
+        })
+    }
+}
+
+pub struct SerializeArray(Vec<Any>);
+
+impl ser::SerializeSeq for SerializeArray {
+    type Ok = Any;
+    type Error = JwstCodecError;
+
+    fn serialize_element<T: Serialize + ?Sized>(&mut self, value: &T) -> JwstCodecResult {
+        self.0.push(to_value(value)?);
+        Ok(())
+    }
+
+    fn end(self) -> JwstCodecResult<Any> {
+        Ok(Any::Array(self.0))
+    }
+}
+
+impl ser::SerializeTuple for SerializeArray {
+    type Ok = Any;
+    type Error = JwstCodecError;
+
+    fn serialize_element<T: Serialize + ?Sized>(&mut self, value: &T) -> JwstCodecResult {
+        ser::SerializeSeq::serialize_element(self, value)
+    }
+
+    fn end(self) -> JwstCodecResult<Any> {
+        ser::SerializeSeq::end(self)
+    }
+}
+
+impl ser::SerializeTupleStruct for SerializeArray {
+    type Ok = Any;
+    type Error = JwstCodecError;
+
+    fn serialize_field<T: Serialize + ?Sized>(&mut self, value: &T) -> JwstCodecResult {
+        ser::SerializeSeq::serialize_element(self, value)
+    }
+
+    fn end(self) -> JwstCodecResult<Any> {
+        ser::SerializeSeq::end(self)
+    }
+}
+
+pub struct SerializeObject {
+    entries: HashMap<String, Any>,
+    key: Option<String>,
+}
+
+impl ser::SerializeMap for SerializeObject {
+    type Ok = Any;
+    type Error = JwstCodecError;
+
+    fn serialize_key<T: Serialize + ?Sized>(&mut self, key: &T) -> JwstCodecResult {
+        // keys of objects are always strings, numbers are converted like json
+        let key = match to_value(key)? {
+            Any::String(key) => key,
+            Any::Integer(key) => key.to_string(),
+            Any::BigInt64(key) => key.to_string(),
+            _ => return Err(JwstCodecError::Serde("key must be a string".into())),
+        };
+        self.key = Some(key);
+        Ok(())
+    }
+
+    fn serialize_value<T: Serialize + ?Sized>(&mut self, value: &T) -> JwstCodecResult {
+        let key = self
+            .key
+            .take()
+            .ok_or_else(|| JwstCodecError::Serde("value is serialized before key".into()))?;
+        self.entries.insert(key, to_value(value)?);
+        Ok(())
+    }
+
+    fn end(self) -> JwstCodecResult<Any> {
+        Ok(Any::Object(self.entries))
+    }
+}
+
+impl ser::SerializeStruct for SerializeObject {
+    type Ok = Any;
+    type Error = JwstCodecError;
+
+    fn serialize_field<T: Serialize + ?Sized>(&mut self, key: &'static str, value: &T) -> JwstCodecResult {
+        self.entries.insert(key.to_owned(), to_value(value)?);
+        Ok(())
+    }
+
+    fn end(self) -> JwstCodecResult<Any> {
+        ser::SerializeMap::end(self)
+    }
+}
+
+/// Enum variants with content are serialized as `{ variant: content }`
+pub struct SerializeVariant<T> {
+    variant: &'static str,
+    inner: T,
+}
+
+fn variant_object(variant: &str, content: Any) -> Any {
+    Any::Object(HashMap::from_iter([(variant.to_owned(), content)]))
+}
+
+impl ser::SerializeTupleVariant for SerializeVariant<SerializeArray> {
+    type Ok = Any;
+    type Error = JwstCodecError;
+
+    fn serialize_field<T: Serialize + ?Sized>(&mut self, value: &T) -> JwstCodecResult {
+        ser::SerializeSeq::serialize_element(&mut self.inner, value)
+    }
+
+    fn end(self) -> JwstCodecResult<Any> {
+        Ok(variant_object(self.variant, ser::SerializeSeq::end(self.inner)?))
+    }
+}
+
+impl ser::SerializeStructVariant for SerializeVariant<SerializeObject> {
+    type Ok = Any;
+    type Error = JwstCodecError;
+
+    fn serialize_field<T: Serialize + ?Sized>(&mut self, key: &'static str, value: &T) -> JwstCodecResult {
+        ser::SerializeStruct::serialize_field(&mut self.inner, key, value)
+    }
+
+    fn end(self) -> JwstCodecResult<Any> {
+        Ok(variant_object(self.variant, ser::SerializeMap::end(self.inner)?))
+    }
+}
+
+// numbers from yjs are float64 if they're out of the range of i32, accept
+// them if they're actually integers in the range of the type
+macro_rules! deserialize_integer {
+    ($($method:ident => $visit:ident($ty:ty),)*) => {
+        $(
+            fn $method<V: Visitor<'de>>(self, visitor: V) -> JwstCodecResult<V::Value> {
+                let float = match self {
+                    Any::Float64(v) if v.fract() == 0.0 => v.0,
+                    Any::Float32(v) if v.fract() == 0.0 => v.0 as f64,
+                    any => return any.deserialize_any(visitor),
+                };
+                // `MAX as f64` is rounded up to the next power of two
+                if float < <$ty>::MIN as f64 || float >= <$ty>::MAX as f64 {
+                    return Err(JwstCodecError::Serde(format!(
+                        "{float} is out of the range of {}",
+                        stringify!($ty)
+                    )));
+                }
+                visitor.$visit(float as $ty)
+            }
+        )*
+    };
+}
+
+impl<'de> de::Deserializer<'de> for Any {
+    type Error = JwstCodecError;
+
+    fn deserialize_any<V: Visitor<'de>>(self, visitor: V) -> JwstCodecResult<V::Value> {
+        match self {
+            Any::Undefined | Any::Null => visitor.visit_unit(),
+            Any::True => visitor.visit_bool(true),
+            Any::False => visitor.visit_bool(false),
+            Any::Integer(v) => visitor.visit_i32(v),
+            Any::BigInt64(v) => visitor.visit_i64(v),
+            Any::Float32(v) => visitor.visit_f32(v.0),
+            Any::Float64(v) => visitor.visit_f64(v.0),
+            Any::String(v) => visitor.visit_string(v),
+            Any::Binary(v) => visitor.visit_byte_buf(v),
+            Any::Array(values) => visitor.visit_seq(SeqDeserializer::new(values.into_iter())),
+            Any::Object(entries) => visitor.visit_map(MapDeserializer::new(
+                entries.into_iter().map(|(key, value)| (MapKeyDeserializer(key), value)),
+            )),
+        }
+    }
+
+    deserialize_integer! {
+        deserialize_i8 => visit_i64(i64),
+        deserialize_i16 => visit_i64(i64),
+        deserialize_i32 => visit_i64(i64),
+        deserialize_i64 => visit_i64(i64),
+        deserialize_u8 => visit_u64(u64),
+        deserialize_u16 => visit_u64(u64),
+        deserialize_u32 => visit_u64(u64),
+        deserialize_u64 => visit_u64(u64),
+    }
+
+    fn deserialize_option<V: Visitor<'de>>(self, visitor: V) -> JwstCodecResult<V::Value> {
+        match self {
+            Any::Undefined | Any::Null => visitor.visit_none(),
+            any => visitor.visit_some(any),
+        }
+    }
+
+    fn deserialize_newtype_struct<V: Visitor<'de>>(self, _name: &'static str, visitor: V) -> JwstCodecResult<V::Value> {
+        visitor.visit_newtype_struct(self)
+    }
+
+    fn deserialize_enum<V: Visitor<'de>>(
+        self,
+        _name: &'static str,
+        _variants: &'static [&'static str],
+        visitor: V,
+    ) -> JwstCodecResult<V::Value> {
+        match self {
+            Any::String(variant) => visitor.visit_enum(variant.into_deserializer()),
+            Any::Object(entries) if entries.len() == 1 => visitor.visit_enum(MapAccessDeserializer::new(
+                MapDeserializer::<_, JwstCodecError>::new(entries.into_iter()),
+            )),
+            _ => Err(JwstCodecError::Serde(
+                "enum must be a string or an object with one entry".into(),
+            )),
+        }
+    }
+
+    serde::forward_to_deserialize_any! {
+        bool i128 u128 f32 f64 char str string bytes byte_buf unit unit_struct seq tuple tuple_struct map struct
+        identifier ignored_any
+    }
+}
+
+// keys of objects are always strings, numbers are parsed from them like json
+struct MapKeyDeserializer(String);
+
+macro_rules! deserialize_key {
+    ($($method:ident => $visit:ident($ty:ty),)*) => {
+        $(
+            fn $method<V: Visitor<'de>>(self, visitor: V) -> JwstCodecResult<V::Value> {
+                match self.0.parse::<$ty>() {
+                    Ok(v) => visitor.$visit(v),
+                    Err(_) => visitor.visit_string(self.0),
+                }
+            }
+        )*
+    };
+}
+
+impl<'de> de::Deserializer<'de> for MapKeyDeserializer {
+    type Error = JwstCodecError;
+
+    fn deserialize_any<V: Visitor<'de>>(self, visitor: V) -> JwstCodecResult<V::Value> {
+        visitor.visit_string(self.0)
+    }
+
+    deserialize_key! {
+        deserialize_i8 => visit_i8(i8),
+        deserialize_i16 => visit_i16(i16),
+        deserialize_i32 => visit_i32(i32),
+        deserialize_i64 => visit_i64(i64),
+        deserialize_u8 => visit_u8(u8),
+        deserialize_u16 => visit_u16(u16),
+        deserialize_u32 => visit_u32(u32),
+        deserialize_u64 => visit_u64(u64),
+    }
+
+    fn deserialize_newtype_struct<V: Visitor<'de>>(self, _name: &'static str, visitor: V) -> JwstCodecResult<V::Value> {
+        visitor.visit_newtype_struct(self)
+    }
+
+    fn deserialize_enum<V: Visitor<'de>>(
+        self,
+        _name: &'static str,
+        _variants: &'static [&'static str],
+        visitor: V,
+    ) -> JwstCodecResult<V::Value> {
+        visitor.visit_enum(self.0.into_deserializer())
+    }
+
+    serde::forward_to_deserialize_any! {
+        bool i128 u128 f32 f64 char str string bytes byte_buf option unit unit_struct seq tuple tuple_struct map
+        struct identifier ignored_any
+    }
+}
+
+impl IntoDeserializer<'_, JwstCodecError> for MapKeyDeserializer {
+    type Deserializer = Self;
+
+    fn into_deserializer(self) -> Self {
+        self
+    }
+}
+
+impl IntoDeserializer<'_, JwstCodecError> for Any {
+    type Deserializer = Self;
+
+    fn into_deserializer(self) -> Self {
+        self
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use serde::Deserialize;
+
+    use super::*;
+    use crate::{Doc, loom_model};
+
+    #[derive(Debug, PartialEq, Serialize, Deserialize)]
+    enum Kind {
+        Page,
+        Link(String),
+        Size(u32, u32),
+        Embed { url: String },
+    }
+
+    #[derive(Debug, PartialEq, Serialize, Deserialize)]
+    struct Block {
+        id: u64,
+        kind: Vec<Kind>,
+        title: Option<String>,
+        props: HashMap<u32, bool>,
+        created: i64,
+    }
+
+    fn block() -> Block {
+        Block {
+            id: 1,
+            kind: vec![
+                Kind::Page,
+                Kind::Link("doc".into()),
+                Kind::Size(1, 2),
+                Kind::Embed { url: "url".into() },
+            ],
+            title: None,
+            props: HashMap::from_iter([(1, true)]),
+            created: 1_700_000_000_000,
+        }
+    }
+
+    #[test]
+    fn test_any_serde() {
+        let any = to_value(&block()).unwrap();
+        let Any::Object(entries) = &any else {
+            panic!("expect an object");
+        };
+        assert_eq!(entries.get("id"), Some(&Any::Integer(1)));
+        assert_eq!(entries.get("title"), Some(&Any::Null));
+        assert_eq!(entries.get("created"), Some(&Any::BigInt64(1_700_000_000_000)));
+        assert_eq!(
+            entries.get("kind"),
+            Some(&Any::Array(vec![
+                Any::String("Page".into()),
+                Any::Object(HashMap::from_iter([("Link".into(), Any::String("doc".into()))])),
+                Any::Object(HashMap::from_iter([(
+                    "Size".into(),
+                    Any::Array(vec![Any::Integer(1), Any::Integer(2)])
+                )])),
+                Any::Object(HashMap::from_iter([(
+                    "Embed".into(),
+                    Any::Object(HashMap::from_iter([("url".into(), Any::String("url".into()))]))
+                )])),
+            ]))
+        );
+        assert_eq!(from_any::<Block>(any).unwrap(), block());
+
+        // numbers out of the range of i32 are float64 in yjs
+        assert_eq!(
+            from_any::<i64>(Any::Float64(1_700_000_000_000.0.into())).unwrap(),
+            1_700_000_000_000
+        );
+        assert!(from_any::<i64>(Any::Float64(1.5.into())).is_err());
+        // the numbers out of the range are not saturated
+        assert!(matches!(
+            from_any::<u64>(Any::Float64((-1.0).into())),
+            Err(JwstCodecError::Serde(_))
+        ));
+        assert!(matches!(
+            from_any::<u32>(Any::Float32((-1.0).into())),
+            Err(JwstCodecError::Serde(_))
+        ));
+        assert!(matches!(
+            from_any::<i64>(Any::Float64(1e20.into())),
+            Err(JwstCodecError::Serde(_))
+        ));
+        assert!(matches!(
+            from_any::<i64>(Any::Float64(f64::INFINITY.into())),
+            Err(JwstCodecError::Serde(_))
+        ));
+        assert!(matches!(
+            from_any::<u8>(Any::Float64(256.0.into())),
+            Err(JwstCodecError::Serde(_))
+        ));
+        assert_eq!(from_any::<i8>(Any::Float64((-128.0).into())).unwrap(), -128);
+        assert!(matches!(
+            from_any::<u32>(Any::String("1".into())),
+            Err(JwstCodecError::Serde(_))
+        ));
+    }
+
+    #[test]
+    fn test_serde_with_map() {
+        loom_model!({
+            let doc = Doc::default();
+            let mut map = doc.get_or_create_map("blocks").unwrap();
+            map.insert_prelim("block".into(), to_value(&block()).unwrap()).unwrap();
+
+            let mut saved = map.get("block").unwrap().to_map().unwrap();
+            assert_eq!(from_value::<Block>(&saved).unwrap(), block());
+
+            saved.insert("title".into(), "title").unwrap();
+            saved.remove("props");
+            #[derive(Debug, PartialEq, Deserialize)]
+            struct Title {
+                title: Option<String>,
+            }
+            assert_eq!(
+                from_value::<Title>(&saved).unwrap(),
+                Title {
+                    title: Some("title".into())
+                }
+            );
+            assert!(from_value::<Block>(&saved).is_err());
+        });
+    }
+}
@@ -44,6 +44,16 @@ impl<'b> RawDecoder<'b> {
         }
     }
 
+    pub(super) fn read_var_buffer_ref(&mut self) -> JwstCodecResult<&'b [u8]> {
+        let input: &'b [u8] = self.buffer.get_ref();
+        let rest_pos = self.buffer.position().min(input.len() as u64) as usize;
+
+        let (tail, buffer) = read_var_buffer(&input[rest_pos..]).map_err(|e| e.map_input(|u| u.len()))?;
+
+        self.buffer.set_position((input.len() - tail.len()) as u64);
+        Ok(buffer)
+    }
+
     pub fn drain(self) -> &'b [u8] {
         let pos = self.buffer.position() as usize;
         let buf = self.buffer.into_inner();
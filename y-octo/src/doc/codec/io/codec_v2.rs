@@ -0,0 +1,715 @@
+use super::*;
+
+// lib0 signed var int, the sign bit is kept even for zero because the rle
+// encoders use a negative zero as a flag
+fn read_signed_var_int(decoder: &mut RawDecoder) -> JwstCodecResult<(bool, u64)> {
+    let mut byte = decoder.read_u8()?;
+    let is_negative = byte & 0b0100_0000 != 0;
+    let mut num = (byte & 0b0011_1111) as u64;
+    let mut shift = 6;
+
+    while byte & 0b1000_0000 != 0 {
+        byte = decoder.read_u8()?;
+        if shift >= u64::BITS {
+            return Err(JwstCodecError::IncompleteDocument("var int overflow".into()));
+        }
+        num |= ((byte & 0b0111_1111) as u64) << shift;
+        shift += 7;
+    }
+
+    Ok((is_negative, num))
+}
+
+fn write_signed_var_int(encoder: &mut RawEncoder, is_negative: bool, mut num: u64) -> JwstCodecResult {
+    encoder.write_u8(
+        // bit or 0b1000_0000 if has more bits
+        if num > 0b0011_1111 { 0b1000_0000 } else { 0 }
+            // bit or 0b0100_0000 if negative
+            | if is_negative { 0b0100_0000 } else { 0 }
+            // store last 6 bits
+            | num as u8 & 0b0011_1111,
+    )?;
+    num >>= 6;
+    while num > 0 {
+        encoder.write_u8(if num > 0b0111_1111 { 0b1000_0000 } else { 0 } | num as u8 & 0b0111_1111)?;
+        num >>= 7;
+    }
+
+    Ok(())
+}
+
+/// Run length encoding of u8 values, the counter of the last run is omitted.
+#[derive(Default)]
+struct RleEncoder {
+    encoder: RawEncoder,
+    state: Option<u8>,
+    count: u64,
+}
+
+impl RleEncoder {
+    fn write(&mut self, value: u8) -> JwstCodecResult {
+        if self.state == Some(value) {
+            self.count += 1;
+        } else {
+            if self.count > 0 {
+                // count is always > 0 here, so we can decrement it by one
+                self.encoder.write_var_u64(self.count - 1)?;
+            }
+            self.count = 1;
+            self.encoder.write_u8(value)?;
+            self.state = Some(value);
+        }
+
+        Ok(())
+    }
+
+    fn into_inner(self) -> Vec<u8> {
+        self.encoder.into_inner()
+    }
+}
+
+struct RleDecoder<'b> {
+    decoder: RawDecoder<'b>,
+    state: u8,
+    count: u64,
+}
+
+impl<'b> RleDecoder<'b> {
+    fn new(buffer: &'b [u8]) -> Self {
+        Self {
+            decoder: RawDecoder::new(buffer),
+            state: 0,
+            count: 0,
+        }
+    }
+
+    fn read(&mut self) -> JwstCodecResult<u8> {
+        if self.count == 0 {
+            self.state = self.decoder.read_u8()?;
+            self.count = if self.decoder.is_empty() {
+                // the last run has no counter, repeat the value forever
+                u64::MAX
+            } else {
+                self.decoder.read_var_u64()?.saturating_add(1)
+            };
+        }
+        self.count -= 1;
+
+        Ok(self.state)
+    }
+}
+
+/// Run length encoding of unsigned integers, a run of single value is written
+/// as is and longer runs are written as negative value followed by the counter.
+#[derive(Default)]
+struct UintOptRleEncoder {
+    encoder: RawEncoder,
+    state: u64,
+    count: u64,
+}
+
+impl UintOptRleEncoder {
+    fn write(&mut self, value: u64) -> JwstCodecResult {
+        if self.state == value {
+            self.count += 1;
+        } else {
+            self.flush()?;
+            self.count = 1;
+            self.state = value;
+        }
+
+        Ok(())
+    }
+
+    fn flush(&mut self) -> JwstCodecResult {
+        if self.count > 0 {
+            write_signed_var_int(&mut self.encoder, self.count > 1, self.state)?;
+            if self.count > 1 {
+                // count is always > 1 here, so we can decrement it by two
+                self.encoder.write_var_u64(self.count - 2)?;
+            }
+        }
+
+        Ok(())
+    }
+
+    fn into_inner(mut self) -> JwstCodecResult<Vec<u8>> {
+        self.flush()?;
+        Ok(self.encoder.into_inner())
+    }
+}
+
+struct UintOptRleDecoder<'b> {
+    decoder: RawDecoder<'b>,
+    state: u64,
+    count: u64,
+}
+
+impl<'b> UintOptRleDecoder<'b> {
+    fn new(buffer: &'b [u8]) -> Self {
+        Self {
+            decoder: RawDecoder::new(buffer),
+            state: 0,
+            count: 0,
+        }
+    }
+
+    fn read(&mut self) -> JwstCodecResult<u64> {
+        if self.count == 0 {
+            let (is_negative, state) = read_signed_var_int(&mut self.decoder)?;
+            self.state = state;
+            self.count = if is_negative {
+                self.decoder.read_var_u64()?.saturating_add(2)
+            } else {
+                1
+            };
+        }
+        self.count -= 1;
+
+        Ok(self.state)
+    }
+}
+
+/// Run length encoding of the differences between integers, the lowest bit of
+/// the diff marks whether a counter follows.
+#[derive(Default)]
+struct IntDiffOptRleEncoder {
+    encoder: RawEncoder,
+    state: i64,
+    diff: i64,
+    count: u64,
+}
+
+impl IntDiffOptRleEncoder {
+    fn write(&mut self, value: u64) -> JwstCodecResult {
+        let value = value as i64;
+        if self.diff == value - self.state {
+            self.state = value;
+            self.count += 1;
+        } else {
+            self.flush()?;
+            self.count = 1;
+            self.diff = value - self.state;
+            self.state = value;
+        }
+
+        Ok(())
+    }
+
+    fn flush(&mut self) -> JwstCodecResult {
+        if self.count > 0 {
+            let encoded_diff = self.diff * 2 + if self.count == 1 { 0 } else { 1 };
+            write_signed_var_int(&mut self.encoder, encoded_diff < 0, encoded_diff.unsigned_abs())?;
+            if self.count > 1 {
+                // count is always > 1 here, so we can decrement it by two
+                self.encoder.write_var_u64(self.count - 2)?;
+            }
+        }
+
+        Ok(())
+    }
+
+    fn into_inner(mut self) -> JwstCodecResult<Vec<u8>> {
+        self.flush()?;
+        Ok(self.encoder.into_inner())
+    }
+}
+
+struct IntDiffOptRleDecoder<'b> {
+    decoder: RawDecoder<'b>,
+    state: i64,
+    diff: i64,
+    count: u64,
+}
+
+impl<'b> IntDiffOptRleDecoder<'b> {
+    fn new(buffer: &'b [u8]) -> Self {
+        Self {
+            decoder: RawDecoder::new(buffer),
+            state: 0,
+            diff: 0,
+            count: 0,
+        }
+    }
+
+    fn read(&mut self) -> JwstCodecResult<u64> {
+        if self.count == 0 {
+            let (is_negative, diff) = read_signed_var_int(&mut self.decoder)?;
+            let diff = if is_negative { -(diff as i64) } else { diff as i64 };
+            self.diff = diff >> 1;
+            self.count = if diff & 1 == 1 {
+                self.decoder.read_var_u64()?.saturating_add(2)
+            } else {
+                1
+            };
+        }
+        self.state = self.state.wrapping_add(self.diff);
+        self.count -= 1;
+
+        Ok(self.state as u64)
+    }
+}
+
+/// All strings are concatenated into one buffer, the lengths of the strings
+/// are written separately in utf-16 code units.
+#[derive(Default)]
+struct StringEncoder {
+    buffer: String,
+    lens: UintOptRleEncoder,
+}
+
+impl StringEncoder {
+    fn write(&mut self, s: &str) -> JwstCodecResult {
+        self.buffer.push_str(s);
+        self.lens.write(s.encode_utf16().count() as u64)
+    }
+
+    fn into_inner(self) -> JwstCodecResult<Vec<u8>> {
+        let mut encoder = RawEncoder::default();
+        encoder.write_var_string(&self.buffer)?;
+
+        let mut buffer = encoder.into_inner();
+        buffer.extend(self.lens.into_inner()?);
+        Ok(buffer)
+    }
+}
+
+struct StringDecoder<'b> {
+    buffer: String,
+    pos: usize,
+    lens: UintOptRleDecoder<'b>,
+}
+
+impl<'b> StringDecoder<'b> {
+    fn new(buffer: &'b [u8]) -> JwstCodecResult<Self> {
+        let mut decoder = RawDecoder::new(buffer);
+        let buffer = decoder.read_var_string()?;
+
+        Ok(Self {
+            buffer,
+            pos: 0,
+            lens: UintOptRleDecoder::new(decoder.drain()),
+        })
+    }
+
+    fn read(&mut self) -> JwstCodecResult<String> {
+        let len = self.lens.read()?;
+        let rest = &self.buffer[self.pos..];
+
+        let mut utf16_len = 0;
+        let mut end = rest.len();
+        for (idx, c) in rest.char_indices() {
+            if utf16_len >= len {
+                end = idx;
+                break;
+            }
+            utf16_len += c.len_utf16() as u64;
+        }
+
+        if utf16_len < len {
+            return Err(JwstCodecError::IncompleteDocument(format!(
+                "string out of bound: expect {len}, actually {utf16_len}"
+            )));
+        }
+
+        let string = rest[..end].to_string();
+        self.pos += end;
+
+        Ok(string)
+    }
+}
+
+// compatible with ydoc v2
+pub struct RawDecoderV2<'b> {
+    rest: RawDecoder<'b>,
+    keys: Vec<String>,
+    key_clock: IntDiffOptRleDecoder<'b>,
+    client: UintOptRleDecoder<'b>,
+    left_clock: IntDiffOptRleDecoder<'b>,
+    right_clock: IntDiffOptRleDecoder<'b>,
+    info: RleDecoder<'b>,
+    string: StringDecoder<'b>,
+    parent_info: RleDecoder<'b>,
+    type_ref: UintOptRleDecoder<'b>,
+    len: UintOptRleDecoder<'b>,
+    ds_cur_val: u64,
+}
+
+impl<'b> RawDecoderV2<'b> {
+    pub fn new(buffer: &'b [u8]) -> JwstCodecResult<Self> {
+        let mut rest = RawDecoder::new(buffer);
+        // feature flag, currently unused
+        rest.read_var_u64()?;
+
+        Ok(Self {
+            keys: Vec::new(),
+            key_clock: IntDiffOptRleDecoder::new(rest.read_var_buffer_ref()?),
+            client: UintOptRleDecoder::new(rest.read_var_buffer_ref()?),
+            left_clock: IntDiffOptRleDecoder::new(rest.read_var_buffer_ref()?),
+            right_clock: IntDiffOptRleDecoder::new(rest.read_var_buffer_ref()?),
+            info: RleDecoder::new(rest.read_var_buffer_ref()?),
+            string: StringDecoder::new(rest.read_var_buffer_ref()?)?,
+            parent_info: RleDecoder::new(rest.read_var_buffer_ref()?),
+            type_ref: UintOptRleDecoder::new(rest.read_var_buffer_ref()?),
+            len: UintOptRleDecoder::new(rest.read_var_buffer_ref()?),
+            rest,
+            ds_cur_val: 0,
+        })
+    }
+}
+
+impl CrdtReader for RawDecoderV2<'_> {
+    fn is_empty(&self) -> bool {
+        self.rest.is_empty()
+    }
+
+    fn len(&self) -> u64 {
+        self.rest.len()
+    }
+
+    fn read_var_u64(&mut self) -> JwstCodecResult<u64> {
+        self.rest.read_var_u64()
+    }
+
+    fn read_var_i32(&mut self) -> JwstCodecResult<i32> {
+        self.rest.read_var_i32()
+    }
+
+    fn read_var_string(&mut self) -> JwstCodecResult<String> {
+        self.rest.read_var_string()
+    }
+
+    fn read_var_buffer(&mut self) -> JwstCodecResult<Vec<u8>> {
+        self.rest.read_var_buffer()
+    }
+
+    fn read_u8(&mut self) -> JwstCodecResult<u8> {
+        self.rest.read_u8()
+    }
+
+    fn read_f32_be(&mut self) -> JwstCodecResult<f32> {
+        self.rest.read_f32_be()
+    }
+
+    fn read_f64_be(&mut self) -> JwstCodecResult<f64> {
+        self.rest.read_f64_be()
+    }
+
+    fn read_i64_be(&mut self) -> JwstCodecResult<i64> {
+        self.rest.read_i64_be()
+    }
+
+    #[inline(always)]
+    fn read_info(&mut self) -> JwstCodecResult<u8> {
+        self.info.read()
+    }
+
+    #[inline(always)]
+    fn read_item_id(&mut self) -> JwstCodecResult<Id> {
+        self.read_left_id()
+    }
+
+    fn read_left_id(&mut self) -> JwstCodecResult<Id> {
+        Ok(Id::new(self.client.read()?, self.left_clock.read()?))
+    }
+
+    fn read_right_id(&mut self) -> JwstCodecResult<Id> {
+        Ok(Id::new(self.client.read()?, self.right_clock.read()?))
+    }
+
+    fn read_client(&mut self) -> JwstCodecResult<Client> {
+        self.client.read()
+    }
+
+    fn read_parent_info(&mut self) -> JwstCodecResult<bool> {
+        Ok(self.parent_info.read()? == 1)
+    }
+
+    fn read_string(&mut self) -> JwstCodecResult<String> {
+        self.string.read()
+    }
+
+    fn read_type_ref(&mut self) -> JwstCodecResult<u64> {
+        self.type_ref.read()
+    }
+
+    fn read_len(&mut self) -> JwstCodecResult<u64> {
+        self.len.read()
+    }
+
+    fn read_key(&mut self) -> JwstCodecResult<String> {
+        let key_clock = self.key_clock.read()? as usize;
+        if let Some(key) = self.keys.get(key_clock) {
+            Ok(key.clone())
+        } else {
+            let key = self.string.read()?;
+            self.keys.push(key.clone());
+            Ok(key)
+        }
+    }
+
+    fn read_json(&mut self) -> JwstCodecResult<Any> {
+        Any::read(self)
+    }
+
+    fn reset_ds_cur_val(&mut self) {
+        self.ds_cur_val = 0;
+    }
+
+    fn read_ds_clock(&mut self) -> JwstCodecResult<Clock> {
+        self.ds_cur_val = self.ds_cur_val.saturating_add(self.rest.read_var_u64()?);
+        Ok(self.ds_cur_val)
+    }
+
+    fn read_ds_len(&mut self) -> JwstCodecResult<Clock> {
+        let len = self.rest.read_var_u64()?.saturating_add(1);
+        self.ds_cur_val = self.ds_cur_val.saturating_add(len);
+        Ok(len)
+    }
+}
+
+// compatible with ydoc v2
+#[derive(Default)]
+pub struct RawEncoderV2 {
+    rest: RawEncoder,
+    key_clock: u64,
+    key_clock_encoder: IntDiffOptRleEncoder,
+    client: UintOptRleEncoder,
+    left_clock: IntDiffOptRleEncoder,
+    right_clock: IntDiffOptRleEncoder,
+    info: RleEncoder,
+    string: StringEncoder,
+    parent_info: RleEncoder,
+    type_ref: UintOptRleEncoder,
+    len: UintOptRleEncoder,
+    ds_cur_val: u64,
+}
+
+impl RawEncoderV2 {
+    pub fn into_inner(self) -> JwstCodecResult<Vec<u8>> {
+        let mut encoder = RawEncoder::default();
+        // feature flag, currently unused
+        encoder.write_var_u64(0)?;
+        encoder.write_var_buffer(&self.key_clock_encoder.into_inner()?)?;
+        encoder.write_var_buffer(&self.client.into_inner()?)?;
+        encoder.write_var_buffer(&self.left_clock.into_inner()?)?;
+        encoder.write_var_buffer(&self.right_clock.into_inner()?)?;
+        encoder.write_var_buffer(&self.info.into_inner())?;
+        encoder.write_var_buffer(&self.string.into_inner()?)?;
+        encoder.write_var_buffer(&self.parent_info.into_inner())?;
+        encoder.write_var_buffer(&self.type_ref.into_inner()?)?;
+        encoder.write_var_buffer(&self.len.into_inner()?)?;
+
+        // rest buffer is appended without length
+        let mut buffer = encoder.into_inner();
+        buffer.extend(self.rest.into_inner());
+        Ok(buffer)
+    }
+}
+
+impl CrdtWriter for RawEncoderV2 {
+    fn write_var_u64(&mut self, num: u64) -> JwstCodecResult {
+        self.rest.write_var_u64(num)
+    }
+    fn write_var_i32(&mut self, num: i32) -> JwstCodecResult {
+        self.rest.write_var_i32(num)
+    }
+    fn write_var_string<S: AsRef<str>>(&mut self, s: S) -> JwstCodecResult {
+        self.rest.write_var_string(s)
+    }
+    fn write_var_buffer(&mut self, buf: &[u8]) -> JwstCodecResult {
+        self.rest.write_var_buffer(buf)
+    }
+    fn write_u8(&mut self, num: u8) -> JwstCodecResult {
+        self.rest.write_u8(num)
+    }
+    fn write_f32_be(&mut self, num: f32) -> JwstCodecResult {
+        self.rest.write_f32_be(num)
+    }
+    fn write_f64_be(&mut self, num: f64) -> JwstCodecResult {
+        self.rest.write_f64_be(num)
+    }
+    fn write_i64_be(&mut self, num: i64) -> JwstCodecResult {
+        self.rest.write_i64_be(num)
+    }
+
+    #[inline(always)]
+    fn write_info(&mut self, num: u8) -> JwstCodecResult {
+        self.info.write(num)
+    }
+
+    #[inline(always)]
+    fn write_item_id(&mut self, id: &Id) -> JwstCodecResult {
+        self.write_left_id(id)
+    }
+
+    fn write_left_id(&mut self, id: &Id) -> JwstCodecResult {
+        self.client.write(id.client)?;
+        self.left_clock.write(id.clock)
+    }
+
+    fn write_right_id(&mut self, id: &Id) -> JwstCodecResult {
+        self.client.write(id.client)?;
+        self.right_clock.write(id.clock)
+    }
+
+    fn write_client(&mut self, client: Client) -> JwstCodecResult {
+        self.client.write(client)
+    }
+
+    fn write_parent_info(&mut self, is_y_key: bool) -> JwstCodecResult {
+        self.parent_info.write(is_y_key as u8)
+    }
+
+    fn write_string(&mut self, s: &str) -> JwstCodecResult {
+        self.string.write(s)
+    }
+
+    fn write_type_ref(&mut self, type_ref: u64) -> JwstCodecResult {
+        self.type_ref.write(type_ref)
+    }
+
+    fn write_len(&mut self, len: u64) -> JwstCodecResult {
+        self.len.write(len)
+    }
+
+    fn write_key(&mut self, key: &str) -> JwstCodecResult {
+        // yjs never reuses the written keys, we keep the same behavior
+        // to produce identical binary
+        self.key_clock_encoder.write(self.key_clock)?;
+        self.key_clock += 1;
+        self.string.write(key)
+    }
+
+    fn write_json(&mut self, any: &Any) -> JwstCodecResult {
+        any.write(self)
+    }
+
+    fn reset_ds_cur_val(&mut self) {
+        self.ds_cur_val = 0;
+    }
+
+    fn write_ds_clock(&mut self, clock: Clock) -> JwstCodecResult {
+        let diff = clock.checked_sub(self.ds_cur_val).ok_or_else(|| {
+            JwstCodecError::InvalidWriteBuffer(format!("delete set is not sorted: {clock} < {}", self.ds_cur_val))
+        })?;
+        self.ds_cur_val = clock;
+        self.rest.write_var_u64(diff)
+    }
+
+    fn write_ds_len(&mut self, len: Clock) -> JwstCodecResult {
+        if len == 0 {
+            return Err(JwstCodecError::InvalidWriteBuffer("empty delete set range".into()));
+        }
+        self.ds_cur_val += len;
+        self.rest.write_var_u64(len - 1)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_rle_codec() {
+        let mut encoder = RleEncoder::default();
+        for v in [1, 1, 1, 2, 3, 3] {
+            encoder.write(v).unwrap();
+        }
+        let buffer = encoder.into_inner();
+        // the counter of last run is omitted
+        assert_eq!(buffer, vec![1, 2, 2, 0, 3]);
+
+        let mut decoder = RleDecoder::new(&buffer);
+        for v in [1, 1, 1, 2, 3, 3, 3, 3] {
+            assert_eq!(decoder.read().unwrap(), v);
+        }
+    }
+
+    #[test]
+    fn test_uint_opt_rle_codec() {
+        let values = [0, 0, 7, 7, 7, 1, 2, 2, u32::MAX as u64 + 1];
+        let mut encoder = UintOptRleEncoder::default();
+        for v in values {
+            encoder.write(v).unwrap();
+        }
+        let buffer = encoder.into_inner().unwrap();
+        // -0 with count, -7 with count, 1, -2 with count, 2^32
+        assert_eq!(&buffer[..7], &[0b0100_0000, 0, 0b0100_0111, 1, 1, 0b0100_0010, 0]);
+
+        let mut decoder = UintOptRleDecoder::new(&buffer);
+        for v in values {
+            assert_eq!(decoder.read().unwrap(), v);
+        }
+    }
+
+    #[test]
+    fn test_int_diff_opt_rle_codec() {
+        let values = [1, 2, 3, 4, 10, 5, 5, 5, 100000];
+        let mut encoder = IntDiffOptRleEncoder::default();
+        for v in values {
+            encoder.write(v).unwrap();
+        }
+        let buffer = encoder.into_inner().unwrap();
+        // diff 1 * 4, diff 6, diff -5, diff 0 * 2, diff 99995
+        assert_eq!(&buffer[..6], &[3, 2, 12, 0b0100_1010, 1, 0]);
+
+        let mut decoder = IntDiffOptRleDecoder::new(&buffer);
+        for v in values {
+            assert_eq!(decoder.read().unwrap(), v);
+        }
+    }
+
+    #[test]
+    fn test_string_codec() {
+        let values = ["hello", "", "世界", "👋🏻", "world"];
+        let mut encoder = StringEncoder::default();
+        for v in values {
+            encoder.write(v).unwrap();
+        }
+        let buffer = encoder.into_inner().unwrap();
+
+        let mut decoder = StringDecoder::new(&buffer).unwrap();
+        for v in values {
+            assert_eq!(decoder.read().unwrap(), v);
+        }
+        assert!(decoder.read().is_err());
+    }
+
+    #[test]
+    fn test_crdt_codec_v2() {
+        let mut encoder = RawEncoderV2::default();
+        encoder.write_info(0x84).unwrap();
+        encoder.write_left_id(&Id::new(1, 2)).unwrap();
+        encoder.write_right_id(&Id::new(1, 5)).unwrap();
+        encoder.write_parent_info(true).unwrap();
+        encoder.write_string("root").unwrap();
+        encoder.write_key("key").unwrap();
+        encoder.write_json(&Any::from("value")).unwrap();
+        encoder.write_len(3).unwrap();
+        encoder.write_type_ref(1).unwrap();
+        encoder.reset_ds_cur_val();
+        encoder.write_ds_clock(3).unwrap();
+        encoder.write_ds_len(2).unwrap();
+        encoder.write_ds_clock(10).unwrap();
+        encoder.write_ds_len(1).unwrap();
+        let buffer = encoder.into_inner().unwrap();
+
+        let mut decoder = RawDecoderV2::new(&buffer).unwrap();
+        assert_eq!(decoder.read_info().unwrap(), 0x84);
+        assert_eq!(decoder.read_left_id().unwrap(), Id::new(1, 2));
+        assert_eq!(decoder.read_right_id().unwrap(), Id::new(1, 5));
+        assert!(decoder.read_parent_info().unwrap());
+        assert_eq!(decoder.read_string().unwrap(), "root");
+        assert_eq!(decoder.read_key().unwrap(), "key");
+        assert_eq!(decoder.read_json().unwrap(), Any::from("value"));
+        assert_eq!(decoder.read_len().unwrap(), 3);
+        assert_eq!(decoder.read_type_ref().unwrap(), 1);
+        decoder.reset_ds_cur_val();
+        assert_eq!(decoder.read_ds_clock().unwrap(), 3);
+        assert_eq!(decoder.read_ds_len().unwrap(), 2);
+        assert_eq!(decoder.read_ds_clock().unwrap(), 10);
+        assert_eq!(decoder.read_ds_len().unwrap(), 1);
+        assert!(decoder.is_empty());
+    }
+}
@@ -21,6 +21,66 @@ pub trait CrdtReader {
 
     fn read_info(&mut self) -> JwstCodecResult<u8>;
     fn read_item_id(&mut self) -> JwstCodecResult<Id>;
+
+    // the following fields are written inline in v1 format,
+    // the v2 decoder reads them from the column-oriented buffers
+    #[inline(always)]
+    fn read_left_id(&mut self) -> JwstCodecResult<Id> {
+        self.read_item_id()
+    }
+
+    #[inline(always)]
+    fn read_right_id(&mut self) -> JwstCodecResult<Id> {
+        self.read_item_id()
+    }
+
+    #[inline(always)]
+    fn read_client(&mut self) -> JwstCodecResult<Client> {
+        self.read_var_u64()
+    }
+
+    #[inline(always)]
+    fn read_parent_info(&mut self) -> JwstCodecResult<bool> {
+        Ok(self.read_var_u64()? == 1)
+    }
+
+    #[inline(always)]
+    fn read_string(&mut self) -> JwstCodecResult<String> {
+        self.read_var_string()
+    }
+
+    #[inline(always)]
+    fn read_type_ref(&mut self) -> JwstCodecResult<u64> {
+        self.read_var_u64()
+    }
+
+    #[inline(always)]
+    fn read_len(&mut self) -> JwstCodecResult<u64> {
+        self.read_var_u64()
+    }
+
+    #[inline(always)]
+    fn read_key(&mut self) -> JwstCodecResult<String> {
+        self.read_var_string()
+    }
+
+    fn read_json(&mut self) -> JwstCodecResult<Any> {
+        let string = self.read_var_string()?;
+        serde_json::from_str(&string).map_err(|_| JwstCodecError::DamagedDocumentJson)
+    }
+
+    #[inline(always)]
+    fn reset_ds_cur_val(&mut self) {}
+
+    #[inline(always)]
+    fn read_ds_clock(&mut self) -> JwstCodecResult<Clock> {
+        self.read_var_u64()
+    }
+
+    #[inline(always)]
+    fn read_ds_len(&mut self) -> JwstCodecResult<Clock> {
+        self.read_var_u64()
+    }
 }
 
 pub trait CrdtRead<R: CrdtReader> {
@@ -19,6 +19,65 @@ pub trait CrdtWriter {
 
     fn write_info(&mut self, num: u8) -> JwstCodecResult;
     fn write_item_id(&mut self, id: &Id) -> JwstCodecResult;
+
+    // the following fields are written inline in v1 format,
+    // the v2 encoder splits them into column-oriented buffers
+    #[inline(always)]
+    fn write_left_id(&mut self, id: &Id) -> JwstCodecResult {
+        self.write_item_id(id)
+    }
+
+    #[inline(always)]
+    fn write_right_id(&mut self, id: &Id) -> JwstCodecResult {
+        self.write_item_id(id)
+    }
+
+    #[inline(always)]
+    fn write_client(&mut self, client: Client) -> JwstCodecResult {
+        self.write_var_u64(client)
+    }
+
+    #[inline(always)]
+    fn write_parent_info(&mut self, is_y_key: bool) -> JwstCodecResult {
+        self.write_var_u64(is_y_key as u64)
+    }
+
+    #[inline(always)]
+    fn write_string(&mut self, s: &str) -> JwstCodecResult {
+        self.write_var_string(s)
+    }
+
+    #[inline(always)]
+    fn write_type_ref(&mut self, type_ref: u64) -> JwstCodecResult {
+        self.write_var_u64(type_ref)
+    }
+
+    #[inline(always)]
+    fn write_len(&mut self, len: u64) -> JwstCodecResult {
+        self.write_var_u64(len)
+    }
+
+    #[inline(always)]
+    fn write_key(&mut self, key: &str) -> JwstCodecResult {
+        self.write_var_string(key)
+    }
+
+    fn write_json(&mut self, any: &Any) -> JwstCodecResult {
+        self.write_var_string(serde_json::to_string(any).map_err(|_| JwstCodecError::DamagedDocumentJson)?)
+    }
+
+    #[inline(always)]
+    fn reset_ds_cur_val(&mut self) {}
+
+    #[inline(always)]
+    fn write_ds_clock(&mut self, clock: Clock) -> JwstCodecResult {
+        self.write_var_u64(clock)
+    }
+
+    #[inline(always)]
+    fn write_ds_len(&mut self, len: Clock) -> JwstCodecResult {
+        self.write_var_u64(len)
+    }
 }
 
 pub trait CrdtWrite<W: CrdtWriter> {
@@ -26,6 +26,9 @@ pub(crate) struct Item {
     pub content: Content,
     #[cfg_attr(all(test, not(loom)), proptest(value = "ItemFlag::default()"))]
     pub flags: ItemFlag,
+    /// the move which shows this item at its position, see [Content::Move]
+    #[cfg_attr(all(test, not(loom)), proptest(value = "Somr::none()"))]
+    pub moved: ItemRef,
 }
 
 // make all Item readonly
@@ -87,6 +90,7 @@ impl Default for Item {
             parent_sub: None,
             content: Content::Deleted(0),
             flags: ItemFlag::from(0),
+            moved: Somr::none(),
         }
     }
 }
@@ -116,6 +120,7 @@ impl Item {
             parent_sub,
             content,
             flags,
+            moved: Somr::none(),
         }
     }
 
@@ -191,7 +196,7 @@ impl Item {
             self.parent_sub.clone(),
         );
 
-        let right_item = Item::new(
+        let mut right_item = Item::new(
             right_id,
             right_content,
             // let caller connect left <-> node <-> right
@@ -200,12 +205,16 @@ impl Item {
             self.parent.clone(),
             self.parent_sub.clone(),
         );
+        right_item.moved = self.moved.clone();
 
-        if left_item.deleted() {
-            left_item.flags.set_deleted();
-        }
-        if left_item.keep() {
-            left_item.flags.set_keep();
+        // the split parts keep the state of the item
+        for item in [&left_item, &right_item] {
+            if self.deleted() {
+                item.flags.set_deleted();
+            }
+            if self.keep() {
+                item.flags.set_keep();
+            }
         }
 
         Ok((left_item, right_item))
@@ -244,29 +253,29 @@ impl Item {
         let item = Self {
             id,
             origin_left_id: if has_left_id {
-                Some(decoder.read_item_id()?)
+                Some(decoder.read_left_id()?)
             } else {
                 None
             },
             origin_right_id: if has_right_id {
-                Some(decoder.read_item_id()?)
+                Some(decoder.read_right_id()?)
             } else {
                 None
             },
             parent: {
                 if has_not_sibling {
-                    let has_parent = decoder.read_var_u64()? == 1;
+                    let has_parent = decoder.read_parent_info()?;
                     Some(if has_parent {
-                        Parent::String(SmolStr::new(decoder.read_var_string()?))
+                        Parent::String(SmolStr::new(decoder.read_string()?))
                     } else {
-                        Parent::Id(decoder.read_item_id()?)
+                        Parent::Id(decoder.read_left_id()?)
                     })
                 } else {
                     None
                 }
             },
             parent_sub: if has_not_sibling && has_parent_sub {
-                Some(SmolStr::new(decoder.read_var_string()?))
+                Some(SmolStr::new(decoder.read_string()?))
             } else {
                 None
             },
@@ -279,6 +288,7 @@ impl Item {
             left: Somr::none(),
             right: Somr::none(),
             flags: ItemFlag::from(0),
+            moved: Somr::none(),
         };
 
         if item.content.countable() {
@@ -301,31 +311,31 @@ impl Item {
         encoder.write_info(info)?;
 
         if let Some(left_id) = self.origin_left_id {
-            encoder.write_item_id(&left_id)?;
+            encoder.write_left_id(&left_id)?;
         }
         if let Some(right_id) = self.origin_right_id {
-            encoder.write_item_id(&right_id)?;
+            encoder.write_right_id(&right_id)?;
         }
 
         if has_not_sibling {
             if let Some(parent) = &self.parent {
                 match parent {
                     Parent::String(s) => {
-                        encoder.write_var_u64(1)?;
-                        encoder.write_var_string(s)?;
+                        encoder.write_parent_info(true)?;
+                        encoder.write_string(s)?;
                     }
                     Parent::Id(id) => {
-                        encoder.write_var_u64(0)?;
-                        encoder.write_item_id(id)?;
+                        encoder.write_parent_info(false)?;
+                        encoder.write_left_id(id)?;
                     }
                     Parent::Type(ty) => {
                         if let Some(ty) = ty.ty() {
                             if let Some(item) = ty.item.get() {
-                                encoder.write_var_u64(0)?;
-                                encoder.write_item_id(&item.id)?;
+                                encoder.write_parent_info(false)?;
+                                encoder.write_left_id(&item.id)?;
                             } else if let Some(name) = &ty.root_name {
-                                encoder.write_var_u64(1)?;
-                                encoder.write_var_string(name)?;
+                                encoder.write_parent_info(true)?;
+                                encoder.write_string(name)?;
                             }
                         }
                     }
@@ -337,7 +347,7 @@ impl Item {
             }
 
             if let Some(parent_sub) = &self.parent_sub {
-                encoder.write_var_string(parent_sub)?;
+                encoder.write_string(parent_sub)?;
             }
         }
 
@@ -425,6 +435,28 @@ mod tests {
         Ok(())
     }
 
+    #[test]
+    #[cfg(not(loom))]
+    fn test_split_keeps_flags() {
+        let item = Item::new(
+            Id::new(1, 0),
+            Content::String("octo".into()),
+            Somr::none(),
+            Somr::none(),
+            None,
+            None,
+        );
+        item.flags.set_deleted();
+        item.flags.set_keep();
+
+        let (left, right) = item.split_at(2).unwrap();
+        for item in [&left, &right] {
+            assert!(item.deleted());
+            assert!(item.keep());
+        }
+        assert_eq!(right.id, Id::new(1, 2));
+    }
+
     #[cfg(not(loom))]
     proptest! {
         #[test]
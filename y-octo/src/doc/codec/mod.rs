@@ -1,23 +1,31 @@
 mod any;
+mod any_serde;
 mod content;
 mod delete_set;
 mod id;
 mod io;
 mod item;
 mod item_flag;
+mod moving;
 mod refs;
+mod relative_position;
+mod snapshot;
 mod update;
 #[cfg(test)]
 mod utils;
 
 pub use any::Any;
+pub use any_serde::{AnySerializer, from_any, from_value, to_value};
 pub(crate) use content::Content;
 pub use delete_set::DeleteSet;
 pub use id::{Client, Clock, Id};
-pub use io::{CrdtRead, CrdtReader, CrdtWrite, CrdtWriter, RawDecoder, RawEncoder};
+pub use io::{CrdtRead, CrdtReader, CrdtWrite, CrdtWriter, RawDecoder, RawDecoderV2, RawEncoder, RawEncoderV2};
 pub(crate) use item::{Item, ItemRef, Parent};
 pub(crate) use item_flag::{ItemFlag, item_flags};
+pub(crate) use moving::Move;
 pub(crate) use refs::Node;
+pub use relative_position::{AbsolutePosition, Assoc, RelativePosition};
+pub use snapshot::Snapshot;
 pub use update::Update;
 #[cfg(test)]
 pub(crate) use utils::*;
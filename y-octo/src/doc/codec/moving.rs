@@ -0,0 +1,158 @@
+use super::*;
+
+const MOVE_COLLAPSED: i32 = 0b0001;
+const MOVE_START_AFTER: i32 = 0b0010;
+const MOVE_END_AFTER: i32 = 0b0100;
+const MOVE_PRIORITY_SHIFT: i32 = 6;
+
+/// The content that moves a range of items in a list to the position of
+/// itself, compatible with `Move` in yrs.
+///
+/// The range is anchored like a [RelativePosition], the start and end are
+/// the items at the ids, or the items right after them if associated
+/// [Assoc::Before]. The end is excluded from the range.
+///
+/// Concurrent moves of the same item are resolved by the priority, and then
+/// by the id of the moves, the loser is recorded in the overrides of the
+/// winner, and it takes the items back once the winner is deleted.
+#[derive(Clone)]
+pub(crate) struct Move {
+    pub start: Id,
+    pub start_assoc: Assoc,
+    pub end: Id,
+    pub end_assoc: Assoc,
+    /// `-1` for the moves created locally, the priority is adapted to be
+    /// higher than the moves overridden by it when it's integrated
+    pub priority: i32,
+    /// the ids of the moves whose items are taken over by this move
+    pub overrides: HashSet<Id>,
+    /// the first item in the range, resolved when the move is integrated
+    pub start_item: ItemRef,
+    /// the item right after the range, none if the range reaches the end of
+    /// the list
+    pub end_item: ItemRef,
+}
+
+impl Move {
+    pub fn new(start: Id, start_assoc: Assoc, end: Id, end_assoc: Assoc) -> Self {
+        Self {
+            start,
+            start_assoc,
+            end,
+            end_assoc,
+            priority: -1,
+            overrides: HashSet::default(),
+            start_item: Somr::none(),
+            end_item: Somr::none(),
+        }
+    }
+
+    /// Whether the range is anchored by a single id, which is the case of
+    /// moving a single item
+    pub fn is_collapsed(&self) -> bool {
+        self.start == self.end
+    }
+
+    /// The items in the range resolved on integration, including the ones
+    /// deleted or shown by other moves
+    pub fn items(&self) -> impl Iterator<Item = ItemRef> + use<> {
+        let end = self.end_item.clone();
+        std::iter::successors(Some(self.start_item.clone()), |item| {
+            item.get().map(|item| item.right.clone())
+        })
+        .take_while(move |item| item.is_some() && (end.is_none() || *item != end))
+    }
+}
+
+impl PartialEq for Move {
+    fn eq(&self, other: &Self) -> bool {
+        self.start == other.start
+            && self.start_assoc == other.start_assoc
+            && self.end == other.end
+            && self.end_assoc == other.end_assoc
+            && self.priority == other.priority
+    }
+}
+
+impl std::fmt::Debug for Move {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("Move")
+            .field("start", &self.start)
+            .field("start_assoc", &self.start_assoc)
+            .field("end", &self.end)
+            .field("end_assoc", &self.end_assoc)
+            .field("priority", &self.priority)
+            .finish()
+    }
+}
+
+impl<R: CrdtReader> CrdtRead<R> for Move {
+    fn read(decoder: &mut R) -> JwstCodecResult<Self> {
+        let flags = decoder.read_var_i32()?;
+        let assoc = |flag: i32| {
+            if flags & flag != 0 { Assoc::After } else { Assoc::Before }
+        };
+
+        let start = Id::new(decoder.read_var_u64()?, decoder.read_var_u64()?);
+        let end = if flags & MOVE_COLLAPSED != 0 {
+            start
+        } else {
+            Id::new(decoder.read_var_u64()?, decoder.read_var_u64()?)
+        };
+
+        Ok(Self {
+            priority: flags >> MOVE_PRIORITY_SHIFT,
+            ..Self::new(start, assoc(MOVE_START_AFTER), end, assoc(MOVE_END_AFTER))
+        })
+    }
+}
+
+impl<W: CrdtWriter> CrdtWrite<W> for Move {
+    fn write(&self, encoder: &mut W) -> JwstCodecResult {
+        let collapsed = self.is_collapsed();
+        let mut flags = self.priority << MOVE_PRIORITY_SHIFT;
+        if collapsed {
+            flags |= MOVE_COLLAPSED;
+        }
+        if self.start_assoc == Assoc::After {
+            flags |= MOVE_START_AFTER;
+        }
+        if self.end_assoc == Assoc::After {
+            flags |= MOVE_END_AFTER;
+        }
+
+        encoder.write_var_i32(flags)?;
+        encoder.write_var_u64(self.start.client)?;
+        encoder.write_var_u64(self.start.clock)?;
+        if !collapsed {
+            encoder.write_var_u64(self.end.client)?;
+            encoder.write_var_u64(self.end.clock)?;
+        }
+
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_move_codec() {
+        let mut content = Move::new(Id::new(1, 2), Assoc::After, Id::new(1, 2), Assoc::Before);
+        content.priority = 1;
+
+        let mut encoder = RawEncoder::default();
+        content.write(&mut encoder).unwrap();
+        let binary = encoder.into_inner();
+        // flags are written as signed var int: 1 << 6 | 0b011
+        assert_eq!(binary, vec![0x83, 0x01, 1, 2]);
+        assert_eq!(Move::read(&mut RawDecoder::new(&binary)).unwrap(), content);
+
+        let content = Move::new(Id::new(1, 0), Assoc::Before, Id::new(2, 3), Assoc::After);
+        let mut encoder = RawEncoder::default();
+        content.write(&mut encoder).unwrap();
+        let binary = encoder.into_inner();
+        assert_eq!(Move::read(&mut RawDecoder::new(&binary)).unwrap(), content);
+    }
+}
@@ -22,7 +22,7 @@ impl<W: CrdtWriter> CrdtWrite<W> for Node {
         match self {
             Node::GC(item) => {
                 writer.write_info(0)?;
-                writer.write_var_u64(item.len)
+                writer.write_len(item.len)
             }
             Node::Skip(item) => {
                 writer.write_info(10)?;
@@ -67,7 +67,7 @@ impl Node {
 
         match first_5_bit {
             0 => {
-                let len = decoder.read_var_u64()?;
+                let len = decoder.read_len()?;
                 Ok(Node::new_gc(id, len))
             }
             10 => {
@@ -195,36 +195,34 @@ impl Node {
     }
 
     pub fn split_at(&self, offset: u64) -> JwstCodecResult<(Self, Self)> {
-        if let Self::Item(item) = self {
-            let item = item.get().unwrap();
-            debug_assert!(offset > 0 && item.len() > 1 && offset < item.len());
-            let id = item.id;
-            let right_id = Id::new(id.client, id.clock + offset);
-            let (left_content, right_content) = item.content.split(offset)?;
-
-            let left_item = Somr::new(Item::new(
-                id,
-                left_content,
-                // let caller connect left <-> node <-> right
-                Somr::none(),
-                Somr::none(),
-                item.parent.clone(),
-                item.parent_sub.clone(),
-            ));
-
-            let right_item = Somr::new(Item::new(
-                right_id,
-                right_content,
-                // let caller connect left <-> node <-> right
-                Somr::none(),
-                Somr::none(),
-                item.parent.clone(),
-                item.parent_sub.clone(),
-            ));
+        debug_assert!(offset > 0 && offset < self.len());
+        match self {
+            Self::Item(item) => {
+                let item = item.get().unwrap();
+                let (mut left_item, mut right_item) = item.split_at(offset)?;
+
+                // the splitted nodes are not connected to any store,
+                // keep the origins so they can be encoded and integrated again
+                left_item.origin_left_id = item.origin_left_id;
+                left_item.origin_right_id = item.origin_right_id;
+                right_item.origin_left_id = Some(left_item.last_id());
+                right_item.origin_right_id = item.origin_right_id;
+
+                if item.deleted() {
+                    left_item.flags.set_deleted();
+                    right_item.flags.set_deleted();
+                }
 
-            Ok((Self::Item(left_item), Self::Item(right_item)))
-        } else {
-            Err(JwstCodecError::ItemSplitNotSupport)
+                Ok((Self::Item(Somr::new(left_item)), Self::Item(Somr::new(right_item))))
+            }
+            Self::GC(node) => Ok((
+                Self::new_gc(node.id, offset),
+                Self::new_gc(Id::new(node.id.client, node.id.clock + offset), node.len - offset),
+            )),
+            Self::Skip(node) => Ok((
+                Self::new_skip(node.id, offset),
+                Self::new_skip(Id::new(node.id.client, node.id.clock + offset), node.len - offset),
+            )),
         }
     }
 
@@ -268,6 +266,9 @@ impl Node {
                     || litem.origin_right_id != ritem.origin_right_id
                     // not runtime continuous
                     || litem.right != rref
+                    // moved items may be the boundaries of moves
+                    || litem.moved.is_some()
+                    || ritem.moved.is_some()
                 {
                     return false;
                 }
@@ -0,0 +1,282 @@
+use serde::{Deserialize, Serialize};
+
+use super::*;
+
+/// Which side of the anchored item a position sticks to when content is
+/// inserted right at it.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Assoc {
+    /// Stick to the item on the right side of the position
+    #[default]
+    After,
+    /// Stick to the item on the left side of the position
+    Before,
+}
+
+impl From<i32> for Assoc {
+    fn from(value: i32) -> Self {
+        if value >= 0 { Assoc::After } else { Assoc::Before }
+    }
+}
+
+impl From<Assoc> for i32 {
+    fn from(value: Assoc) -> Self {
+        match value {
+            Assoc::After => 0,
+            Assoc::Before => -1,
+        }
+    }
+}
+
+/// A position in a list type that sticks to the surrounding content under
+/// concurrent edits, compatible with `Y.RelativePosition`.
+///
+/// The position is anchored to the item at the index, or to the end of the
+/// type if there is no such item. The type is referenced by the `Id` of its
+/// item, or by the root name if it's a root type.
+#[derive(Debug, Default, Clone, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(from = "RelativePositionJson", into = "RelativePositionJson")]
+pub struct RelativePosition {
+    pub type_id: Option<Id>,
+    pub root_name: Option<String>,
+    pub item: Option<Id>,
+    pub assoc: Assoc,
+}
+
+impl<R: CrdtReader> CrdtRead<R> for RelativePosition {
+    fn read(decoder: &mut R) -> JwstCodecResult<Self> {
+        let mut position = RelativePosition::default();
+        match decoder.read_var_u64()? {
+            0 => position.item = Some(decoder.read_item_id()?),
+            1 => position.root_name = Some(decoder.read_var_string()?),
+            2 => position.type_id = Some(decoder.read_item_id()?),
+            _ => return Err(JwstCodecError::InvalidRelativePosition),
+        }
+        // the association is optional in the old versions of yjs
+        if !decoder.is_empty() {
+            position.assoc = Assoc::from(decoder.read_var_i32()?);
+        }
+
+        Ok(position)
+    }
+}
+
+impl<W: CrdtWriter> CrdtWrite<W> for RelativePosition {
+    fn write(&self, encoder: &mut W) -> JwstCodecResult {
+        if let Some(item) = &self.item {
+            encoder.write_var_u64(0)?;
+            encoder.write_item_id(item)?;
+        } else if let Some(root_name) = &self.root_name {
+            encoder.write_var_u64(1)?;
+            encoder.write_var_string(root_name)?;
+        } else if let Some(type_id) = &self.type_id {
+            encoder.write_var_u64(2)?;
+            encoder.write_item_id(type_id)?;
+        } else {
+            return Err(JwstCodecError::InvalidRelativePosition);
+        }
+        encoder.write_var_i32(self.assoc.into())?;
+
+        Ok(())
+    }
+}
+
+impl RelativePosition {
+    // decode from ydoc v1
+    pub fn decode_v1<T: AsRef<[u8]>>(buffer: T) -> JwstCodecResult<RelativePosition> {
+        RelativePosition::read(&mut RawDecoder::new(buffer.as_ref()))
+    }
+
+    pub fn encode_v1(&self) -> JwstCodecResult<Vec<u8>> {
+        let mut encoder = RawEncoder::default();
+        self.write(&mut encoder)?;
+        Ok(encoder.into_inner())
+    }
+}
+
+#[derive(Serialize, Deserialize)]
+struct IdJson {
+    client: Client,
+    clock: Clock,
+}
+
+/// The json representation used by `Y.relativePositionToJSON`
+#[derive(Serialize, Deserialize)]
+struct RelativePositionJson {
+    #[serde(rename = "type", default, skip_serializing_if = "Option::is_none")]
+    type_id: Option<IdJson>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    tname: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    item: Option<IdJson>,
+    #[serde(default)]
+    assoc: i32,
+}
+
+impl From<RelativePositionJson> for RelativePosition {
+    fn from(json: RelativePositionJson) -> Self {
+        Self {
+            type_id: json.type_id.map(|id| Id::new(id.client, id.clock)),
+            root_name: json.tname,
+            item: json.item.map(|id| Id::new(id.client, id.clock)),
+            assoc: Assoc::from(json.assoc),
+        }
+    }
+}
+
+impl From<RelativePosition> for RelativePositionJson {
+    fn from(position: RelativePosition) -> Self {
+        let to_json = |id: Id| IdJson {
+            client: id.client,
+            clock: id.clock,
+        };
+
+        Self {
+            type_id: position.type_id.map(to_json),
+            tname: position.root_name,
+            item: position.item.map(to_json),
+            assoc: position.assoc.into(),
+        }
+    }
+}
+
+/// The index in a type resolved from a [RelativePosition], see
+/// [Doc::absolute_position](crate::Doc::absolute_position).
+#[derive(Debug, Clone, PartialEq)]
+pub struct AbsolutePosition {
+    pub target: Value,
+    pub index: u64,
+    pub assoc: Assoc,
+}
+
+#[cfg(test)]
+mod tests {
+    use serde_json::json;
+
+    use super::*;
+    use crate::{Doc, DocOptions};
+
+    #[test]
+    fn test_relative_position_codec() {
+        let position = RelativePosition {
+            type_id: Some(Id::new(1, 0)),
+            root_name: None,
+            item: Some(Id::new(1, 2)),
+            assoc: Assoc::Before,
+        };
+        // the type is not encoded in binary if the item exists, like yjs
+        assert_eq!(position.encode_v1().unwrap(), vec![0, 1, 2, 0x41]);
+        assert_eq!(
+            RelativePosition::decode_v1([0, 1, 2, 0x41]).unwrap(),
+            RelativePosition {
+                type_id: None,
+                ..position.clone()
+            }
+        );
+        assert_eq!(
+            serde_json::to_value(&position).unwrap(),
+            json!({ "type": { "client": 1, "clock": 0 }, "item": { "client": 1, "clock": 2 }, "assoc": -1 })
+        );
+
+        let position = RelativePosition {
+            root_name: Some("text".into()),
+            ..Default::default()
+        };
+        let binary = position.encode_v1().unwrap();
+        assert_eq!(binary, vec![1, 4, b't', b'e', b'x', b't', 0]);
+        assert_eq!(RelativePosition::decode_v1(&binary).unwrap(), position);
+        // the association is missing in old versions of yjs
+        assert_eq!(RelativePosition::decode_v1(&binary[..6]).unwrap(), position);
+
+        let json = json!({ "tname": "text", "assoc": 0 });
+        assert_eq!(serde_json::to_value(&position).unwrap(), json);
+        assert_eq!(serde_json::from_value::<RelativePosition>(json).unwrap(), position);
+
+        assert!(RelativePosition::decode_v1([3, 0]).is_err());
+        assert!(RelativePosition::default().encode_v1().is_err());
+    }
+
+    #[test]
+    fn test_relative_position_in_text() {
+        loom_model!({
+            let doc = DocOptions::new().with_client_id(1).build();
+            let mut text = doc.get_or_create_text("text").unwrap();
+            text.insert(0, "abc").unwrap();
+
+            let after = text.relative_position(1, Assoc::After).unwrap();
+            let before = text.relative_position(1, Assoc::Before).unwrap();
+            let end = text.relative_position(3, Assoc::After).unwrap();
+            assert_eq!(after.item, Some(Id::new(1, 1)));
+            assert_eq!(before.item, Some(Id::new(1, 0)));
+            assert_eq!(end.root_name.as_deref(), Some("text"));
+            assert_eq!(end.item, None);
+
+            let resolve = |doc: &Doc, position: &RelativePosition| doc.absolute_position(position).unwrap().index;
+
+            // insert right at the position
+            text.insert(1, "xy").unwrap();
+            assert_eq!(resolve(&doc, &after), 3);
+            assert_eq!(resolve(&doc, &before), 1);
+            assert_eq!(resolve(&doc, &end), 5);
+
+            // concurrent edits from remote
+            let mut remote = DocOptions::new().with_client_id(2).build();
+            remote
+                .apply_update_from_binary_v1(doc.encode_update_v1().unwrap())
+                .unwrap();
+            let mut remote_text = remote.get_or_create_text("text").unwrap();
+            remote_text.insert(0, "😀").unwrap();
+            // the indexes are measured in utf-16 by default
+            remote_text.remove(3, 2).unwrap();
+            assert_eq!(remote_text.to_string(), "😀abc");
+
+            let binary = after.encode_v1().unwrap();
+            let position = RelativePosition::decode_v1(binary).unwrap();
+            let resolved = remote.absolute_position(&position).unwrap();
+            assert_eq!(resolved.target, Value::Text(remote_text.clone()));
+            assert_eq!(resolved.index, 3);
+
+            // the deleted item is resolved to where it was
+            remote_text.remove(3, 1).unwrap();
+            assert_eq!(resolve(&remote, &position), 3);
+
+            // unknown items
+            let position = RelativePosition {
+                item: Some(Id::new(3, 0)),
+                ..Default::default()
+            };
+            assert_eq!(remote.absolute_position(&position), None);
+        });
+    }
+
+    #[test]
+    fn test_relative_position_in_nested_array() {
+        loom_model!({
+            let doc = Doc::default();
+            let mut map = doc.get_or_create_map("map").unwrap();
+            map.insert("array".into(), doc.create_array().unwrap()).unwrap();
+            let mut array = map.get("array").and_then(|value| value.to_array()).unwrap();
+
+            let start = array.relative_position(0, Assoc::Before).unwrap();
+            let end = array.relative_position(0, Assoc::After).unwrap();
+            assert_eq!(start.type_id, array.id());
+            assert_eq!(start.item, None);
+
+            array.push(1).unwrap();
+            array.push(2).unwrap();
+            let before_last = array.relative_position(2, Assoc::Before).unwrap();
+            assert_eq!(
+                before_last.item,
+                Some(array.id().map(|id| Id::new(id.client, id.clock + 2)).unwrap())
+            );
+
+            array.insert(0, 0).unwrap();
+            array.push(3).unwrap();
+            let resolved = doc.absolute_position(&start).unwrap();
+            assert_eq!(resolved.target, Value::Array(array.clone()));
+            assert_eq!(resolved.index, 0);
+            assert_eq!(doc.absolute_position(&end).unwrap().index, 4);
+            assert_eq!(doc.absolute_position(&before_last).unwrap().index, 3);
+        });
+    }
+}
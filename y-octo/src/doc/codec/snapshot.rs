@@ -0,0 +1,106 @@
+use super::*;
+use crate::doc::StateVector;
+
+/// The state of a doc at a point in time, compatible with `Y.Snapshot`.
+/// A snapshot only records which structs exist and which are deleted, the
+/// content is restored from a doc that has not been garbage collected.
+#[derive(Debug, Default, Clone, PartialEq)]
+pub struct Snapshot {
+    pub state_vector: StateVector,
+    pub delete_set: DeleteSet,
+}
+
+impl<R: CrdtReader> CrdtRead<R> for Snapshot {
+    fn read(decoder: &mut R) -> JwstCodecResult<Self> {
+        let delete_set = DeleteSet::read(decoder)?;
+        let state_vector = StateVector::read(decoder)?;
+
+        Ok(Self {
+            state_vector,
+            delete_set,
+        })
+    }
+}
+
+impl<W: CrdtWriter> CrdtWrite<W> for Snapshot {
+    fn write(&self, encoder: &mut W) -> JwstCodecResult {
+        self.delete_set.write(encoder)?;
+        self.state_vector.write(encoder)?;
+
+        Ok(())
+    }
+}
+
+impl Snapshot {
+    pub fn new(state_vector: StateVector, delete_set: DeleteSet) -> Self {
+        Self {
+            state_vector,
+            delete_set,
+        }
+    }
+
+    /// Whether the struct with given id exists and is not deleted in snapshot
+    pub fn is_visible(&self, id: &Id) -> bool {
+        self.state_vector.get(&id.client) > id.clock
+            && !self
+                .delete_set
+                .get(&id.client)
+                .is_some_and(|deletes| deletes.contains(id.clock))
+    }
+
+    // decode from ydoc v1
+    pub fn decode_v1<T: AsRef<[u8]>>(buffer: T) -> JwstCodecResult<Snapshot> {
+        Snapshot::read(&mut RawDecoder::new(buffer.as_ref()))
+    }
+
+    pub fn encode_v1(&self) -> JwstCodecResult<Vec<u8>> {
+        let mut encoder = RawEncoder::default();
+        self.write(&mut encoder)?;
+        Ok(encoder.into_inner())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use yrs::{Options, ReadTxn, Text, Transact, updates::encoder::Encode};
+
+    use super::*;
+
+    #[test]
+    fn test_snapshot_codec() {
+        loom_model!({
+            let mut delete_set = DeleteSet::default();
+            delete_set.add(1, 0, 2);
+            delete_set.add(1, 5, 1);
+            delete_set.add(2, 3, 4);
+
+            let snapshot = Snapshot::new(StateVector::from([(1, 10), (2, 7)]), delete_set);
+            let binary = snapshot.encode_v1().unwrap();
+
+            assert_eq!(Snapshot::decode_v1(binary).unwrap(), snapshot);
+            assert_eq!(Snapshot::default().encode_v1().unwrap(), vec![0, 0]);
+        });
+    }
+
+    #[test]
+    #[cfg_attr(any(miri, loom), ignore)]
+    fn test_snapshot_compatible_with_yrs() {
+        let doc = yrs::Doc::with_options(Options {
+            skip_gc: true,
+            ..Default::default()
+        });
+        let text = doc.get_or_insert_text("text");
+        {
+            let mut trx = doc.transact_mut();
+            text.insert(&mut trx, 0, "hello world");
+            text.remove_range(&mut trx, 0, 6);
+        }
+        let binary = doc.transact().snapshot().encode_v1();
+
+        let snapshot = Snapshot::decode_v1(&binary).unwrap();
+        assert_eq!(snapshot.state_vector.values().copied().collect::<Vec<_>>(), vec![11]);
+        let deleted = snapshot.delete_set.values().next().unwrap();
+        assert!(deleted.contains(0) && deleted.contains(5) && !deleted.contains(6));
+        assert_eq!(snapshot.encode_v1().unwrap(), binary);
+    }
+}
@@ -26,7 +26,7 @@ impl<R: CrdtReader> CrdtRead<R> for Update {
         let mut map = ClientMap::with_capacity(num_of_clients.min(HASHMAP_SAFE_CAPACITY));
         for _ in 0..num_of_clients {
             let num_of_structs = decoder.read_var_u64()? as usize;
-            let client = decoder.read_var_u64()?;
+            let client = decoder.read_client()?;
             let mut clock = decoder.read_var_u64()?;
 
             // same reason as above
@@ -71,7 +71,7 @@ impl<W: CrdtWriter> CrdtWrite<W> for Update {
             let structs = self.structs.get(&client).unwrap();
 
             encoder.write_var_u64(structs.len() as u64)?;
-            encoder.write_var_u64(client)?;
+            encoder.write_client(client)?;
             encoder.write_var_u64(structs.front().map(|s| s.clock()).unwrap_or(0))?;
 
             for struct_info in structs {
@@ -97,6 +97,81 @@ impl Update {
         Ok(encoder.into_inner())
     }
 
+    // decode from ydoc v2
+    pub fn decode_v2<T: AsRef<[u8]>>(buffer: T) -> JwstCodecResult<Update> {
+        Update::read(&mut RawDecoderV2::new(buffer.as_ref())?)
+    }
+
+    pub fn encode_v2(&self) -> JwstCodecResult<Vec<u8>> {
+        let mut encoder = RawEncoderV2::default();
+        self.write(&mut encoder)?;
+        encoder.into_inner()
+    }
+
+    /// Get the state vector of the structs in update, only the continuous
+    /// structs starting from clock 0 are counted, just like
+    /// `Y.encodeStateVectorFromUpdate`
+    pub fn state_vector(&self) -> StateVector {
+        let mut sv = StateVector::default();
+
+        for (client, structs) in &self.structs {
+            let mut clock = 0;
+            for node in structs {
+                if node.is_skip() || node.clock() != clock {
+                    break;
+                }
+                clock += node.len();
+            }
+
+            if clock > 0 {
+                sv.set_max(*client, clock);
+            }
+        }
+
+        sv
+    }
+
+    /// Get the structs that are missing in given state vector, the item on
+    /// the boundary will be splitted, just like `Y.diffUpdate`.
+    /// The delete set is always kept since it can't be diffed by state vector.
+    pub fn diff(&self, sv: &StateVector) -> JwstCodecResult<Update> {
+        let mut structs = ClientMap::new();
+
+        for (client, nodes) in &self.structs {
+            let clock = sv.get(client);
+            let mut nodes = nodes
+                .iter()
+                .skip_while(|node| node.is_skip() || node.clock() + node.len() <= clock);
+
+            if let Some(first) = nodes.next() {
+                let mut diff = VecDeque::new();
+                if first.clock() < clock {
+                    diff.push_back(first.split_at(clock - first.clock())?.1);
+                } else {
+                    diff.push_back(Self::copy_node(first));
+                }
+                diff.extend(nodes.map(Self::copy_node));
+
+                structs.insert(*client, diff);
+            }
+        }
+
+        Ok(Update {
+            structs,
+            delete_set: self.delete_set.clone(),
+            ..Update::default()
+        })
+    }
+
+    // items in update will be owned by the store after integrated,
+    // so we can't share them with another update
+    fn copy_node(node: &Node) -> Node {
+        match node {
+            Node::Item(item) => Node::Item(Somr::new(item.get().unwrap().clone())),
+            _ => node.clone(),
+        }
+    }
+
     pub(crate) fn iter(&mut self, state: StateVector) -> UpdateIterator<'_> {
         UpdateIterator::new(self, state)
     }
@@ -686,4 +761,90 @@ mod tests {
             assert_eq!(merged2.structs.get(&0).unwrap().len(), 9);
         });
     }
+
+    #[test]
+    fn test_update_state_vector() {
+        loom_model!({
+            let update = Update {
+                structs: ClientMap::from_iter([
+                    (
+                        0,
+                        VecDeque::from([
+                            struct_item((0, 0), 2),
+                            struct_item((0, 2), 1),
+                            Node::new_skip((0, 3).into(), 2),
+                            struct_item((0, 5), 1),
+                        ]),
+                    ),
+                    // not started from 0
+                    (1, VecDeque::from([struct_item((1, 3), 1)])),
+                    (2, VecDeque::from([Node::new_gc((2, 0).into(), 4)])),
+                ]),
+                ..Default::default()
+            };
+
+            assert_eq!(update.state_vector(), StateVector::from([(0, 3), (2, 4)]));
+        });
+    }
+
+    #[test]
+    fn test_update_diff() {
+        loom_model!({
+            let mut update = Update {
+                structs: ClientMap::from_iter([
+                    (
+                        0,
+                        VecDeque::from([
+                            struct_item((0, 0), 2),
+                            struct_item((0, 2), 4),
+                            Node::new_skip((0, 6).into(), 2),
+                            struct_item((0, 8), 1),
+                        ]),
+                    ),
+                    (1, VecDeque::from([struct_item((1, 0), 1)])),
+                ]),
+                ..Default::default()
+            };
+            update.delete_set.add(1, 0, 1);
+
+            let diff = update.diff(&StateVector::from([(0, 4), (1, 1)])).unwrap();
+
+            assert!(!diff.structs.contains_key(&1));
+            assert_eq!(diff.delete_set, update.delete_set);
+
+            let structs = diff.structs.get(&0).unwrap();
+            assert_eq!(structs.len(), 3);
+            assert_eq!(structs[0].id(), Id::new(0, 4));
+            assert_eq!(structs[0].len(), 2);
+            assert_eq!(structs[0].as_item().get().unwrap().origin_left_id, Some(Id::new(0, 3)));
+            assert!(structs[1].is_skip());
+
+            let diff = update.diff(&StateVector::default()).unwrap();
+            assert_eq!(diff.structs, update.structs);
+        });
+    }
+
+    #[test]
+    fn test_update_diff_with_doc() {
+        loom_model!({
+            let doc = Doc::new();
+            let mut text = doc.get_or_create_text("text").unwrap();
+            text.insert(0, "hello").unwrap();
+
+            let mut doc_new = Doc::try_from_binary_v1(doc.encode_update_v1().unwrap()).unwrap();
+
+            text.insert(5, " world").unwrap();
+            let update = Update::decode_v1(doc.encode_update_v1().unwrap()).unwrap();
+            assert_eq!(update.state_vector(), doc.get_state_vector());
+
+            // split in the middle of "hello"
+            let diff = update.diff(&StateVector::from([(doc.client(), 3)])).unwrap();
+            let structs = diff.structs.get(&doc.client()).unwrap();
+            assert_eq!(structs.front().unwrap().id(), Id::new(doc.client(), 3));
+            assert_eq!(structs.iter().map(|s| s.len()).sum::<u64>(), 8);
+
+            doc_new.apply_update(diff).unwrap();
+            assert_eq!(doc_new.get_or_create_text("text").unwrap().to_string(), "hello world");
+        });
+    }
 }
@@ -0,0 +1,186 @@
+use std::ops::Range;
+
+/// An operation to turn the old sequence into the new one, see [diff].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub(crate) enum DiffOp {
+    /// Keep the next `n` elements of the old sequence
+    Equal(usize),
+    /// Delete the next `n` elements of the old sequence
+    Delete(usize),
+    /// Insert the elements of the new sequence in the range
+    Insert(Range<usize>),
+}
+
+/// Compute the shortest edit script between two sequences with the linear
+/// space variant of the Myers' diff algorithm, which takes `O((N + M) * D)`
+/// time for `D` differences.
+pub(crate) fn diff<T: PartialEq>(old: &[T], new: &[T]) -> Vec<DiffOp> {
+    let mut ops = Vec::new();
+    diff_range(old, new, 0, &mut ops);
+    ops
+}
+
+fn push_op(ops: &mut Vec<DiffOp>, op: DiffOp) {
+    match (ops.last_mut(), op) {
+        (_, DiffOp::Equal(0) | DiffOp::Delete(0)) => {}
+        (_, DiffOp::Insert(range)) if range.is_empty() => {}
+        (Some(DiffOp::Equal(last)), DiffOp::Equal(n)) => *last += n,
+        (Some(DiffOp::Delete(last)), DiffOp::Delete(n)) => *last += n,
+        (Some(DiffOp::Insert(last)), DiffOp::Insert(range)) if last.end == range.start => last.end = range.end,
+        (_, op) => ops.push(op),
+    }
+}
+
+// `offset` is the index of `new` in the whole new sequence
+fn diff_range<T: PartialEq>(old: &[T], new: &[T], offset: usize, ops: &mut Vec<DiffOp>) {
+    let prefix = old.iter().zip(new).take_while(|(a, b)| a == b).count();
+    let (old, new) = (&old[prefix..], &new[prefix..]);
+    let suffix = old
+        .iter()
+        .rev()
+        .zip(new.iter().rev())
+        .take_while(|(a, b)| a == b)
+        .count();
+    let (old, new) = (&old[..old.len() - suffix], &new[..new.len() - suffix]);
+    let offset = offset + prefix;
+
+    push_op(ops, DiffOp::Equal(prefix));
+    if old.is_empty() || new.is_empty() {
+        push_op(ops, DiffOp::Delete(old.len()));
+        push_op(ops, DiffOp::Insert(offset..offset + new.len()));
+    } else {
+        // there are at least 2 differences once the common prefix and suffix
+        // are trimmed, so both sides of the middle snake are smaller
+        let (x, y, u, v) = middle_snake(old, new);
+        diff_range(&old[..x], &new[..y], offset, ops);
+        push_op(ops, DiffOp::Equal(u - x));
+        diff_range(&old[u..], &new[v..], offset + v, ops);
+    }
+    push_op(ops, DiffOp::Equal(suffix));
+}
+
+/// Find the snake in the middle of the shortest edit path, returns its start
+/// and end points as `(x, y, u, v)`.
+fn middle_snake<T: PartialEq>(old: &[T], new: &[T]) -> (usize, usize, usize, usize) {
+    let (n, m) = (old.len() as isize, new.len() as isize);
+    let delta = n - m;
+    let odd = delta % 2 != 0;
+    let max = (n + m + 1) / 2 + 1;
+    // the furthest x on each diagonal `k = x - y`, the backward one is
+    // measured from the ends of the sequences
+    let mut forward = vec![0; 2 * max as usize + 1];
+    let mut backward = vec![0; 2 * max as usize + 1];
+    let index = |k: isize| (k + max) as usize;
+
+    for d in 0..max {
+        for k in (-d..=d).step_by(2) {
+            let mut x = if k == -d || (k != d && forward[index(k - 1)] < forward[index(k + 1)]) {
+                forward[index(k + 1)]
+            } else {
+                forward[index(k - 1)] + 1
+            };
+            let (x0, y0) = (x, x - k);
+            let mut y = y0;
+            while x < n && y < m && old[x as usize] == new[y as usize] {
+                x += 1;
+                y += 1;
+            }
+            forward[index(k)] = x;
+
+            let back_k = delta - k;
+            if odd && (-(d - 1)..=d - 1).contains(&back_k) && x + backward[index(back_k)] >= n {
+                return (x0 as usize, y0 as usize, x as usize, y as usize);
+            }
+        }
+
+        for k in (-d..=d).step_by(2) {
+            let mut x = if k == -d || (k != d && backward[index(k - 1)] < backward[index(k + 1)]) {
+                backward[index(k + 1)]
+            } else {
+                backward[index(k - 1)] + 1
+            };
+            let (x0, y0) = (x, x - k);
+            let mut y = y0;
+            while x < n && y < m && old[(n - x - 1) as usize] == new[(m - y - 1) as usize] {
+                x += 1;
+                y += 1;
+            }
+            backward[index(k)] = x;
+
+            let forward_k = delta - k;
+            if !odd && (-d..=d).contains(&forward_k) && x + forward[index(forward_k)] >= n {
+                return ((n - x) as usize, (m - y) as usize, (n - x0) as usize, (m - y0) as usize);
+            }
+        }
+    }
+
+    unreachable!("the paths always overlap before `max`")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn apply(old: &str, new: &str, ops: &[DiffOp]) -> String {
+        let (old, new) = (old.chars().collect::<Vec<_>>(), new.chars().collect::<Vec<_>>());
+        let mut index = 0;
+        let mut result = String::new();
+        for op in ops {
+            match op {
+                DiffOp::Equal(n) => {
+                    result.extend(&old[index..index + n]);
+                    index += n;
+                }
+                DiffOp::Delete(n) => index += n,
+                DiffOp::Insert(range) => result.extend(&new[range.clone()]),
+            }
+        }
+        assert_eq!(index, old.len());
+        result
+    }
+
+    fn distance(ops: &[DiffOp]) -> usize {
+        ops.iter()
+            .map(|op| match op {
+                DiffOp::Equal(_) => 0,
+                DiffOp::Delete(n) => *n,
+                DiffOp::Insert(range) => range.len(),
+            })
+            .sum()
+    }
+
+    #[test]
+    fn test_diff() {
+        let cases = [
+            ("", "", 0),
+            ("abc", "abc", 0),
+            ("", "abc", 3),
+            ("abc", "", 3),
+            ("abc", "axc", 2),
+            ("abcabba", "cbabac", 5),
+            ("hello world", "hello, brave new world", 11),
+            ("the quick brown fox", "a quick brown dog jumps", 14),
+            ("🦀 rust", "rust 🦀", 4),
+        ];
+
+        for (old, new, expected) in cases {
+            let ops = diff(&old.chars().collect::<Vec<_>>(), &new.chars().collect::<Vec<_>>());
+            assert_eq!(apply(old, new, &ops), new, "{old} -> {new}");
+            assert_eq!(distance(&ops), expected, "{old} -> {new}");
+        }
+
+        let ops = diff(
+            &"abxcd".chars().collect::<Vec<_>>(),
+            &"abycd".chars().collect::<Vec<_>>(),
+        );
+        assert_eq!(
+            ops,
+            vec![
+                DiffOp::Equal(2),
+                DiffOp::Delete(1),
+                DiffOp::Insert(2..3),
+                DiffOp::Equal(2)
+            ]
+        );
+    }
+}
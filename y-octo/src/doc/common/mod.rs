@@ -1,7 +1,9 @@
+mod diff;
 mod range;
 mod somr;
 mod state;
 
+pub(crate) use diff::*;
 pub use range::*;
 pub use somr::*;
 pub use state::*;
@@ -26,6 +26,7 @@ pub struct DocStoreStatus {
 /// let doc = DocOptions::new()
 ///     .with_client_id(1)
 ///     .with_guid("guid".into())
+///     .with_offset_kind(y_octo::OffsetKind::Unicode)
 ///     .auto_gc(true)
 ///     .build();
 ///
@@ -36,6 +37,16 @@ pub struct DocOptions {
     pub guid: String,
     pub client_id: u64,
     pub gc: bool,
+    /// The unit of indexes and lengths used by [Text], it's a local option
+    /// and will not be synced to other peers.
+    pub(crate) offset_kind: OffsetKind,
+    /// Whether the doc should be loaded by peers automatically once it's
+    /// added as a subdoc, it's synced with the subdoc item.
+    pub(crate) auto_load: bool,
+    /// Whether the content of the doc is requested, subdocs from remote
+    /// peers are not loaded until [Doc::load] is called unless `auto_load`
+    /// is set.
+    pub(crate) should_load: bool,
 }
 
 impl Default for DocOptions {
@@ -45,12 +56,18 @@ impl Default for DocOptions {
                 client_id: 1,
                 guid: "test".into(),
                 gc: true,
+                offset_kind: OffsetKind::default(),
+                auto_load: false,
+                should_load: true,
             }
         } else {
             Self {
                 client_id: prefer_small_random(),
                 guid: nanoid::nanoid!(),
                 gc: true,
+                offset_kind: OffsetKind::default(),
+                auto_load: false,
+                should_load: true,
             }
         }
     }
@@ -76,6 +93,21 @@ impl DocOptions {
         self
     }
 
+    pub fn with_offset_kind(mut self, offset_kind: OffsetKind) -> Self {
+        self.offset_kind = offset_kind;
+        self
+    }
+
+    pub fn auto_load(mut self, auto_load: bool) -> Self {
+        self.auto_load = auto_load;
+        self
+    }
+
+    pub fn with_should_load(mut self, should_load: bool) -> Self {
+        self.should_load = should_load;
+        self
+    }
+
     pub fn build(self) -> Doc {
         Doc::with_options(self)
     }
@@ -83,10 +115,12 @@ impl DocOptions {
 
 impl From<DocOptions> for Any {
     fn from(value: DocOptions) -> Self {
-        Any::Object(HashMap::from_iter([
-            ("gc".into(), value.gc.into()),
-            ("guid".into(), value.guid.into()),
-        ]))
+        let mut options = HashMap::from_iter([("gc".into(), value.gc.into()), ("guid".into(), value.guid.into())]);
+        if value.auto_load {
+            options.insert("autoLoad".into(), true.into());
+        }
+
+        Any::Object(options)
     }
 }
 
@@ -96,7 +130,9 @@ impl TryFrom<Any> for DocOptions {
     fn try_from(value: Any) -> Result<Self, Self::Error> {
         match value {
             Any::Object(map) => {
-                let mut options = DocOptions::default();
+                // the options come from subdoc items, they're not loaded
+                // until requested, see [Doc::load]
+                let mut options = DocOptions::default().with_should_load(false);
                 for (key, value) in map {
                     match key.as_str() {
                         "gc" => {
@@ -105,9 +141,16 @@ impl TryFrom<Any> for DocOptions {
                         "guid" => {
                             options.guid = String::try_from(value)?;
                         }
+                        "autoLoad" => {
+                            options.auto_load = bool::try_from(value)?;
+                        }
+                        "shouldLoad" => {
+                            options.should_load = bool::try_from(value)?;
+                        }
                         _ => {}
                     }
                 }
+                options.should_load |= options.auto_load;
 
                 Ok(options)
             }
@@ -148,7 +191,10 @@ impl Doc {
     }
 
     pub fn with_options(options: DocOptions) -> Self {
-        let store = Arc::new(RwLock::new(DocStore::with_client(options.client_id)));
+        let mut store = DocStore::with_client(options.client_id);
+        store.offset_kind = options.offset_kind;
+        store.should_load = options.should_load;
+        let store = Arc::new(RwLock::new(store));
         #[cfg(feature = "events")]
         let publisher = Arc::new(DocPublisher::new(store.clone()));
 
@@ -239,8 +285,43 @@ impl Doc {
         self.apply_update(update)
     }
 
-    pub fn apply_update(&mut self, mut update: Update) -> JwstCodecResult {
-        let mut store = self.store.write().unwrap();
+    pub fn try_from_binary_v2<T: AsRef<[u8]>>(binary: T) -> JwstCodecResult<Self> {
+        Self::try_from_binary_v2_with_options(binary, DocOptions::default())
+    }
+
+    pub fn try_from_binary_v2_with_options<T: AsRef<[u8]>>(binary: T, options: DocOptions) -> JwstCodecResult<Self> {
+        let mut doc = Doc::with_options(options);
+        doc.apply_update_from_binary_v2(binary)?;
+        Ok(doc)
+    }
+
+    pub fn apply_update_from_binary_v2<T: AsRef<[u8]>>(&mut self, binary: T) -> JwstCodecResult {
+        let mut decoder = RawDecoderV2::new(binary.as_ref())?;
+        let update = Update::read(&mut decoder)?;
+        self.apply_update(update)
+    }
+
+    pub fn apply_update(&mut self, update: Update) -> JwstCodecResult {
+        self.apply_update_with_origin(update, None)
+    }
+
+    /// Apply the update in a remote transaction with the given origin, so the
+    /// transaction observers can tell where the changes come from.
+    pub fn apply_update_with_origin(&mut self, update: Update, origin: Option<Origin>) -> JwstCodecResult {
+        let mut store = lock_store(&self.store);
+        let scope = store.begin_transaction(origin, false);
+        let result = self.integrate_update(&mut store, update);
+        commit_transaction(store, scope, false);
+        result?;
+
+        if self.opts.gc {
+            self.gc()?;
+        }
+
+        Ok(())
+    }
+
+    fn integrate_update(&self, store: &mut DocStore, mut update: Update) -> JwstCodecResult {
         let mut retry = false;
 
         loop {
@@ -317,18 +398,297 @@ impl Doc {
             }
         }
 
-        if self.opts.gc {
-            store.optimize()?;
-        }
-
         Ok(())
     }
 
+    /// Run `f` in a transaction with the given origin, all changes made in
+    /// `f` are committed together and produce one update.
+    ///
+    /// ```
+    /// use y_octo::Doc;
+    ///
+    /// let doc = Doc::new();
+    /// let mut text = doc.get_or_create_text("text").unwrap();
+    ///
+    /// let transaction = doc.transact_mut(Some("editor".into()));
+    /// text.insert(0, "hello").unwrap();
+    /// text.insert(5, " world").unwrap();
+    /// let committed = transaction.commit().unwrap();
+    ///
+    /// assert_eq!(committed.origin(), Some(&"editor".into()));
+    /// let remote = Doc::try_from_binary_v1(committed.update_v1()).unwrap();
+    /// assert_eq!(remote.get_or_create_text("text").unwrap().to_string(), "hello world");
+    /// ```
+    pub fn transact<T>(&self, origin: Option<Origin>, f: impl FnOnce(&mut TransactionMut) -> T) -> T {
+        let mut transaction = self.transact_mut(origin);
+        // committed when dropped, the update is encoded only if it's observed
+        f(&mut transaction)
+    }
+
+    /// Begin a transaction with the given origin, it's committed by
+    /// [TransactionMut::commit] or when dropped.
+    pub fn transact_mut(&self, origin: Option<Origin>) -> TransactionMut {
+        TransactionMut::new(self.store.clone(), origin, true)
+    }
+
+    /// Observe the updates of the doc, the callback is called synchronously
+    /// with the update in v1 format and the origin of the transaction once a
+    /// transaction or [Doc::apply_update] commits. The callback is removed
+    /// when the returned [Subscription] is dropped.
+    ///
+    /// ```
+    /// use y_octo::Doc;
+    ///
+    /// let doc = Doc::default();
+    /// let remote = Doc::default();
+    /// let mut text = doc.get_or_create_text("text").unwrap();
+    ///
+    /// let sync = remote.clone();
+    /// let subscription = doc.observe_update_v1(move |update, _origin| {
+    ///     sync.clone().apply_update_from_binary_v1(update).unwrap();
+    /// });
+    ///
+    /// text.insert(0, "hello").unwrap();
+    /// assert_eq!(remote.get_or_create_text("text").unwrap().to_string(), "hello");
+    ///
+    /// drop(subscription);
+    /// text.insert(5, " world").unwrap();
+    /// assert_eq!(remote.get_or_create_text("text").unwrap().to_string(), "hello");
+    /// ```
+    pub fn observe_update_v1(&self, f: impl Fn(&[u8], Option<&Origin>) + Send + Sync + 'static) -> Subscription {
+        Subscription::new(
+            &self.store.read().unwrap().update_observers,
+            Arc::new(move |transaction| {
+                // the update is left empty if it failed to encode
+                if !transaction.update.is_empty() {
+                    f(transaction.update_v1(), transaction.origin());
+                }
+            }),
+        )
+    }
+
+    /// Observe the subdocs added, removed or loaded in each transaction, the
+    /// callback is removed when the returned [Subscription] is dropped.
+    ///
+    /// ```
+    /// use std::sync::{Arc, Mutex};
+    ///
+    /// use y_octo::{Doc, DocOptions};
+    ///
+    /// let doc = Doc::default();
+    /// let mut pages = doc.get_or_create_map("pages").unwrap();
+    ///
+    /// let guids = Arc::new(Mutex::new(vec![]));
+    /// let added = guids.clone();
+    /// let _subscription = doc.observe_subdocs(move |event| {
+    ///     added.lock().unwrap().extend(event.added().iter().map(|doc| doc.guid().to_string()));
+    /// });
+    ///
+    /// let page = DocOptions::new().with_guid("page".into()).build();
+    /// pages.insert("page".into(), page).unwrap();
+    /// assert_eq!(*guids.lock().unwrap(), vec!["page".to_string()]);
+    /// ```
+    pub fn observe_subdocs(&self, f: impl Fn(&SubdocsEvent) + Send + Sync + 'static) -> Subscription {
+        Subscription::new(
+            &self.store.read().unwrap().transaction_observers,
+            Arc::new(move |transaction| {
+                if !transaction.subdocs.is_empty() {
+                    f(transaction.subdocs());
+                }
+            }),
+        )
+    }
+
+    /// The subdocs whose items are not deleted
+    pub fn subdocs(&self) -> Vec<Doc> {
+        self.store.read().unwrap().subdocs.values().cloned().collect()
+    }
+
+    pub fn subdoc_guids(&self) -> HashSet<String> {
+        self.store
+            .read()
+            .unwrap()
+            .subdocs
+            .values()
+            .map(|doc| doc.guid().to_owned())
+            .collect()
+    }
+
+    /// Whether the content of the doc is requested, it's `false` for the
+    /// subdocs from remote peers until [Doc::load] is called, unless they're
+    /// created with [DocOptions::auto_load].
+    pub fn should_load(&self) -> bool {
+        self.store.read().unwrap().should_load
+    }
+
+    /// Request the content of the doc, the parent doc reports the subdoc as
+    /// loaded to its [Doc::observe_subdocs] observers so the provider can
+    /// start syncing it.
+    pub fn load(&self) {
+        let parent = {
+            let mut store = self.store.write().unwrap();
+            if store.should_load {
+                return;
+            }
+            store.should_load = true;
+            store.parent.as_ref().and_then(|parent| parent.upgrade())
+        };
+
+        if let Some(parent) = parent {
+            let mut store = lock_store(&parent);
+            let scope = store.begin_transaction(None, true);
+            if let Some(transaction) = &mut store.transaction {
+                transaction.subdocs.loaded.push(self.clone());
+            }
+            commit_transaction(store, scope, false);
+        }
+    }
+
     pub fn keys(&self) -> Vec<String> {
         let store = self.store.read().unwrap();
         store.types.keys().cloned().collect()
     }
 
+    /// Convert all root types into an [Any::Object] keyed by their names, see
+    /// [Value::to_json].
+    ///
+    /// ```
+    /// use y_octo::{Any, Doc};
+    ///
+    /// let doc = Doc::default();
+    /// let mut map = doc.get_or_create_map("map").unwrap();
+    /// map.insert("text".into(), doc.create_text().unwrap()).unwrap();
+    /// map.get("text").unwrap().to_text().unwrap().insert(0, "hello").unwrap();
+    ///
+    /// let json = serde_json::to_value(doc.to_json()).unwrap();
+    /// assert_eq!(json, serde_json::json!({ "map": { "text": "hello" } }));
+    /// ```
+    pub fn to_json(&self) -> Any {
+        let types = self.store.read().unwrap().types.clone();
+
+        Any::Object(
+            types
+                .into_iter()
+                .map(|(name, ty)| (name, Self::root_value(ty).to_json()))
+                .collect(),
+        )
+    }
+
+    /// Create a doc with a root type built from the json value, objects and
+    /// arrays are created as nested shared types recursively, see [Prelim].
+    ///
+    /// The root is a [Map] for objects, an [Array] for arrays or a [Text]
+    /// for strings.
+    pub fn from_json<S: AsRef<str>>(root_name: S, value: Any) -> JwstCodecResult<Doc> {
+        Self::from_json_with_options(root_name, value, DocOptions::default())
+    }
+
+    pub fn from_json_with_options<S: AsRef<str>>(
+        root_name: S,
+        value: Any,
+        options: DocOptions,
+    ) -> JwstCodecResult<Doc> {
+        let doc = Doc::with_options(options);
+        let (prelim, root) = match Prelim::from(value) {
+            prelim @ Prelim::Map(_) => (prelim, Value::Map(doc.get_or_create_map(root_name)?)),
+            prelim @ Prelim::Array(_) => (prelim, Value::Array(doc.get_or_create_array(root_name)?)),
+            Prelim::Any(Any::String(string)) => (Prelim::Text(string), Value::Text(doc.get_or_create_text(root_name)?)),
+            _ => return Err(JwstCodecError::UnexpectedType("Object, Array or String")),
+        };
+        doc.transact(None, |_| prelim.fill(&root))?;
+
+        Ok(doc)
+    }
+
+    /// Get the value at the path, the first segment is the name of the root
+    /// type and the rest are resolved by [Value::at].
+    ///
+    /// ```
+    /// use y_octo::{Doc, Value};
+    ///
+    /// let doc = Doc::default();
+    /// doc.set_path(&["blocks", "abc", "title"], "hello").unwrap();
+    /// assert_eq!(doc.get_path(&["blocks", "abc", "title"]), Some(Value::from("hello")));
+    /// ```
+    pub fn get_path<P: Into<Path>>(&self, path: P) -> Option<Value> {
+        let path = path.into();
+        let (root, path) = path.split_first()?;
+        let ty = self.store.read().unwrap().types.get(&root.to_string()).cloned()?;
+
+        match Self::root_value(ty) {
+            Value::Any(Any::Undefined) => None,
+            value => value.at(path),
+        }
+    }
+
+    /// Set the value at the path in one transaction, the root type and the
+    /// missing intermediate keys are created as maps. The index of arrays
+    /// replaces the existing value, or appends to the array if it equals the
+    /// length. The root types can't be set, so the path must have at least
+    /// two segments.
+    pub fn set_path<P: Into<Path>, V: Into<Value>>(&self, path: P, value: V) -> JwstCodecResult {
+        let path = path.into();
+        // checked before the root is created, so nothing is left behind
+        let Some((root, path)) = path.split_first().filter(|(_, path)| !path.is_empty()) else {
+            return Err(JwstCodecError::RootSetNotSupport);
+        };
+
+        self.transact(None, |_| {
+            let root = match self.get_path(&[root]) {
+                Some(root) => root,
+                None => Value::Map(self.get_or_create_map(root.to_string())?),
+            };
+            root.set_at(path, value.into())
+        })
+    }
+
+    /// Delete the value at the path in one transaction, returns whether the
+    /// value existed.
+    pub fn delete_path<P: Into<Path>>(&self, path: P) -> JwstCodecResult<bool> {
+        let path = path.into();
+        let Some((root, path)) = path.split_first() else {
+            return Ok(false);
+        };
+
+        match self.get_path(&[root]) {
+            Some(root) => self.transact(None, |_| root.delete_at(path)),
+            None => Ok(false),
+        }
+    }
+
+    // the kind of root types from remote is unknown until they're accessed
+    // by name, guess it from the content
+    fn root_value(ty: YTypeRef) -> Value {
+        let kind = ty.ty().map(|inner| {
+            if inner.kind() != YTypeKind::Unknown {
+                return inner.kind();
+            }
+
+            let mut item_ref = inner.start.clone();
+            while let Some(item) = item_ref.get() {
+                match &item.content {
+                    Content::Deleted(_) => item_ref = item.right.clone(),
+                    Content::String(_) | Content::Format { .. } | Content::Embed(_) => return YTypeKind::Text,
+                    _ => return YTypeKind::Array,
+                }
+            }
+
+            if inner.map.is_empty() {
+                YTypeKind::Unknown
+            } else {
+                YTypeKind::Map
+            }
+        });
+
+        match kind {
+            Some(YTypeKind::Array) => Value::Array(Array::from_unchecked(ty)),
+            Some(YTypeKind::Map) => Value::Map(Map::from_unchecked(ty)),
+            Some(YTypeKind::Text) => Value::Text(Text::from_unchecked(ty)),
+            Some(YTypeKind::Unknown) | None => Value::Any(Any::Undefined),
+            Some(_) => Value::from(ty),
+        }
+    }
+
     pub fn get_or_create_text<S: AsRef<str>>(&self, name: S) -> JwstCodecResult<Text> {
         YTypeBuilder::new(self.store.clone())
             .with_kind(YTypeKind::Text)
@@ -364,6 +724,26 @@ impl Doc {
         YTypeBuilder::new(self.store.clone()).with_kind(YTypeKind::Map).build()
     }
 
+    pub fn get_or_create_xml_fragment<S: AsRef<str>>(&self, name: S) -> JwstCodecResult<XMLFragment> {
+        YTypeBuilder::new(self.store.clone())
+            .with_kind(YTypeKind::XMLFragment)
+            .set_name(name.as_ref().to_string())
+            .build()
+    }
+
+    pub fn create_xml_element<S: AsRef<str>>(&self, tag: S) -> JwstCodecResult<XMLElement> {
+        YTypeBuilder::new(self.store.clone())
+            .with_kind(YTypeKind::XMLElement)
+            .set_tag_name(tag.as_ref().to_string())
+            .build()
+    }
+
+    pub fn create_xml_text(&self) -> JwstCodecResult<XMLText> {
+        YTypeBuilder::new(self.store.clone())
+            .with_kind(YTypeKind::XMLText)
+            .build()
+    }
+
     pub fn get_map(&self, str: &str) -> JwstCodecResult<Map> {
         YTypeBuilder::new(self.store.clone())
             .with_kind(YTypeKind::Map)
@@ -383,6 +763,18 @@ impl Doc {
         Ok(encoder.into_inner())
     }
 
+    pub fn encode_update_v2(&self) -> JwstCodecResult<Vec<u8>> {
+        self.encode_state_as_update_v2(&StateVector::default())
+    }
+
+    pub fn encode_state_as_update_v2(&self, sv: &StateVector) -> JwstCodecResult<Vec<u8>> {
+        let update = self.encode_state_as_update(sv)?;
+
+        let mut encoder = RawEncoderV2::default();
+        update.write(&mut encoder)?;
+        encoder.into_inner()
+    }
+
     pub fn encode_update(&self) -> JwstCodecResult<Update> {
         self.encode_state_as_update(&StateVector::default())
     }
@@ -399,6 +791,29 @@ impl Doc {
         self.store.read().unwrap().get_delete_sets()
     }
 
+    pub fn snapshot(&self) -> Snapshot {
+        self.store.read().unwrap().snapshot()
+    }
+
+    /// Create a new doc with the state of given snapshot, just like
+    /// `Y.createDocFromSnapshot`. The doc must be created with
+    /// `auto_gc(false)`, otherwise the deleted content may be lost.
+    pub fn restore_snapshot(&self, snapshot: &Snapshot) -> JwstCodecResult<Doc> {
+        if self.opts.gc {
+            return Err(JwstCodecError::SnapshotGcEnabled);
+        }
+
+        let update = self.store.read().unwrap().snapshot_as_update(snapshot)?;
+
+        let options = DocOptions::new()
+            .with_guid(self.opts.guid.clone())
+            .with_offset_kind(self.opts.offset_kind)
+            .auto_gc(false);
+        Doc::try_from_binary_v1_with_options(update.encode_v1()?, options)
+    }
+
+    /// Subscribe the updates by polling the store in a background thread, it's
+    /// kept for compatibility, use [Doc::observe_update_v1] instead.
     #[cfg(feature = "events")]
     pub fn subscribe(&self, cb: impl Fn(&[u8], &[History]) + Sync + Send + 'static) {
         self.publisher.subscribe(cb);
@@ -419,16 +834,64 @@ impl Doc {
         Arc::<DocPublisher>::strong_count(&self.publisher)
     }
 
+    /// Resolve the current index of a [RelativePosition], returns [None] if
+    /// the anchored item or type doesn't exist in this doc. The index of text
+    /// is measured in [DocOptions::with_offset_kind].
+    ///
+    /// ```
+    /// use y_octo::{Assoc, Doc};
+    ///
+    /// let doc = Doc::default();
+    /// let mut text = doc.get_or_create_text("text").unwrap();
+    /// text.insert(0, "world").unwrap();
+    ///
+    /// let position = text.relative_position(0, Assoc::After).unwrap();
+    /// text.insert(0, "hello ").unwrap();
+    /// assert_eq!(doc.absolute_position(&position).unwrap().index, 6);
+    /// ```
+    pub fn absolute_position(&self, position: &RelativePosition) -> Option<AbsolutePosition> {
+        let (ty, index) = self.store.read().unwrap().resolve_relative_position(position)?;
+        let target = Value::from(ty);
+        let index = match &target {
+            Value::Text(text) => text
+                .convert_offset(index, OffsetKind::Utf16, self.opts.offset_kind)
+                .ok()?,
+            Value::XMLText(text) => Text::from_unchecked(text.0.clone())
+                .convert_offset(index, OffsetKind::Utf16, self.opts.offset_kind)
+                .ok()?,
+            _ => index,
+        };
+
+        Some(AbsolutePosition {
+            target,
+            index,
+            assoc: position.assoc,
+        })
+    }
+
     pub fn gc(&self) -> JwstCodecResult<()> {
-        self.store.write().unwrap().optimize()
+        let mut store = lock_store(&self.store);
+        // the items deleted in the transaction in progress are kept for its
+        // events and undo managers until it's committed, like yjs
+        let result = match store.transaction {
+            Some(_) => Ok(()),
+            None => store.optimize(),
+        };
+        unlock_store(store);
+
+        result
     }
 }
 
 #[cfg(test)]
 mod tests {
-    use yrs::{Array, Map, Options, Transact, types::ToJson, updates::decoder::Decode};
+    use yrs::{
+        Array, GetString, Map, Options, Text, Transact, XmlElementPrelim, XmlFragment, XmlTextPrelim, types::ToJson,
+        updates::decoder::Decode,
+    };
 
     use super::*;
+    use crate::sync::{Mutex, thread};
 
     #[test]
     fn test_encode_state_as_update() {
@@ -704,6 +1167,399 @@ mod tests {
         });
     }
 
+    #[test]
+    fn test_encode_update_v2() {
+        loom_model!({
+            let doc = Doc::new();
+
+            let mut text = doc.get_or_create_text("text").unwrap();
+            text.insert(0, "hello 世界 👋🏻").unwrap();
+            text.remove(2, 2).unwrap();
+
+            let mut root = doc.get_or_create_map("root").unwrap();
+            let mut child = doc.create_map().unwrap();
+            root.insert("child".to_string(), child.clone()).unwrap();
+            child.insert("k".to_string(), "v").unwrap();
+            root.insert("num".to_string(), 42).unwrap();
+
+            let mut array = doc.get_or_create_array("array").unwrap();
+            array.insert(0, true).unwrap();
+            array.insert(1, "value").unwrap();
+            array.remove(0, 1).unwrap();
+
+            let doc_v1 = Doc::try_from_binary_v1(doc.encode_update_v1().unwrap()).unwrap();
+            let doc_v2 = Doc::try_from_binary_v2(doc.encode_update_v2().unwrap()).unwrap();
+
+            assert_eq!(doc_v2.get_or_create_text("text").unwrap().to_string(), "heo 世界 👋🏻");
+            assert_eq!(doc_v1.encode_update_v1().unwrap(), doc_v2.encode_update_v1().unwrap());
+        });
+    }
+
+    #[test]
+    #[cfg_attr(any(miri, loom), ignore)]
+    fn test_apply_update_v2() {
+        let updates = [
+            include_bytes!("../fixtures/basic.bin").to_vec(),
+            include_bytes!("../fixtures/database.bin").to_vec(),
+            include_bytes!("../fixtures/large.bin").to_vec(),
+            include_bytes!("../fixtures/with-subdoc.bin").to_vec(),
+            include_bytes!("../fixtures/edge-case-left-right-same-node.bin").to_vec(),
+        ];
+
+        for update in updates {
+            let doc = Doc::try_from_binary_v1(&update).unwrap();
+            let doc_new = Doc::try_from_binary_v2(doc.encode_update_v2().unwrap()).unwrap();
+
+            assert_eq!(doc.encode_update_v1().unwrap(), doc_new.encode_update_v1().unwrap());
+        }
+    }
+
+    #[test]
+    #[cfg_attr(any(miri, loom), ignore)]
+    fn test_update_v2_compatible_with_yrs() {
+        let binary = {
+            let doc = yrs::Doc::new();
+            let text = doc.get_or_insert_text("text");
+            let map = doc.get_or_insert_map("map");
+            let array = doc.get_or_insert_array("array");
+            let mut trx = doc.transact_mut();
+            text.insert(&mut trx, 0, "hello 世界 👋🏻");
+            text.remove_range(&mut trx, 2, 2);
+            map.insert(&mut trx, "a", 1);
+            map.insert(&mut trx, "b", "c");
+            array.insert(&mut trx, 0, true);
+            array.insert(&mut trx, 1, "value");
+
+            trx.encode_update_v2()
+        };
+
+        let doc = Doc::try_from_binary_v2(&binary).unwrap();
+        assert_eq!(doc.get_or_create_text("text").unwrap().to_string(), "heo 世界 👋🏻");
+        assert_eq!(doc.get_or_create_array("array").unwrap().len(), 2);
+
+        let yrs_doc = yrs::Doc::new();
+        let text = yrs_doc.get_or_insert_text("text");
+        let mut trx = yrs_doc.transact_mut();
+        trx.apply_update(yrs::Update::decode_v2(&doc.encode_update_v2().unwrap()).unwrap())
+            .unwrap();
+
+        assert_eq!(text.get_string(&trx), "heo 世界 👋🏻");
+    }
+
+    #[test]
+    #[cfg_attr(any(miri, loom), ignore)]
+    fn test_xml_fragment_from_yrs() {
+        let (binary, expected) = {
+            let doc = yrs::Doc::new();
+            let fragment = doc.get_or_insert_xml_fragment("prosemirror");
+            let mut trx = doc.transact_mut();
+            let paragraph = fragment.push_back(&mut trx, XmlElementPrelim::empty("paragraph"));
+            paragraph.insert_attribute(&mut trx, "align", "left");
+            paragraph.push_back(&mut trx, XmlTextPrelim::new("hello"));
+
+            (trx.encode_update_v1(), fragment.get_string(&trx))
+        };
+
+        let doc = Doc::try_from_binary_v1(&binary).unwrap();
+        let mut fragment = doc.get_or_create_xml_fragment("prosemirror").unwrap();
+        assert_eq!(fragment.to_string(), expected);
+
+        let mut heading = doc.create_xml_element("heading").unwrap();
+        fragment.insert(0, heading.clone()).unwrap();
+        heading.push(doc.create_xml_text().unwrap()).unwrap();
+        assert_eq!(
+            fragment.to_string(),
+            r#"<heading></heading><paragraph align="left">hello</paragraph>"#
+        );
+        assert!(doc.get_or_create_text("prosemirror").is_err());
+    }
+
+    #[test]
+    fn test_restore_snapshot() {
+        loom_model!({
+            let doc = DocOptions::new().auto_gc(false).build();
+            let mut text = doc.get_or_create_text("text").unwrap();
+            let mut map = doc.get_or_create_map("map").unwrap();
+
+            text.insert(0, "hello").unwrap();
+            map.insert("a".to_string(), 1).unwrap();
+            let snapshot1 = doc.snapshot();
+
+            text.insert(5, " world").unwrap();
+            text.remove(0, 6).unwrap();
+            map.insert("a".to_string(), 2).unwrap();
+            map.insert("b".to_string(), 3).unwrap();
+            let snapshot2 = doc.snapshot();
+
+            text.remove(0, 5).unwrap();
+            map.remove("b");
+
+            let restored = doc.restore_snapshot(&snapshot1).unwrap();
+            assert_eq!(restored.get_or_create_text("text").unwrap().to_string(), "hello");
+            let restored_map = restored.get_or_create_map("map").unwrap();
+            assert_eq!(restored_map.get("a"), Some(Value::Any(Any::Integer(1))));
+            assert_eq!(restored_map.len(), 1);
+
+            let restored = doc.restore_snapshot(&snapshot2).unwrap();
+            assert_eq!(restored.get_or_create_text("text").unwrap().to_string(), "world");
+            let restored_map = restored.get_or_create_map("map").unwrap();
+            assert_eq!(restored_map.get("a"), Some(Value::Any(Any::Integer(2))));
+            assert_eq!(restored_map.get("b"), Some(Value::Any(Any::Integer(3))));
+
+            let snapshot = Snapshot::decode_v1(snapshot2.encode_v1().unwrap()).unwrap();
+            assert_eq!(restored.snapshot().state_vector, snapshot.state_vector);
+
+            assert_eq!(
+                Doc::new().restore_snapshot(&snapshot1).unwrap_err(),
+                JwstCodecError::SnapshotGcEnabled
+            );
+        });
+    }
+
+    #[test]
+    fn test_transact() {
+        loom_model!({
+            let doc = DocOptions::new().with_client_id(1).build();
+            let mut text = doc.get_or_create_text("text").unwrap();
+            let mut map = doc.get_or_create_map("map").unwrap();
+            text.insert(0, "hello").unwrap();
+            let initial = doc.encode_update_v1().unwrap();
+            let before_state = doc.get_state_vector();
+
+            let transaction = doc.transact_mut(Some("editor".into()));
+            text.insert(5, " world").unwrap();
+            text.remove(0, 1).unwrap();
+            map.insert("key".into(), 1).unwrap();
+            // nested transaction joins the outer one
+            let origin = doc.transact(None, |transaction| {
+                map.insert("key".into(), 2).unwrap();
+                transaction.origin()
+            });
+            assert_eq!(origin, Some("editor".into()));
+            assert_eq!(transaction.before_state(), before_state);
+            let committed = transaction.commit().unwrap();
+
+            assert_eq!(committed.origin(), Some(&"editor".into()));
+            assert!(committed.is_local());
+            assert_eq!(committed.before_state(), &before_state);
+            assert_eq!(committed.after_state(), &doc.get_state_vector());
+            assert!(committed.delete_set().contains(&Id::new(1, 0)));
+
+            // nothing changed
+            assert!(doc.transact_mut(None).commit().is_none());
+
+            let mut remote = DocOptions::new().with_client_id(2).build();
+            let origins = Arc::new(Mutex::new(Vec::new()));
+            let origins_ref = origins.clone();
+            remote
+                .store
+                .read()
+                .unwrap()
+                .transaction_observers
+                .observe(Arc::new(move |transaction| {
+                    origins_ref
+                        .lock()
+                        .unwrap()
+                        .push((transaction.origin().cloned(), transaction.is_local()));
+                }));
+
+            remote.apply_update_from_binary_v1(initial).unwrap();
+            remote
+                .apply_update_with_origin(Update::decode_v1(committed.update_v1()).unwrap(), Some("sync".into()))
+                .unwrap();
+
+            assert_eq!(remote.get_or_create_text("text").unwrap().to_string(), "ello world");
+            assert_eq!(
+                remote.get_or_create_map("map").unwrap().get("key"),
+                Some(Value::Any(Any::Integer(2)))
+            );
+            assert_eq!(
+                *origins.lock().unwrap(),
+                vec![(None, false), (Some("sync".into()), false)]
+            );
+        });
+    }
+
+    #[test]
+    fn test_observe_update_v1() {
+        loom_model!({
+            let doc = DocOptions::new().with_client_id(1).build();
+            let mut remote = DocOptions::new().with_client_id(2).build();
+            let mut map = doc.get_or_create_map("map").unwrap();
+
+            let updates = Arc::new(Mutex::new(Vec::new()));
+            let updates_ref = updates.clone();
+            let subscription = doc.observe_update_v1(move |update, origin| {
+                updates_ref.lock().unwrap().push((update.to_vec(), origin.cloned()));
+            });
+
+            map.insert("a".into(), 1).unwrap();
+            doc.transact(Some("editor".into()), |_| {
+                map.insert("b".into(), 2).unwrap();
+                map.insert("c".into(), 3).unwrap();
+            });
+            // observers aren't called if nothing changed
+            doc.transact(None, |_| {});
+
+            let received = std::mem::take(&mut *updates.lock().unwrap());
+            assert_eq!(received.len(), 2);
+            assert_eq!(received[0].1, None);
+            assert_eq!(received[1].1, Some("editor".into()));
+            for (update, _) in received {
+                remote.apply_update_from_binary_v1(update).unwrap();
+            }
+            assert_eq!(remote.get_or_create_map("map").unwrap().len(), 3);
+
+            // updates applied from remote are reported with their origin
+            let mut remote_map = remote.get_or_create_map("map").unwrap();
+            remote_map.insert("d".into(), 4).unwrap();
+            let update = Update::decode_v1(remote.encode_update_v1().unwrap()).unwrap();
+            let mut doc_ref = doc.clone();
+            doc_ref.apply_update_with_origin(update, Some("sync".into())).unwrap();
+            {
+                let received = updates.lock().unwrap();
+                assert_eq!(received.len(), 1);
+                assert_eq!(received[0].1, Some("sync".into()));
+            }
+            assert_eq!(map.get("d"), Some(Value::Any(Any::Integer(4))));
+
+            drop(subscription);
+            map.insert("e".into(), 5).unwrap();
+            assert_eq!(updates.lock().unwrap().len(), 1);
+        });
+    }
+
+    #[test]
+    fn test_remote_update_in_local_transaction() {
+        loom_model!({
+            let doc = DocOptions::new().with_client_id(1).build();
+            let remote = DocOptions::new().with_client_id(2).build();
+            let mut text = doc.get_or_create_text("text").unwrap();
+            let map = doc.get_or_create_map("map").unwrap();
+            remote
+                .get_or_create_map("map")
+                .unwrap()
+                .insert("key".into(), 1)
+                .unwrap();
+            let update = Update::decode_v1(remote.encode_update_v1().unwrap()).unwrap();
+
+            let updates = Arc::new(Mutex::new(Vec::new()));
+            let updates_ref = updates.clone();
+            let _update_subscription = doc.observe_update_v1(move |update, origin| {
+                updates_ref.lock().unwrap().push((update.to_vec(), origin.cloned()));
+            });
+            let events = Arc::new(Mutex::new(Vec::new()));
+            let text_events = events.clone();
+            let _text_subscription = text.observe(move |event| {
+                text_events
+                    .lock()
+                    .unwrap()
+                    .push(("text", event.origin().cloned(), event.is_local()));
+            });
+            let map_events = events.clone();
+            let _map_subscription = map.observe(move |event| {
+                map_events
+                    .lock()
+                    .unwrap()
+                    .push(("map", event.origin().cloned(), event.is_local()));
+            });
+
+            // the remote update is applied in a transaction of its own
+            let transaction = doc.transact_mut(Some("editor".into()));
+            text.insert(0, "hello").unwrap();
+            doc.clone()
+                .apply_update_with_origin(update, Some("sync".into()))
+                .unwrap();
+            text.insert(5, " world").unwrap();
+            let committed = transaction.commit().unwrap();
+
+            assert_eq!(committed.origin(), Some(&"editor".into()));
+            assert!(committed.is_local());
+            assert_eq!(
+                *events.lock().unwrap(),
+                vec![
+                    ("map", Some("sync".into()), false),
+                    ("text", Some("editor".into()), true)
+                ]
+            );
+
+            let updates = std::mem::take(&mut *updates.lock().unwrap());
+            assert_eq!(
+                updates.iter().map(|(_, origin)| origin.clone()).collect::<Vec<_>>(),
+                vec![Some("sync".into()), Some("editor".into())]
+            );
+            // the local update doesn't echo the remote changes
+            let local = Doc::try_from_binary_v1(&updates[1].0).unwrap();
+            assert_eq!(local.get_or_create_text("text").unwrap().to_string(), "hello world");
+            assert_eq!(local.get_or_create_map("map").unwrap().get("key"), None);
+            assert_eq!(updates[1].0, committed.update_v1());
+        });
+    }
+
+    #[test]
+    fn test_transaction_owned_by_thread() {
+        loom_model!({
+            let doc = DocOptions::new().with_client_id(1).build();
+            let mut text = doc.get_or_create_text("text").unwrap();
+            let mut map = doc.get_or_create_map("map").unwrap();
+
+            let transaction = doc.transact_mut(Some("editor".into()));
+            text.insert(0, "hello").unwrap();
+            // the changes of other threads wait for the transaction to commit
+            let handle = thread::spawn(move || map.insert("key".into(), 1).unwrap());
+            #[cfg(not(loom))]
+            {
+                thread::sleep(std::time::Duration::from_millis(50));
+                assert!(!handle.is_finished());
+            }
+
+            let committed = transaction.commit().unwrap();
+            handle.join().unwrap();
+
+            let local = Doc::try_from_binary_v1(committed.update_v1()).unwrap();
+            assert_eq!(local.get_or_create_text("text").unwrap().to_string(), "hello");
+            assert_eq!(local.get_or_create_map("map").unwrap().get("key"), None);
+            assert_eq!(
+                doc.get_or_create_map("map").unwrap().get("key"),
+                Some(Value::Any(Any::Integer(1)))
+            );
+        });
+    }
+
+    #[test]
+    fn test_gc_after_transaction() {
+        loom_model!({
+            let mut doc = DocOptions::new().with_client_id(1).build();
+            let mut map = doc.get_or_create_map("map").unwrap();
+            map.insert("key".into(), "value").unwrap();
+
+            let changes = Arc::new(Mutex::new(Vec::new()));
+            let changes_ref = changes.clone();
+            let _subscription = map.observe(move |event| {
+                changes_ref.lock().unwrap().push(event.keys_changed().clone());
+            });
+
+            let remote = DocOptions::new().with_client_id(2).build();
+            remote.get_or_create_text("text").unwrap().insert(0, "hello").unwrap();
+            {
+                let _transaction = doc.transact_mut(None);
+                map.remove("key");
+                // the remote update is applied in a nested transaction, the
+                // items deleted by the outer one are not gc'd until it's committed
+                doc.apply_update_from_binary_v1(remote.encode_update_v1().unwrap())
+                    .unwrap();
+            }
+
+            assert_eq!(
+                *changes.lock().unwrap(),
+                vec![HashMap::from_iter([(
+                    "key".to_string(),
+                    EntryChange::Removed(Value::from("value"))
+                )])]
+            );
+        });
+    }
+
     #[test]
     #[cfg_attr(any(miri, loom), ignore)]
     fn test_apply_update() {
@@ -720,4 +1576,217 @@ mod tests {
             doc.apply_update_from_binary_v1(&update).unwrap();
         }
     }
+
+    // the guids of added, removed and loaded subdocs in each event
+    type SubdocsEvents = Arc<Mutex<Vec<[Vec<String>; 3]>>>;
+
+    fn collect_subdocs(doc: &Doc) -> (SubdocsEvents, Subscription) {
+        let events = Arc::new(Mutex::new(Vec::new()));
+        let events_ref = events.clone();
+        let subscription = doc.observe_subdocs(move |event| {
+            let guids = |docs: &[Doc]| docs.iter().map(|doc| doc.guid().to_string()).collect::<Vec<_>>();
+            events_ref
+                .lock()
+                .unwrap()
+                .push([guids(event.added()), guids(event.removed()), guids(event.loaded())]);
+        });
+
+        (events, subscription)
+    }
+
+    #[test]
+    fn test_subdocs() {
+        loom_model!({
+            let doc = DocOptions::new().with_client_id(1).build();
+            let mut pages = doc.get_or_create_map("pages").unwrap();
+            let (events, _subscription) = collect_subdocs(&doc);
+
+            let page1 = DocOptions::new().with_guid("page1".into()).build();
+            let page2 = DocOptions::new().with_guid("page2".into()).auto_load(true).build();
+            doc.transact(None, |_| {
+                pages.insert("page1".into(), page1.clone()).unwrap();
+                pages.insert("page2".into(), page2).unwrap();
+            });
+            assert_eq!(doc.subdoc_guids(), HashSet::from_iter(["page1".into(), "page2".into()]));
+            // the subdoc is the same instance as the inserted one
+            let Some(Value::Doc(page)) = pages.get("page1") else {
+                panic!("expect a subdoc");
+            };
+            assert!(Arc::ptr_eq(&page.store, &page1.store));
+
+            let mut remote = DocOptions::new().with_client_id(2).build();
+            let (remote_events, _remote_subscription) = collect_subdocs(&remote);
+            remote
+                .apply_update_from_binary_v1(doc.encode_update_v1().unwrap())
+                .unwrap();
+            let remote_pages = remote.get_or_create_map("pages").unwrap();
+            let Some(Value::Doc(remote_page1)) = remote_pages.get("page1") else {
+                panic!("expect a subdoc");
+            };
+            // subdocs from remote are not loaded unless auto load is set
+            assert!(!remote_page1.should_load());
+            assert!(
+                remote
+                    .subdocs()
+                    .iter()
+                    .any(|doc| doc.guid() == "page2" && doc.should_load())
+            );
+
+            remote_page1.load();
+            // loading twice is a no-op
+            remote_page1.load();
+            assert!(remote_page1.should_load());
+            {
+                let mut events = remote_events.lock().unwrap();
+                assert_eq!(events.len(), 2);
+                events[0][0].sort();
+                assert_eq!(events[0][0], vec!["page1".to_string(), "page2".to_string()]);
+                assert_eq!(events[0][2], vec!["page2".to_string()]);
+                assert_eq!(events[1], [vec![], vec![], vec!["page1".to_string()]]);
+            }
+
+            pages.remove("page1");
+            assert_eq!(doc.subdoc_guids(), HashSet::from_iter(["page2".into()]));
+            // the subdoc inserted and deleted in the same transaction is not reported
+            doc.transact(None, |_| {
+                pages.insert("page3".into(), Doc::default()).unwrap();
+                pages.remove("page3");
+            });
+
+            let mut events = events.lock().unwrap();
+            assert_eq!(events.len(), 2);
+            events[0][0].sort();
+            events[0][2].sort();
+            assert_eq!(events[0][0], vec!["page1".to_string(), "page2".to_string()]);
+            assert_eq!(events[0][2], vec!["page1".to_string(), "page2".to_string()]);
+            assert_eq!(events[1], [vec![], vec!["page1".to_string()], vec![]]);
+        });
+    }
+
+    #[test]
+    fn test_insert_doc_into_itself() {
+        loom_model!({
+            let doc = DocOptions::new().with_client_id(1).build();
+            let mut pages = doc.get_or_create_map("pages").unwrap();
+            assert_eq!(
+                pages.insert("self".into(), doc.clone()),
+                Err(JwstCodecError::InvalidSubdoc)
+            );
+            assert!(pages.is_empty());
+
+            let page = DocOptions::new().with_client_id(2).build();
+            pages.insert("page".into(), page.clone()).unwrap();
+            let mut children = page.get_or_create_array("children").unwrap();
+            assert_eq!(children.push(doc.clone()), Err(JwstCodecError::InvalidSubdoc));
+            assert!(children.is_empty());
+            assert_eq!(doc.subdocs().len(), 1);
+            assert!(page.subdocs().is_empty());
+        });
+    }
+
+    #[test]
+    #[cfg_attr(any(miri, loom), ignore)]
+    fn test_subdocs_from_binary() {
+        let mut doc = Doc::new();
+        let (events, _subscription) = collect_subdocs(&doc);
+        doc.apply_update_from_binary_v1(include_bytes!("../fixtures/with-subdoc.bin"))
+            .unwrap();
+
+        // the guid of the subdoc item takes precedence over the one in options
+        let guid = "space:iNH19Un-EM-hello-world".to_string();
+        assert_eq!(doc.subdoc_guids(), HashSet::from_iter([guid.clone()]));
+        let subdoc = doc.subdocs().pop().unwrap();
+        assert!(!subdoc.should_load());
+
+        let mut spaces = doc.get_map("spaces").unwrap();
+        let key = spaces.keys().next().unwrap().to_string();
+        subdoc.load();
+        assert_eq!(
+            spaces.get(&key).and_then(|value| match value {
+                Value::Doc(doc) => Some(doc.should_load()),
+                _ => None,
+            }),
+            Some(true)
+        );
+
+        spaces.remove(&key);
+        assert!(doc.subdocs().is_empty());
+        assert_eq!(
+            *events.lock().unwrap(),
+            vec![
+                [vec![guid.clone()], vec![], vec![]],
+                [vec![], vec![], vec![guid.clone()]],
+                [vec![], vec![guid], vec![]],
+            ]
+        );
+    }
+
+    #[test]
+    fn test_to_json() {
+        loom_model!({
+            let doc = DocOptions::new().with_client_id(1).build();
+            let mut map = doc.get_or_create_map("map").unwrap();
+            let mut array = doc.get_or_create_array("array").unwrap();
+            let mut text = doc.get_or_create_text("text").unwrap();
+            let mut fragment = doc.get_or_create_xml_fragment("xml").unwrap();
+
+            text.insert(0, "hello").unwrap();
+            map.insert("number".into(), 1).unwrap();
+            map.insert("nested".into(), doc.create_map().unwrap()).unwrap();
+            let mut nested = map.get("nested").unwrap().to_map().unwrap();
+            nested.insert("list".into(), doc.create_array().unwrap()).unwrap();
+            nested.get("list").unwrap().to_array().unwrap().push("a").unwrap();
+            nested
+                .insert("page".into(), DocOptions::new().with_guid("page".into()).build())
+                .unwrap();
+
+            array.push(true).unwrap();
+            array.push(doc.create_text().unwrap()).unwrap();
+            array.get(1).unwrap().to_text().unwrap().insert(0, "world").unwrap();
+
+            let mut paragraph = doc.create_xml_element("p").unwrap();
+            fragment.push(paragraph.clone()).unwrap();
+            paragraph.insert_attribute("align".into(), "left").unwrap();
+            paragraph.push(doc.create_xml_text().unwrap()).unwrap();
+            let Some(Value::XMLText(mut xml_text)) = paragraph.get(0) else {
+                panic!("expect xml text");
+            };
+            xml_text.insert(0, "content").unwrap();
+
+            let expected = HashMap::<String, Any>::from_iter([
+                (
+                    "map".into(),
+                    Any::Object(HashMap::from_iter([
+                        ("number".into(), Any::Integer(1)),
+                        (
+                            "nested".into(),
+                            Any::Object(HashMap::from_iter([
+                                ("list".into(), Any::Array(vec![Any::String("a".into())])),
+                                ("page".into(), Any::String("page".into())),
+                            ])),
+                        ),
+                    ])),
+                ),
+                ("array".into(), Any::Array(vec![Any::True, Any::String("world".into())])),
+                ("text".into(), Any::String("hello".into())),
+                ("xml".into(), Any::String("<p align=\"left\">content</p>".into())),
+            ]);
+            assert_eq!(doc.to_json(), Any::Object(expected.clone()));
+
+            // root types from remote are not accessed yet
+            let remote = Doc::try_from_binary_v1(doc.encode_update_v1().unwrap()).unwrap();
+            let Any::Object(roots) = remote.to_json() else {
+                panic!("expect an object");
+            };
+            for name in ["map", "array", "text"] {
+                assert_eq!(roots.get(name), expected.get(name));
+            }
+
+            #[cfg(feature = "serde_json")]
+            assert_eq!(
+                serde_json::Value::from(remote.get_or_create_array("array").unwrap().get(0).unwrap().to_json()),
+                serde_json::json!(true)
+            );
+        });
+    }
 }
@@ -8,7 +8,9 @@ mod history;
 #[cfg(feature = "events")]
 mod publisher;
 mod store;
+mod transaction;
 mod types;
+mod undo;
 mod utils;
 
 pub use ahash::{HashMap, HashMapExt, HashSet, HashSetExt};
@@ -21,7 +23,13 @@ pub use hasher::ClientMap;
 pub use history::{History, HistoryOptions, StoreHistory};
 use smol_str::SmolStr;
 pub(crate) use store::DocStore;
+pub(crate) use transaction::{
+    Observer, Observers, TransactionObservers, TransactionOwner, TransactionScope, commit_transaction, lock_store,
+    unlock_store,
+};
+pub use transaction::{Origin, SubdocsEvent, Subscription, Transaction, TransactionMut};
 pub use types::*;
+pub use undo::{UndoManager, UndoManagerOptions};
 pub use utils::*;
 
 use super::*;
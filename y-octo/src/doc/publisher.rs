@@ -26,22 +26,13 @@ impl DocPublisher {
         let history = StoreHistory::new(&store);
         history.resolve();
 
-        let publisher = Self {
+        Self {
             store,
             history,
             subscribers,
             observer: Arc::default(),
             observing: Arc::new(AtomicBool::new(false)),
-        };
-
-        if cfg!(all(
-            feature = "subscribe",
-            not(any(feature = "bench", fuzzing, loom, miri))
-        )) {
-            publisher.start();
         }
-
-        publisher
     }
 
     pub fn start(&self) {
@@ -148,10 +139,19 @@ impl DocPublisher {
 
     pub(crate) fn subscribe(&self, subscriber: impl Fn(&[u8], &[History]) + Send + Sync + 'static) {
         self.subscribers.write().unwrap().push(Box::new(subscriber));
+
+        // the polling thread is started only once someone subscribes
+        if cfg!(all(
+            feature = "subscribe",
+            not(any(feature = "bench", fuzzing, loom, miri))
+        )) {
+            self.start();
+        }
     }
 
     pub(crate) fn unsubscribe_all(&self) {
         self.subscribers.write().unwrap().clear();
+        self.stop();
     }
 }
 
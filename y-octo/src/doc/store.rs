@@ -31,6 +31,23 @@ pub(crate) struct DocStore {
     pub last_optimized_state: StateVector,
     // changed item's parent, value is the parent's sub key if exists
     pub changed: ChangedTypeRefs,
+    // the unit of text indexes, see [DocOptions::with_offset_kind]
+    pub offset_kind: OffsetKind,
+    // the transaction in progress, changes are recorded into it
+    pub transaction: Option<Transaction>,
+    // the thread making changes, see [lock_store]
+    pub owner: Arc<TransactionOwner>,
+    pub transaction_observers: TransactionObservers,
+    // the observers of encoded updates, see [Doc::observe_update_v1]
+    pub update_observers: TransactionObservers,
+    // the event deltas not read yet, see [DocStore::build_pending_deltas]
+    pub pending_deltas: Vec<Weak<dyn PendingDelta>>,
+    // subdocs integrated into this store, keyed by the id of their items
+    pub subdocs: HashMap<Id, Doc>,
+    // whether the content of this doc is requested, see [Doc::load]
+    pub should_load: bool,
+    // the store of the parent doc if this is a subdoc
+    pub parent: Option<WeakStoreRef>,
 }
 
 pub(crate) type StoreRef = Arc<RwLock<DocStore>>;
@@ -106,10 +123,136 @@ impl DocStore {
         Self::items_as_state_vector(&self.items)
     }
 
+    /// Begin a transaction for the changes, the local changes join the
+    /// transaction in progress, otherwise the transaction in progress is put
+    /// aside until the new one is committed. The store should be locked by
+    /// [lock_store], so the transaction in progress is always began by the
+    /// current thread.
+    pub fn begin_transaction(&mut self, origin: Option<Origin>, local: bool) -> TransactionScope {
+        let outer = match self.transaction.take() {
+            Some(transaction) if local => {
+                self.transaction = Some(transaction);
+                return TransactionScope::Joined;
+            }
+            Some(mut transaction) => {
+                transaction.after_state = self.get_state_vector();
+                Some(Box::new((transaction, mem::take(&mut self.changed))))
+            }
+            None => None,
+        };
+
+        let mut transaction = Transaction::new(origin, local, self.get_state_vector());
+        // record the types changed in this transaction separately
+        mem::swap(&mut transaction.changed, &mut self.changed);
+        self.transaction = Some(transaction);
+
+        match outer {
+            Some(outer) => TransactionScope::Nested(outer),
+            None => TransactionScope::New,
+        }
+    }
+
+    /// Bring back the transaction put aside by a nested one, the changes
+    /// made in the nested transaction are excluded from it.
+    pub fn restore_transaction(&mut self, outer: Box<(Transaction, ChangedTypeRefs)>) {
+        let (mut transaction, changed) = *outer;
+        for (client, clock) in self.get_state_vector().iter() {
+            // the clients which had no items inserted by the outer transaction
+            if transaction.before_state.get(client) == transaction.after_state.get(client) {
+                transaction.before_state.set_max(*client, *clock);
+            }
+        }
+        // the types changed by the nested transaction are kept in the store
+        // level record only
+        for (ty, keys) in mem::replace(&mut self.changed, changed) {
+            transaction.changed.entry(ty).or_default().extend(keys);
+        }
+
+        self.transaction = Some(transaction);
+    }
+
+    /// Take the transaction in progress, returns [None] if nothing changed
+    /// in it. The update is encoded only if it's requested or observed.
+    pub fn commit_transaction(&mut self, encode_update: bool) -> Option<Transaction> {
+        let mut transaction = self.transaction.take()?;
+        mem::swap(&mut transaction.changed, &mut self.changed);
+        for (ty, keys) in &transaction.changed {
+            self.changed.entry(ty.clone()).or_default().extend(keys.iter().cloned());
+        }
+        transaction.after_state = self.get_state_vector();
+        if transaction.is_empty() {
+            return None;
+        }
+
+        // nothing to sync if only subdocs are loaded
+        if transaction.before_state == transaction.after_state && transaction.delete_set.is_empty()
+            || !encode_update && self.update_observers.is_empty()
+        {
+            return Some(transaction);
+        }
+
+        let update = Self::diff_structs(&self.items, &transaction.before_state).and_then(|structs| {
+            Update {
+                structs,
+                delete_set: transaction.delete_set.clone(),
+                ..Update::default()
+            }
+            .encode_v1()
+        });
+        match update {
+            Ok(update) => transaction.update = update,
+            Err(e) => warn!("failed to encode the update of transaction: {e}"),
+        }
+
+        Some(transaction)
+    }
+
     pub fn get_delete_sets(&self) -> DeleteSet {
         self.delete_set.clone()
     }
 
+    pub fn snapshot(&self) -> Snapshot {
+        Snapshot::new(self.get_state_vector(), Self::generate_delete_set(&self.items))
+    }
+
+    /// Collect all structs before the state vector of given snapshot,
+    /// the struct on the boundary will be splitted.
+    pub fn snapshot_as_update(&self, snapshot: &Snapshot) -> JwstCodecResult<Update> {
+        let mut structs = ClientMap::new();
+
+        for (client, clock) in snapshot.state_vector.iter() {
+            let (client, clock) = (*client, *clock);
+            if clock == 0 {
+                continue;
+            }
+
+            if let Some(items) = self.items.get(&client) {
+                let mut nodes = VecDeque::new();
+                for node in items {
+                    if node.clock() >= clock {
+                        break;
+                    }
+
+                    if node.clock() + node.len() > clock {
+                        nodes.push_back(node.split_at(clock - node.clock())?.0);
+                    } else {
+                        nodes.push_back(node.clone());
+                    }
+                }
+
+                if !nodes.is_empty() {
+                    structs.insert(client, nodes);
+                }
+            }
+        }
+
+        Ok(Update {
+            structs,
+            delete_set: snapshot.delete_set.clone(),
+            ..Update::default()
+        })
+    }
+
     fn items_as_state_vector(items: &ClientMap<VecDeque<Node>>) -> StateVector {
         let mut state = StateVector::default();
         for (client, structs) in items.iter() {
@@ -122,6 +265,77 @@ impl DocStore {
         state
     }
 
+    /// Resolve the type and index of a relative position, like
+    /// `createAbsolutePositionFromRelativePosition` in yjs, the index is
+    /// measured in the length of items.
+    pub fn resolve_relative_position(&self, position: &RelativePosition) -> Option<(YTypeRef, u64)> {
+        if let Some(id) = position.item {
+            if self.get_state(id.client) <= id.clock {
+                return None;
+            }
+
+            let node = self.get_node(id)?;
+            let right = node.as_item();
+            let right = right.get()?;
+            let Some(Parent::Type(parent)) = &right.parent else {
+                return None;
+            };
+
+            let mut index = 0;
+            let parent_deleted = parent
+                .ty()
+                .and_then(|ty| ty.item.get().map(|item| item.deleted()))
+                .unwrap_or(false);
+            if !parent_deleted && let Some(parent) = parent.ty() {
+                if right.indexable() {
+                    index = id.clock - right.id.clock;
+                    if position.assoc == Assoc::Before {
+                        index += 1;
+                    }
+                }
+
+                // count the items shown before it
+                for item in ItemWalker::new(parent.start.clone()) {
+                    let Some(item) = item.get() else {
+                        break;
+                    };
+                    if item.id == right.id {
+                        break;
+                    }
+                    if item.indexable() {
+                        index += item.len();
+                    }
+                }
+            }
+
+            Some((parent.clone(), index))
+        } else {
+            let ty = if let Some(root_name) = &position.root_name {
+                self.types.get(root_name).cloned()?
+            } else if let Some(id) = position.type_id {
+                if self.get_state(id.client) <= id.clock {
+                    return None;
+                }
+
+                let node = self.get_node(id)?;
+                let item = node.as_item();
+                match &item.get()?.content {
+                    Content::Type(ty) => ty.clone(),
+                    _ => return None,
+                }
+            } else {
+                return None;
+            };
+
+            let index = match position.assoc {
+                Assoc::After => ty.ty()?.len,
+                Assoc::Before => 0,
+            };
+
+            Some((ty, index))
+        }
+    }
+
     pub fn add_node(&mut self, item: Node) -> JwstCodecResult {
         let client_id = item.client();
         match self.items.entry(client_id) {
@@ -465,6 +679,10 @@ impl DocStore {
                 }
 
                 if let Some(Parent::Type(ty)) = &this.parent {
+                    if let Some(doc) = this.content.subdoc() {
+                        Self::check_subdoc(&doc, &ty.store, &self.parent)?;
+                    }
+
                     let mut parent_lock: Option<RwLockWriteGuard<YType>> = None;
                     let parent = if let Some(p) = parent {
                         p
@@ -580,6 +798,27 @@ impl DocStore {
                     }
                     this.right = right.clone();
 
+                    // the item is shown by the same move as the left one
+                    if this.parent_sub.is_none()
+                        && let Some(left) = this.left.get()
+                    {
+                        this.moved = left.moved.clone();
+                    }
+
+                    if let Some(doc) = this.content.subdoc() {
+                        self.add_subdoc(this.id, doc, &ty.store);
+                    }
+
+                    let is_move = matches!(this.content, Content::Move(_));
+                    if is_move && !this.deleted() {
+                        // the search markers walk the items in the order they're
+                        // inserted, they're not used until the moves are deleted
+                        parent.moves += 1;
+                        if let Some(markers) = &parent.markers {
+                            markers.borrow_mut().clear();
+                        }
+                    }
+
                     let parent_deleted = parent.item.get().map(|item| item.deleted()).unwrap_or(false);
 
                     // should delete
@@ -589,13 +828,18 @@ impl DocStore {
                         // adjust parent length
                         if this.parent_sub.is_none() && this.countable() {
                             parent.len += this.len();
+                            parent.offset_len += self.offset_kind.content_len(&this.content);
                         }
                     }
 
-                    parent_lock.take();
-
                     // mark changed item's parent
                     Self::mark_changed(&mut self.changed, ty.clone(), this.parent_sub.clone());
+
+                    if is_move && !this.deleted() {
+                        self.integrate_move(&item_owner_ref.clone(), Some(parent));
+                    }
+
+                    parent_lock.take();
                 } else {
                     // if parent not exists, integrate GC node instead
                     // don't delete it because it may referenced by other nodes
@@ -616,19 +860,298 @@ impl DocStore {
         self.add_node(node)
     }
 
-    pub fn delete_item(&mut self, item: &Item, parent: Option<&mut YType>) {
+    pub fn delete_item(&mut self, item: &Item, mut parent: Option<&mut YType>) {
         let mut pending_delete_sets = HashMap::new();
-        Self::delete_item_inner(&mut pending_delete_sets, &mut self.changed, item, parent);
+        let mut removed_subdocs = Vec::new();
+        let mut removed_moves = Vec::new();
+        Self::delete_item_inner(
+            &mut pending_delete_sets,
+            &mut removed_subdocs,
+            &mut removed_moves,
+            &mut self.changed,
+            self.offset_kind,
+            item,
+            parent.as_deref_mut(),
+        );
+        self.add_delete_sets(pending_delete_sets);
+        self.remove_subdocs(removed_subdocs);
+        self.remove_moves(removed_moves, parent);
+    }
+
+    fn add_delete_sets(&mut self, pending_delete_sets: HashMap<u64, Vec<Range<u64>>>) {
         for (client, ranges) in pending_delete_sets {
+            if let Some(transaction) = &mut self.transaction {
+                transaction.delete_set.batch_add_ranges(client, ranges.clone());
+            }
             self.delete_set.batch_add_ranges(client, ranges);
         }
     }
 
+    /// A doc can't be inserted into itself or its subdocs, it would never be
+    /// loaded and the stores would be locked by themselves.
+    fn check_subdoc(doc: &Doc, store: &WeakStoreRef, parent: &Option<WeakStoreRef>) -> JwstCodecResult {
+        let target = Arc::as_ptr(&doc.store);
+        if Weak::as_ptr(store) == target {
+            return Err(JwstCodecError::InvalidSubdoc);
+        }
+
+        let mut parent = parent.as_ref().and_then(|parent| parent.upgrade());
+        while let Some(store) = parent {
+            if Arc::as_ptr(&store) == target {
+                return Err(JwstCodecError::InvalidSubdoc);
+            }
+            parent = store
+                .read()
+                .unwrap()
+                .parent
+                .as_ref()
+                .and_then(|parent| parent.upgrade());
+        }
+
+        Ok(())
+    }
+
+    fn add_subdoc(&mut self, id: Id, doc: Doc, parent: &WeakStoreRef) {
+        let should_load = {
+            let mut store = doc.store.write().unwrap();
+            store.parent = Some(parent.clone());
+            store.should_load
+        };
+
+        if let Some(transaction) = &mut self.transaction {
+            transaction.subdocs.added.push(doc.clone());
+            if should_load {
+                transaction.subdocs.loaded.push(doc.clone());
+            }
+        }
+        self.subdocs.insert(id, doc);
+    }
+
+    fn remove_subdocs(&mut self, ids: Vec<Id>) {
+        for id in ids {
+            let Some(doc) = self.subdocs.remove(&id) else {
+                continue;
+            };
+            doc.store.write().unwrap().parent = None;
+
+            if let Some(transaction) = &mut self.transaction {
+                let subdocs = &mut transaction.subdocs;
+                let is_same = |other: &Doc| Arc::ptr_eq(&other.store, &doc.store);
+                // the subdoc added and removed in the same transaction is not reported
+                if let Some(idx) = subdocs.added.iter().position(is_same) {
+                    subdocs.added.remove(idx);
+                    subdocs.loaded.retain(|other| !is_same(other));
+                } else {
+                    subdocs.removed.push(doc);
+                }
+            }
+        }
+    }
+
+    /// Take over the items in the range of the move, like `Move::integrate_block`
+    /// in yrs. The items shown by another move are taken over if this move
+    /// has a higher priority, or the same priority and a higher id, the loser
+    /// is recorded in the overrides of the winner.
+    fn integrate_move(&mut self, item_ref: &ItemRef, mut parent: Option<&mut YType>) {
+        let Some((id, priority, start, end)) = item_ref.get().and_then(|item| match &item.content {
+            Content::Move(content) => Some((
+                item.id,
+                content.priority,
+                (content.start, content.start_assoc),
+                (content.end, content.end_assoc),
+            )),
+            _ => None,
+        }) else {
+            return;
+        };
+
+        // the range is empty if the anchors are unknown
+        let (start_item, end_item) = self.move_anchor(start).zip(self.move_anchor(end)).unwrap_or_default();
+        let targets = {
+            // SAFETY: store is the only entry of mutating items
+            let mut item = unsafe { item_ref.get_mut_unchecked() };
+            let Content::Move(content) = &mut item.content else {
+                return;
+            };
+            content.start_item = start_item;
+            content.end_item = end_item;
+            content.items().collect::<Vec<_>>()
+        };
+
+        let adapt_priority = priority < 0;
+        let mut max_priority = 0;
+        let mut overrides = Vec::new();
+        let mut cleanups = Vec::new();
+        let mut looped = false;
+        for target_ref in targets {
+            let Some((current, is_live_move)) = target_ref.get().map(|target| {
+                (
+                    target.moved.clone(),
+                    !target.deleted() && matches!(target.content, Content::Move(_)),
+                )
+            }) else {
+                continue;
+            };
+            if target_ref == *item_ref {
+                continue;
+            }
+
+            let (current_priority, current_collapsed) = match current.get().map(|moved| &moved.content) {
+                Some(Content::Move(content)) => (content.priority, content.is_collapsed()),
+                _ => (-1, false),
+            };
+            let is_lower = |other: Id| other.client < id.client || other.client == id.client && other.clock < id.clock;
+
+            if adapt_priority
+                || current_priority < priority
+                || current_priority == priority && current.get().is_some_and(|moved| is_lower(moved.id))
+            {
+                if let Some(moved) = current.get() {
+                    // a single item can't be shown twice, the overridden
+                    // move of it is useless
+                    if current_collapsed {
+                        cleanups.push(current.clone());
+                    }
+                    overrides.push(moved.id);
+                }
+                max_priority = max_priority.max(current_priority);
+                self.set_moved(&target_ref, item_ref.clone());
+
+                if is_live_move && Self::find_move_loop(&target_ref, &mut HashSet::from_iter([id])) {
+                    looped = true;
+                    break;
+                }
+            } else if current.is_some() {
+                // SAFETY: store is the only entry of mutating items
+                if let Content::Move(content) = &mut unsafe { current.get_mut_unchecked() }.content {
+                    content.overrides.insert(id);
+                }
+            }
+        }
+
+        {
+            // SAFETY: store is the only entry of mutating items
+            let mut item = unsafe { item_ref.get_mut_unchecked() };
+            if let Content::Move(content) = &mut item.content {
+                if adapt_priority {
+                    content.priority = max_priority + 1;
+                }
+                content.overrides.extend(overrides);
+            }
+        }
+
+        if looped {
+            // the move is moved into its own range, drop it and give the
+            // items back to the overridden moves
+            if let Some(item) = item_ref.get() {
+                self.delete_item(item, parent);
+            }
+            return;
+        }
+
+        for cleanup in cleanups {
+            if let Some(item) = cleanup.get() {
+                self.delete_item(item, parent.as_deref_mut());
+            }
+        }
+    }
+
+    /// Resolve the item at the anchor of a move, the item is split at the
+    /// anchor. It returns [None] if the anchor is unknown.
+    fn move_anchor(&mut self, (id, assoc): (Id, Assoc)) -> Option<ItemRef> {
+        match assoc {
+            Assoc::After => {
+                let item = self.split_at_and_get_right(id).ok()?.as_item();
+                item.is_some().then_some(item)
+            }
+            Assoc::Before => {
+                let item = self.split_at_and_get_left(id).ok()?.as_item();
+                item.get().map(|item| item.right.clone())
+            }
+        }
+    }
+
+    /// Whether the move ends up in its own range through the moves in the
+    /// range, the moves visited are tracked to detect the loop.
+    fn find_move_loop(item_ref: &ItemRef, tracked: &mut HashSet<Id>) -> bool {
+        let Some(item) = item_ref.get() else {
+            return false;
+        };
+        if !tracked.insert(item.id) {
+            return true;
+        }
+
+        let Content::Move(content) = &item.content else {
+            return false;
+        };
+        content.items().any(|target_ref| {
+            target_ref.get().is_some_and(|target| {
+                !target.deleted() && target.moved == *item_ref && Self::find_move_loop(&target_ref, tracked)
+            })
+        })
+    }
+
+    fn set_moved(&mut self, item_ref: &ItemRef, moved: ItemRef) {
+        // SAFETY: store is the only entry of mutating items
+        let mut item = unsafe { item_ref.get_mut_unchecked() };
+        if let Some(transaction) = &mut self.transaction {
+            transaction
+                .prev_moved
+                .entry(item.id)
+                .or_insert_with(|| item.moved.get().map(|moved| moved.id));
+        }
+        item.moved = moved;
+    }
+
+    /// Give the items of the deleted moves back to the moves overridden by
+    /// them, the parent is the list of the moves if it's locked by the caller
+    fn remove_moves(&mut self, moves: Vec<(Id, Move)>, mut parent: Option<&mut YType>) {
+        let mut visited = HashSet::new();
+        for (id, content) in moves {
+            for item_ref in content.items() {
+                if item_ref
+                    .get()
+                    .is_some_and(|item| item.moved.get().is_some_and(|moved| moved.id == id))
+                {
+                    self.set_moved(&item_ref, Somr::none());
+                }
+            }
+
+            for id in content.overrides {
+                self.reintegrate_move(id, &mut visited, parent.as_deref_mut());
+            }
+        }
+    }
+
+    fn reintegrate_move(&mut self, id: Id, visited: &mut HashSet<Id>, mut parent: Option<&mut YType>) {
+        if !visited.insert(id) {
+            return;
+        }
+
+        let item_ref = self.get_node(id).map(|node| node.as_item()).unwrap_or_default();
+        let Some(item) = item_ref.get() else {
+            return;
+        };
+        if item.deleted() {
+            // the items may be taken over by the moves it overrides
+            if let Content::Move(content) = &item.content {
+                for id in content.overrides.clone() {
+                    self.reintegrate_move(id, visited, parent.as_deref_mut());
+                }
+            }
+        } else {
+            self.integrate_move(&item_ref, parent);
+        }
+    }
+
     fn delete_item_inner(
         delete_set: &mut HashMap<u64, Vec<Range<u64>>>,
+        removed_subdocs: &mut Vec<Id>,
+        removed_moves: &mut Vec<(Id, Move)>,
         changed: &mut ChangedTypeRefs,
+        offset_kind: OffsetKind,
         item: &Item,
-        parent: Option<&mut YType>,
+        mut parent: Option<&mut YType>,
     ) {
         // 1. mark item as deleted, if item is gced, return
         if !item.delete() {
@@ -644,12 +1167,16 @@ impl DocStore {
 
         // 3. adjust parent length
         if item.parent_sub.is_none() && item.countable() {
-            if let Some(parent) = parent {
+            let offset_len = offset_kind.content_len(&item.content);
+            if let Some(parent) = parent.as_deref_mut() {
                 if parent.len != 0 {
                     parent.len -= item.len();
+                    parent.offset_len -= offset_len;
                 }
             } else if let Some(Parent::Type(ty)) = &item.parent {
-                ty.ty_mut().unwrap().len -= item.len();
+                let mut ty = ty.ty_mut().unwrap();
+                ty.len -= item.len();
+                ty.offset_len -= offset_len;
             }
         }
 
@@ -661,7 +1188,15 @@ impl DocStore {
                     let mut item_ref = ty.start.clone();
                     while let Some(item) = item_ref.get() {
                         if !item.deleted() {
-                            Self::delete_item_inner(delete_set, changed, item, Some(&mut ty));
+                            Self::delete_item_inner(
+                                delete_set,
+                                removed_subdocs,
+                                removed_moves,
+                                changed,
+                                offset_kind,
+                                item,
+                                Some(&mut ty),
+                            );
                         }
 
                         item_ref = item.right.clone();
@@ -672,13 +1207,31 @@ impl DocStore {
                         if let Some(item) = item.get()
                             && !item.deleted()
                         {
-                            Self::delete_item_inner(delete_set, changed, item, Some(&mut ty));
+                            Self::delete_item_inner(
+                                delete_set,
+                                removed_subdocs,
+                                removed_moves,
+                                changed,
+                                offset_kind,
+                                item,
+                                Some(&mut ty),
+                            );
                         }
                     }
                 }
             }
             Content::Doc { .. } => {
-                // TODO: remove subdoc
+                removed_subdocs.push(item.id);
+            }
+            Content::Move(content) => {
+                if let Some(parent) = parent {
+                    parent.moves = parent.moves.saturating_sub(1);
+                } else if let Some(Parent::Type(ty)) = &item.parent
+                    && let Some(mut ty) = ty.ty_mut()
+                {
+                    ty.moves = ty.moves.saturating_sub(1);
+                }
+                removed_moves.push((item.id, (**content).clone()));
             }
             _ => {}
         }
@@ -717,6 +1270,8 @@ impl DocStore {
             };
 
             let mut pending_delete_sets = HashMap::new();
+            let mut removed_subdocs = Vec::new();
+            let mut removed_moves = Vec::new();
             while idx < items.len() {
                 let node = items[idx].clone();
                 let id = node.id();
@@ -732,16 +1287,24 @@ impl DocStore {
                             DocStore::split_node_at(items, idx, end - id.clock)?;
                         }
 
-                        Self::delete_item_inner(&mut pending_delete_sets, &mut self.changed, item, None);
+                        Self::delete_item_inner(
+                            &mut pending_delete_sets,
+                            &mut removed_subdocs,
+                            &mut removed_moves,
+                            &mut self.changed,
+                            self.offset_kind,
+                            item,
+                            None,
+                        );
                     }
                 } else {
                     break;
                 }
                 idx += 1;
             }
-            for (client, ranges) in pending_delete_sets {
-                self.delete_set.batch_add_ranges(client, ranges);
-            }
+            self.add_delete_sets(pending_delete_sets);
+            self.remove_subdocs(removed_subdocs);
+            self.remove_moves(removed_moves, None);
         };
 
         Ok(())
@@ -816,7 +1379,7 @@ impl DocStore {
                 let clock = items.front().unwrap().id().clock.max(clock);
                 if let Some(index) = Self::get_node_index(items, clock) {
                     let first_block = items.get(index).unwrap();
-                    let offset = first_block.clock() - clock;
+                    let offset = clock - first_block.clock();
                     if offset != 0 {
                         vec_struct_info.push_back(first_block.clone().split_at(offset)?.1);
                     } else {
@@ -847,7 +1410,18 @@ impl DocStore {
     }
 
     /// Optimize the memory usage of store
+    /// Build the deltas of the events not read yet before the types are
+    /// changed again, so the events still describe their own transaction.
+    pub fn build_pending_deltas(&mut self) {
+        for delta in mem::take(&mut self.pending_deltas) {
+            if let Some(delta) = delta.upgrade() {
+                delta.build();
+            }
+        }
+    }
+
     pub fn optimize(&mut self) -> JwstCodecResult {
+        self.build_pending_deltas();
         //  1. gc delete set
         self.gc_delete_set()?;
         //  2. merge delete set (in our delete set impl, which is based on `OrderRange`
@@ -1205,6 +1779,28 @@ mod tests {
         });
     }
 
+    #[test]
+    fn test_diff_structs_from_middle_of_item() {
+        loom_model!({
+            let mut doc_store = DocStore::with_client(1);
+            doc_store
+                .add_node(Node::Item(Somr::new(
+                    ItemBuilder::new()
+                        .id((1, 0).into())
+                        .content(Content::String(String::from("octo")))
+                        .build(),
+                )))
+                .unwrap();
+
+            // the remote has the first 2 clocks, only the rest of the item is sent
+            let structs = DocStore::diff_structs(&doc_store.items, &StateVector::from([(1, 2)])).unwrap();
+            let nodes = structs.get(&1).unwrap();
+            assert_eq!(nodes.len(), 1);
+            assert_eq!(nodes[0].id(), Id::new(1, 2));
+            assert_eq!(nodes[0].len(), 2);
+        });
+    }
+
     #[test]
     fn test_split_and_get() {
         loom_model!({
@@ -0,0 +1,445 @@
+use super::{
+    store::{ChangedTypeRefs, StoreRef},
+    *,
+};
+use crate::sync::{
+    Arc, Condvar, Mutex, RwLockWriteGuard,
+    thread::{self, ThreadId},
+};
+
+/// The origin of a transaction, it's used to tell apart the changes made by
+/// different sources, e.g. the local editor, a sync provider or an
+/// [UndoManager].
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct Origin(Vec<u8>);
+
+impl Origin {
+    pub fn as_bytes(&self) -> &[u8] {
+        &self.0
+    }
+}
+
+impl From<&str> for Origin {
+    fn from(value: &str) -> Self {
+        Self(value.as_bytes().to_vec())
+    }
+}
+
+impl From<String> for Origin {
+    fn from(value: String) -> Self {
+        Self(value.into_bytes())
+    }
+}
+
+impl From<&[u8]> for Origin {
+    fn from(value: &[u8]) -> Self {
+        Self(value.to_vec())
+    }
+}
+
+impl From<Vec<u8>> for Origin {
+    fn from(value: Vec<u8>) -> Self {
+        Self(value)
+    }
+}
+
+impl From<u64> for Origin {
+    fn from(value: u64) -> Self {
+        Self(value.to_be_bytes().to_vec())
+    }
+}
+
+/// The subdocs changed in a transaction, see [Doc::observe_subdocs].
+#[derive(Debug, Clone, Default)]
+pub struct SubdocsEvent {
+    pub(crate) added: Vec<Doc>,
+    pub(crate) removed: Vec<Doc>,
+    pub(crate) loaded: Vec<Doc>,
+}
+
+impl SubdocsEvent {
+    /// The subdocs inserted into the doc
+    pub fn added(&self) -> &[Doc] {
+        &self.added
+    }
+
+    /// The subdocs whose items are deleted
+    pub fn removed(&self) -> &[Doc] {
+        &self.removed
+    }
+
+    /// The subdocs requested to load, it includes the added subdocs which
+    /// should be loaded right away.
+    pub fn loaded(&self) -> &[Doc] {
+        &self.loaded
+    }
+
+    pub(crate) fn is_empty(&self) -> bool {
+        self.added.is_empty() && self.removed.is_empty() && self.loaded.is_empty()
+    }
+}
+
+/// The record of changes made to the doc in a committed transaction.
+#[derive(Debug, Clone)]
+pub struct Transaction {
+    pub(crate) origin: Option<Origin>,
+    pub(crate) local: bool,
+    pub(crate) before_state: StateVector,
+    pub(crate) after_state: StateVector,
+    // items deleted in this transaction
+    pub(crate) delete_set: DeleteSet,
+    // the update of this transaction, encoded on commit if it's requested
+    pub(crate) update: Vec<u8>,
+    // the types changed in this transaction, taken out on commit to emit events
+    pub(crate) changed: ChangedTypeRefs,
+    pub(crate) subdocs: SubdocsEvent,
+    // the move which showed the item before this transaction, keyed by the
+    // id of the items moved in this transaction
+    pub(crate) prev_moved: HashMap<Id, Option<Id>>,
+}
+
+impl Transaction {
+    pub(crate) fn new(origin: Option<Origin>, local: bool, before_state: StateVector) -> Self {
+        Self {
+            origin,
+            local,
+            after_state: before_state.clone(),
+            before_state,
+            delete_set: DeleteSet::default(),
+            update: Vec::new(),
+            changed: ChangedTypeRefs::default(),
+            subdocs: SubdocsEvent::default(),
+            prev_moved: HashMap::default(),
+        }
+    }
+
+    pub fn origin(&self) -> Option<&Origin> {
+        self.origin.as_ref()
+    }
+
+    /// Whether the changes are made locally, `false` if they come from
+    /// applying updates.
+    pub fn is_local(&self) -> bool {
+        self.local
+    }
+
+    pub fn before_state(&self) -> &StateVector {
+        &self.before_state
+    }
+
+    pub fn after_state(&self) -> &StateVector {
+        &self.after_state
+    }
+
+    pub fn delete_set(&self) -> &DeleteSet {
+        &self.delete_set
+    }
+
+    /// The update in v1 format which contains all changes of this
+    /// transaction, it's only encoded for the transactions committed by
+    /// [TransactionMut::commit] or observed by [Doc::observe_update_v1],
+    /// and left empty otherwise.
+    pub fn update_v1(&self) -> &[u8] {
+        &self.update
+    }
+
+    pub fn subdocs(&self) -> &SubdocsEvent {
+        &self.subdocs
+    }
+
+    /// The ranges of items inserted in this transaction, grouped by client
+    pub(crate) fn insertions(&self) -> DeleteSet {
+        let mut insertions = DeleteSet::default();
+        for (client, clock) in self.after_state.iter() {
+            let before = self.before_state.get(client);
+            if *clock > before {
+                insertions.add_range(*client, before..*clock);
+            }
+        }
+
+        insertions
+    }
+
+    pub(crate) fn is_empty(&self) -> bool {
+        self.before_state == self.after_state && self.delete_set.is_empty() && self.subdocs.is_empty()
+    }
+}
+
+/// How the changes take part in the transactions of store, see
+/// [DocStore::begin_transaction].
+pub(crate) enum TransactionScope {
+    /// A new transaction is began for the changes
+    New,
+    /// The changes join the transaction in progress
+    Joined,
+    /// A new transaction is began while the one in progress is put aside
+    Nested(Box<(Transaction, ChangedTypeRefs)>),
+}
+
+/// The thread owning the transactions of a store, the other threads wait
+/// until no transaction is in progress before making changes.
+pub(crate) struct TransactionOwner {
+    owner: Mutex<Option<ThreadId>>,
+    released: Condvar,
+}
+
+impl Default for TransactionOwner {
+    fn default() -> Self {
+        Self {
+            owner: Mutex::new(None),
+            released: Condvar::new(),
+        }
+    }
+}
+
+impl std::fmt::Debug for TransactionOwner {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("TransactionOwner").finish_non_exhaustive()
+    }
+}
+
+impl TransactionOwner {
+    /// Block until the store is not owned by another thread, and take it
+    fn acquire(&self) {
+        let current = thread::current().id();
+        let mut owner = self.owner.lock().unwrap();
+        while owner.is_some_and(|owner| owner != current) {
+            owner = self.released.wait(owner).unwrap();
+        }
+        *owner = Some(current);
+    }
+
+    fn release(&self) {
+        *self.owner.lock().unwrap() = None;
+        self.released.notify_all();
+    }
+}
+
+/// Lock the store to make changes, it blocks until the transaction began by
+/// another thread is committed. The store is owned by the current thread
+/// until it's unlocked by [unlock_store] with no transaction in progress.
+pub(crate) fn lock_store(store: &StoreRef) -> RwLockWriteGuard<'_, DocStore> {
+    let owner = store.read().unwrap().owner.clone();
+    owner.acquire();
+
+    let mut guard = store.write().unwrap();
+    guard.build_pending_deltas();
+    guard
+}
+
+/// Unlock the store locked by [lock_store], the other threads waiting for
+/// it are woken up if no transaction is in progress.
+pub(crate) fn unlock_store(store: RwLockWriteGuard<'_, DocStore>) {
+    let owner = store.transaction.is_none().then(|| store.owner.clone());
+    drop(store);
+
+    if let Some(owner) = owner {
+        owner.release();
+    }
+}
+
+/// A transaction in progress, all changes made to the doc are recorded into
+/// it until it's committed, see [Doc::transact_mut].
+///
+/// The transaction is owned by the thread creating it, the changes made by
+/// other threads wait until it's committed. A transaction created on the
+/// same thread while another one is in progress joins the outer one and
+/// commits nothing by itself.
+pub struct TransactionMut {
+    store: StoreRef,
+    scope: Option<TransactionScope>,
+    // the transaction is bound to the owner thread
+    _owner: std::marker::PhantomData<*const ()>,
+}
+
+impl TransactionMut {
+    pub(crate) fn new(store: StoreRef, origin: Option<Origin>, local: bool) -> Self {
+        let scope = lock_store(&store).begin_transaction(origin, local);
+
+        Self {
+            store,
+            scope: Some(scope),
+            _owner: std::marker::PhantomData,
+        }
+    }
+
+    pub fn origin(&self) -> Option<Origin> {
+        self.read(|transaction| transaction.origin.clone()).flatten()
+    }
+
+    pub fn before_state(&self) -> StateVector {
+        self.read(|transaction| transaction.before_state.clone())
+            .unwrap_or_default()
+    }
+
+    /// The items deleted in this transaction so far
+    pub fn delete_set(&self) -> DeleteSet {
+        self.read(|transaction| transaction.delete_set.clone())
+            .unwrap_or_default()
+    }
+
+    /// Commit the transaction, returns [None] if nothing changed or the
+    /// transaction joins an outer one.
+    pub fn commit(mut self) -> Option<Transaction> {
+        let scope = self.scope.take()?;
+
+        commit_transaction(self.store.write().unwrap(), scope, true)
+    }
+
+    fn read<T>(&self, f: impl FnOnce(&Transaction) -> T) -> Option<T> {
+        self.store.read().unwrap().transaction.as_ref().map(f)
+    }
+}
+
+impl Drop for TransactionMut {
+    fn drop(&mut self) {
+        if let Some(scope) = self.scope.take() {
+            commit_transaction(self.store.write().unwrap(), scope, false);
+        }
+    }
+}
+
+pub(crate) type Observer<T> = Arc<dyn Fn(&T) + Send + Sync>;
+
+struct ObserverList<T> {
+    next_id: u64,
+    observers: Vec<(u64, Observer<T>)>,
+}
+
+impl<T> Default for ObserverList<T> {
+    fn default() -> Self {
+        Self {
+            next_id: 0,
+            observers: Vec::new(),
+        }
+    }
+}
+
+/// The callbacks shared by all references of the list, they're called
+/// without holding the store lock.
+pub(crate) struct Observers<T>(Arc<Mutex<ObserverList<T>>>);
+
+pub(crate) type TransactionObservers = Observers<Transaction>;
+
+impl<T> Default for Observers<T> {
+    fn default() -> Self {
+        Self(Arc::default())
+    }
+}
+
+impl<T> Clone for Observers<T> {
+    fn clone(&self) -> Self {
+        Self(self.0.clone())
+    }
+}
+
+impl<T> std::fmt::Debug for Observers<T> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("Observers")
+            .field("count", &self.0.lock().unwrap().observers.len())
+            .finish()
+    }
+}
+
+impl<T> Observers<T> {
+    pub fn observe(&self, observer: Observer<T>) -> u64 {
+        let mut list = self.0.lock().unwrap();
+        let id = list.next_id;
+        list.next_id += 1;
+        list.observers.push((id, observer));
+
+        id
+    }
+
+    pub fn unobserve(&self, id: u64) {
+        self.0
+            .lock()
+            .unwrap()
+            .observers
+            .retain(|(observer_id, _)| *observer_id != id);
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.0.lock().unwrap().observers.is_empty()
+    }
+
+    pub fn notify(&self, value: &T) {
+        // clone the list out so the observers can (un)register observers
+        let observers = self
+            .0
+            .lock()
+            .unwrap()
+            .observers
+            .iter()
+            .map(|(_, observer)| observer.clone())
+            .collect::<Vec<_>>();
+
+        for observer in observers {
+            observer(value);
+        }
+    }
+}
+
+/// The handle of an observer registered on the doc or a type, the observer
+/// is removed once the subscription is dropped.
+#[must_use = "the observer is removed once the subscription is dropped"]
+pub struct Subscription {
+    unobserve: Option<Box<dyn FnOnce() + Send + Sync>>,
+}
+
+impl Subscription {
+    pub(crate) fn new<T: 'static>(observers: &Observers<T>, observer: Observer<T>) -> Self {
+        let observers = observers.clone();
+        let id = observers.observe(observer);
+
+        Self {
+            unobserve: Some(Box::new(move || observers.unobserve(id))),
+        }
+    }
+}
+
+impl std::fmt::Debug for Subscription {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("Subscription").finish()
+    }
+}
+
+impl Drop for Subscription {
+    fn drop(&mut self) {
+        if let Some(unobserve) = self.unobserve.take() {
+            unobserve();
+        }
+    }
+}
+
+/// Commit the transaction if it was began by the caller, the observers are
+/// called after the store lock is released.
+pub(crate) fn commit_transaction(
+    mut store: RwLockWriteGuard<'_, DocStore>,
+    scope: TransactionScope,
+    encode_update: bool,
+) -> Option<Transaction> {
+    let mut committed = match scope {
+        TransactionScope::Joined => None,
+        _ => store.commit_transaction(encode_update),
+    };
+    let events = committed
+        .as_mut()
+        .map(|transaction| collect_events(&mut store, transaction))
+        .unwrap_or_default();
+    if let TransactionScope::Nested(outer) = scope {
+        store.restore_transaction(outer);
+    }
+    let observers = store.transaction_observers.clone();
+    let update_observers = store.update_observers.clone();
+    unlock_store(store);
+
+    events.notify();
+
+    if let Some(transaction) = &committed {
+        observers.notify(transaction);
+        update_observers.notify(transaction);
+    }
+
+    committed
+}
@@ -1,3 +1,5 @@
+use std::ops::{Bound, RangeBounds};
+
 use super::*;
 
 impl_type!(Array);
@@ -79,6 +81,44 @@ impl Array {
         })
     }
 
+    /// Create a position sticks to the element at the index, see
+    /// [RelativePosition].
+    pub fn relative_position(&self, index: u64, assoc: Assoc) -> JwstCodecResult<RelativePosition> {
+        self.relative_position_at(index, assoc)
+    }
+
+    /// Observe the changes of array, the observer is removed when the
+    /// returned [Subscription] is dropped.
+    ///
+    /// ```
+    /// use y_octo::{ArrayDeltaOp, Doc, Value};
+    ///
+    /// let doc = Doc::default();
+    /// let mut array = doc.get_or_create_array("array").unwrap();
+    /// array.push(1).unwrap();
+    ///
+    /// let _subscription = array.observe(|event| {
+    ///     assert_eq!(
+    ///         event.delta(),
+    ///         &vec![ArrayDeltaOp::Retain(1), ArrayDeltaOp::Insert(vec![Value::from(2)])]
+    ///     );
+    /// });
+    /// array.push(2).unwrap();
+    /// ```
+    pub fn observe(&self, f: impl Fn(&ArrayEvent) + Send + Sync + 'static) -> Subscription {
+        self.0.observe(Arc::new(move |event| {
+            if let Event::Array(event) = event {
+                f(event)
+            }
+        }))
+    }
+
+    /// Observe the changes of this array and all nested types, the events of a
+    /// transaction are received at once with their paths from this array.
+    pub fn observe_deep(&self, f: impl Fn(&[Event]) + Send + Sync + 'static) -> Subscription {
+        self.0.observe_deep(Arc::new(move |events: &Vec<Event>| f(events)))
+    }
+
     pub fn iter(&self) -> ArrayIter<'_> {
         ArrayIter {
             iter: self.iter_item(),
@@ -94,9 +134,114 @@ impl Array {
         self.insert_at(idx, val.into().into())
     }
 
+    /// Insert the values at the index in one transaction, the consecutive
+    /// plain values are stored in a single item instead of one item per
+    /// value, which is much cheaper to integrate and encode.
+    ///
+    /// ```
+    /// use y_octo::{Doc, Value};
+    ///
+    /// let doc = Doc::default();
+    /// let mut array = doc.get_or_create_array("array").unwrap();
+    /// array.insert_range(0, (0..3).map(Value::from)).unwrap();
+    /// assert_eq!(array.to_vec(), vec![Value::from(0), Value::from(1), Value::from(2)]);
+    /// ```
+    pub fn insert_range(&mut self, idx: u64, values: impl IntoIterator<Item = Value>) -> JwstCodecResult {
+        let mut contents = Vec::new();
+        let mut pending = Vec::new();
+        for value in values {
+            match value {
+                // arrays are spread into the list like `insert`
+                Value::Any(Any::Array(values)) => pending.extend(values),
+                Value::Any(any) if !matches!(any, Any::Binary(_)) => pending.push(any),
+                // binaries and shared types are stored in their own items
+                value => {
+                    if !pending.is_empty() {
+                        contents.push(Content::Any(std::mem::take(&mut pending)));
+                    }
+                    contents.push(value.into());
+                }
+            }
+        }
+        if !pending.is_empty() {
+            contents.push(Content::Any(pending));
+        }
+
+        self.insert_contents_at(idx, contents)
+    }
+
+    /// Insert the value and create the nested types in it recursively,
+    /// returns the inserted value.
+    pub fn insert_prelim<P: Into<Prelim>>(&mut self, idx: u64, prelim: P) -> JwstCodecResult<Value> {
+        let mut array = self.clone();
+        prelim.into().integrate(&self.0.store, |value| array.insert(idx, value))
+    }
+
+    pub fn push_prelim<P: Into<Prelim>>(&mut self, prelim: P) -> JwstCodecResult<Value> {
+        self.insert_prelim(self.len(), prelim)
+    }
+
     pub fn remove(&mut self, idx: u64, len: u64) -> JwstCodecResult {
         self.remove_at(idx, len)
     }
+
+    /// Remove all values of the array
+    pub fn clear(&mut self) -> JwstCodecResult {
+        self.remove_at(0, self.len())
+    }
+
+    /// The values in the range, the range is clamped to the length of the
+    /// array.
+    pub fn slice(&self, range: impl RangeBounds<u64>) -> Vec<Value> {
+        let start = match range.start_bound() {
+            Bound::Included(start) => *start,
+            Bound::Excluded(start) => start.saturating_add(1),
+            Bound::Unbounded => 0,
+        };
+        let end = match range.end_bound() {
+            Bound::Included(end) => end.saturating_add(1),
+            Bound::Excluded(end) => *end,
+            Bound::Unbounded => u64::MAX,
+        };
+
+        self.iter()
+            .skip(start as usize)
+            .take(end.saturating_sub(start) as usize)
+            .collect()
+    }
+
+    pub fn to_vec(&self) -> Vec<Value> {
+        self.iter().collect()
+    }
+
+    /// Move the value at `from` to the index `to`, the index is counted
+    /// before moving. The value keeps its identity instead of being deleted
+    /// and inserted again, and concurrent moves of it are resolved like yrs.
+    ///
+    /// ```
+    /// use y_octo::{Any, Doc, Value};
+    ///
+    /// let doc = Doc::default();
+    /// let mut array = doc.get_or_create_array("array").unwrap();
+    /// for value in ["a", "b", "c"] {
+    ///     array.push(value).unwrap();
+    /// }
+    ///
+    /// array.move_to(0, 2).unwrap();
+    /// assert_eq!(
+    ///     Value::Array(array).to_json(),
+    ///     Any::Array(vec!["b".into(), "a".into(), "c".into()])
+    /// );
+    /// ```
+    pub fn move_to(&mut self, from: u64, to: u64) -> JwstCodecResult {
+        self.move_at(from, from + 1, to)
+    }
+
+    /// Move the values from `start` to `end` to the index `to`, both sides
+    /// of the range are inclusive like yrs, see [Array::move_to].
+    pub fn move_range_to(&mut self, start: u64, end: u64, to: u64) -> JwstCodecResult {
+        self.move_at(start, end + 1, to)
+    }
 }
 
 impl serde::Serialize for Array {
@@ -236,6 +381,7 @@ mod tests {
                     guid: String::from("1"),
                     client_id: 1,
                     gc: true,
+                    ..Default::default()
                 },
             )
             .unwrap();
@@ -244,4 +390,307 @@ mod tests {
             assert_eq!(arr.get(2).unwrap(), Value::Any(Any::String("world".to_string())))
         });
     }
+
+    fn values(array: &Array) -> Vec<Any> {
+        array
+            .iter()
+            .map(|value| match value {
+                Value::Any(any) => any,
+                value => value.to_json(),
+            })
+            .collect()
+    }
+
+    fn sync(from: &Doc, to: &mut Doc) {
+        let update = from.encode_state_as_update_v1(&to.get_state_vector()).unwrap();
+        to.apply_update_from_binary_v1(update).unwrap();
+    }
+
+    #[test]
+    fn test_array_insert_range() {
+        loom_model!({
+            let doc = DocOptions::new().with_client_id(1).build();
+            let mut array = doc.get_or_create_array("array").unwrap();
+            array.push("end").unwrap();
+
+            let map = doc.create_map().unwrap();
+            let values = (0..3)
+                .map(Value::from)
+                .chain([Value::Map(map), Value::from(3), Value::from(4)]);
+            array.insert_range(0, values).unwrap();
+            assert_eq!(array.len(), 7);
+            assert!(array.get(3).unwrap().to_map().is_some());
+
+            // the plain values are stored in the same item
+            let items = array
+                .iter_item()
+                .map(|item| item.get().unwrap().len())
+                .collect::<Vec<_>>();
+            assert_eq!(items, [3, 1, 2, 1]);
+
+            assert_eq!(array.slice(1..3), [Value::from(1), Value::from(2)]);
+            assert_eq!(
+                array.slice(4..),
+                [3, 4]
+                    .map(Value::from)
+                    .into_iter()
+                    .chain([Value::from("end")])
+                    .collect::<Vec<_>>()
+            );
+            assert_eq!(array.slice(5..=5), [Value::from(4)]);
+            // out of bound ranges are clamped
+            assert_eq!(array.slice(6..100), [Value::from("end")]);
+            assert!(array.slice(8..).is_empty());
+            assert_eq!(array.to_vec(), array.slice(..));
+
+            let remote = Doc::try_from_binary_v1(doc.encode_update_v1().unwrap()).unwrap();
+            assert_eq!(remote.to_json(), doc.to_json());
+
+            array.clear().unwrap();
+            assert!(array.is_empty());
+            assert!(array.to_vec().is_empty());
+            array.insert_range(0, []).unwrap();
+            assert!(array.is_empty());
+        });
+    }
+
+    #[test]
+    fn test_array_move() {
+        loom_model!({
+            let doc = DocOptions::new().with_client_id(1).build();
+            let mut array = doc.get_or_create_array("array").unwrap();
+            array.insert(0, Any::Array((0..5).map(Any::Integer).collect())).unwrap();
+            let nested = array.push_prelim(Prelim::Map(HashMap::default())).unwrap();
+
+            array.move_to(0, 3).unwrap();
+            assert_eq!(values(&array)[..5], [1, 2, 0, 3, 4].map(Any::Integer));
+            array.move_to(5, 0).unwrap();
+            assert_eq!(array.len(), 6);
+            // the nested type is moved instead of being copied
+            assert_eq!(array.get(0), Some(nested.clone()));
+            assert_eq!(values(&array)[1..], [1, 2, 0, 3, 4].map(Any::Integer));
+
+            // move the same item again
+            array.move_to(3, 6).unwrap();
+            assert_eq!(values(&array)[1..], [1, 2, 3, 4, 0].map(Any::Integer));
+
+            array.move_range_to(1, 2, 5).unwrap();
+            assert_eq!(values(&array)[1..], [3, 4, 1, 2, 0].map(Any::Integer));
+            assert_eq!(array.get(3), Some(Value::from(1)));
+
+            // edit around and inside the moved items
+            array.insert(4, 5).unwrap();
+            array.remove(5, 1).unwrap();
+            array.push(6).unwrap();
+            assert_eq!(values(&array)[1..], [3, 4, 1, 5, 0, 6].map(Any::Integer));
+
+            // moving to the same position is no-op
+            array.move_to(1, 2).unwrap();
+            array.move_range_to(1, 2, 1).unwrap();
+            assert_eq!(values(&array)[1..], [3, 4, 1, 5, 0, 6].map(Any::Integer));
+            assert_eq!(array.move_to(7, 0), Err(JwstCodecError::IndexOutOfBound(7)));
+            assert_eq!(array.move_to(0, 8), Err(JwstCodecError::IndexOutOfBound(8)));
+            assert_eq!(array.move_range_to(2, 1, 0), Err(JwstCodecError::IndexOutOfBound(2)));
+
+            let remote = Doc::try_from_binary_v1(doc.encode_update_v1().unwrap()).unwrap();
+            let remote_array = remote.get_or_create_array("array").unwrap();
+            assert_eq!(values(&remote_array), values(&array));
+            assert_eq!(remote.to_json(), doc.to_json());
+        });
+    }
+
+    #[test]
+    fn test_array_concurrent_move() {
+        loom_model!({
+            let mut doc1 = DocOptions::new().with_client_id(1).build();
+            let mut array1 = doc1.get_or_create_array("array").unwrap();
+            array1
+                .insert(0, Any::Array((0..4).map(Any::Integer).collect()))
+                .unwrap();
+            let mut doc2 = DocOptions::new().with_client_id(2).build();
+            sync(&doc1, &mut doc2);
+            let mut array2 = doc2.get_or_create_array("array").unwrap();
+
+            // the same item is moved to different positions
+            array1.move_to(0, 2).unwrap();
+            array2.move_to(0, 4).unwrap();
+            assert_eq!(values(&array1), [1, 0, 2, 3].map(Any::Integer));
+            assert_eq!(values(&array2), [1, 2, 3, 0].map(Any::Integer));
+
+            sync(&doc1, &mut doc2);
+            sync(&doc2, &mut doc1);
+            // the move with higher client id wins on the same priority
+            assert_eq!(values(&array1), [1, 2, 3, 0].map(Any::Integer));
+            assert_eq!(values(&array2), values(&array1));
+
+            // a later move overrides the concurrent ones
+            array1.move_to(3, 0).unwrap();
+            sync(&doc1, &mut doc2);
+            assert_eq!(values(&array2), [0, 1, 2, 3].map(Any::Integer));
+
+            // moved ranges overlap
+            array1.move_range_to(0, 1, 4).unwrap();
+            array2.move_range_to(1, 2, 0).unwrap();
+            sync(&doc1, &mut doc2);
+            sync(&doc2, &mut doc1);
+            assert_eq!(values(&array1), values(&array2));
+            assert_eq!(array1.len(), 4);
+
+            // the item moved and deleted concurrently
+            array1.move_to(0, 4).unwrap();
+            array2.remove(0, 1).unwrap();
+            sync(&doc1, &mut doc2);
+            sync(&doc2, &mut doc1);
+            assert_eq!(values(&array1), values(&array2));
+            assert_eq!(array1.len(), 3);
+        });
+    }
+
+    #[test]
+    #[cfg_attr(miri, ignore)]
+    fn test_array_move_compatible_with_yrs() {
+        use yrs::{Array as _, Assoc, Options, ReadTxn, Transact, updates::decoder::Decode};
+
+        loom_model!({
+            let yrs_values = |doc: &yrs::Doc, array: &yrs::ArrayRef| {
+                let trx = doc.transact();
+                array
+                    .iter(&trx)
+                    .map(|value| Any::String(value.to_string(&trx)))
+                    .collect::<Vec<_>>()
+            };
+            let to_yrs = |doc: &Doc, yrs_doc: &yrs::Doc| {
+                yrs_doc
+                    .transact_mut()
+                    .apply_update(yrs::Update::decode_v1(&doc.encode_update_v1().unwrap()).unwrap())
+                    .unwrap();
+            };
+            let from_yrs = |yrs_doc: &yrs::Doc, doc: &mut Doc| {
+                let update = yrs_doc
+                    .transact()
+                    .encode_state_as_update_v1(&yrs::StateVector::default());
+                doc.apply_update_from_binary_v1(update).unwrap();
+            };
+
+            // the moves made by yrs
+            let yrs_doc = yrs::Doc::with_options(Options {
+                client_id: 1,
+                ..Default::default()
+            });
+            let yrs_array = yrs_doc.get_or_insert_array("array");
+            {
+                let mut trx = yrs_doc.transact_mut();
+                for (index, value) in ["a", "b", "c", "d", "e"].into_iter().enumerate() {
+                    yrs_array.insert(&mut trx, index as u32, value);
+                }
+                yrs_array.move_to(&mut trx, 0, 3);
+                yrs_array.move_range_to(&mut trx, 3, Assoc::After, 4, Assoc::Before, 0);
+            }
+            let mut doc = DocOptions::new().with_client_id(2).build();
+            from_yrs(&yrs_doc, &mut doc);
+            let mut array = doc.get_or_create_array("array").unwrap();
+            assert_eq!(values(&array), yrs_values(&yrs_doc, &yrs_array));
+
+            // the moves made by y-octo
+            array.move_to(4, 1).unwrap();
+            array.move_range_to(0, 1, 5).unwrap();
+            to_yrs(&doc, &yrs_doc);
+            assert_eq!(yrs_values(&yrs_doc, &yrs_array), values(&array));
+
+            // the same item is moved concurrently
+            array.move_to(0, 5).unwrap();
+            yrs_array.move_to(&mut yrs_doc.transact_mut(), 0, 2);
+            to_yrs(&doc, &yrs_doc);
+            from_yrs(&yrs_doc, &mut doc);
+            assert_eq!(values(&array), yrs_values(&yrs_doc, &yrs_array));
+            assert_eq!(array.len(), 5);
+
+            // the moved ranges overlap
+            array.move_range_to(0, 2, 5).unwrap();
+            yrs_array.move_range_to(&mut yrs_doc.transact_mut(), 1, Assoc::After, 3, Assoc::Before, 0);
+            to_yrs(&doc, &yrs_doc);
+            from_yrs(&yrs_doc, &mut doc);
+            assert_eq!(values(&array), yrs_values(&yrs_doc, &yrs_array));
+            assert_eq!(array.len(), 5);
+        });
+    }
+
+    #[test]
+    fn test_array_move_with_search_markers() {
+        loom_model!({
+            let doc = DocOptions::new().with_client_id(1).build();
+            let mut array = doc.get_or_create_array("array").unwrap();
+            let mut expected = Vec::new();
+            for i in 0..100 {
+                array.push(i).unwrap();
+                expected.push(Any::Integer(i));
+            }
+            // create the search markers before moving
+            assert_eq!(array.get(60), Some(Value::from(60)));
+
+            let undo_manager = UndoManager::new(&doc, array.clone()).unwrap();
+            array.move_range_to(10, 29, 80).unwrap();
+            let moved = expected.drain(10..30).collect::<Vec<_>>();
+            expected.splice(60..60, moved);
+            array.move_to(95, 5).unwrap();
+            let moved = expected.remove(95);
+            expected.insert(5, moved);
+            assert_eq!(array.0.ty().unwrap().moves, 2);
+
+            // the positions are found in the order the items are shown
+            for (i, index) in [70, 3, 55, 90, 12, 66].into_iter().enumerate() {
+                let value = Any::Integer(100 + i as i32);
+                array.insert(index, value.clone()).unwrap();
+                expected.insert(index as usize, value);
+                assert_eq!(
+                    array.get(index + 1).and_then(|value| value.to_any()),
+                    expected.get(index as usize + 1).cloned()
+                );
+            }
+            assert_eq!(values(&array), expected);
+
+            // the search markers are used again after the moves are deleted
+            undo_manager.undo().unwrap();
+            assert_eq!(array.0.ty().unwrap().moves, 0);
+            let expected = values(&array);
+            for index in [80, 20, 50] {
+                assert_eq!(
+                    array.get(index).and_then(|value| value.to_any()),
+                    expected.get(index as usize).cloned()
+                );
+            }
+            assert!(
+                array
+                    .0
+                    .ty()
+                    .unwrap()
+                    .markers
+                    .as_ref()
+                    .is_some_and(|markers| !markers.borrow().is_empty())
+            );
+        });
+    }
+
+    #[test]
+    fn test_array_move_undo() {
+        loom_model!({
+            let doc = DocOptions::new().with_client_id(1).build();
+            let mut array = doc.get_or_create_array("array").unwrap();
+            array.insert(0, Any::Array((0..3).map(Any::Integer).collect())).unwrap();
+
+            let undo_manager = UndoManager::new(&doc, array.clone()).unwrap();
+            array.move_to(0, 3).unwrap();
+            undo_manager.stop_capturing();
+            array.move_to(0, 3).unwrap();
+            assert_eq!(values(&array), [2, 0, 1].map(Any::Integer));
+
+            // the items are given back to the overridden move
+            undo_manager.undo().unwrap();
+            assert_eq!(values(&array), [1, 2, 0].map(Any::Integer));
+            undo_manager.undo().unwrap();
+            assert_eq!(values(&array), [0, 1, 2].map(Any::Integer));
+            undo_manager.redo().unwrap();
+            assert_eq!(values(&array), [1, 2, 0].map(Any::Integer));
+        });
+    }
 }
@@ -0,0 +1,1103 @@
+use std::mem;
+
+use super::*;
+use crate::{
+    doc::{Observers, Origin, Transaction},
+    sync::{Arc, OnceLock, Weak},
+};
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum ArrayDeltaOp {
+    Insert(Vec<Value>),
+    Retain(u64),
+    Delete(u64),
+}
+
+pub type ArrayDelta = Vec<ArrayDeltaOp>;
+
+/// The delta of an event not built yet, they're built by
+/// [DocStore::build_pending_deltas] before the doc is changed again.
+pub(crate) trait PendingDelta: Send + Sync {
+    fn build(&self);
+}
+
+struct LazyDeltaInner<T> {
+    delta: OnceLock<T>,
+    build: Box<dyn Fn() -> T + Send + Sync>,
+}
+
+impl<T: Send + Sync> PendingDelta for LazyDeltaInner<T> {
+    fn build(&self) {
+        self.delta.get_or_init(|| (self.build)());
+    }
+}
+
+/// The delta of an event built on the first access instead of under the
+/// store lock, it's shared by the clones of the event.
+#[derive(Clone)]
+struct LazyDelta<T>(Arc<LazyDeltaInner<T>>);
+
+impl<T: Send + Sync + 'static> LazyDelta<T> {
+    fn new(build: impl Fn() -> T + Send + Sync + 'static) -> Self {
+        Self(Arc::new(LazyDeltaInner {
+            delta: OnceLock::new(),
+            build: Box::new(build),
+        }))
+    }
+
+    fn get(&self) -> &T {
+        self.0.delta.get_or_init(|| (self.0.build)())
+    }
+
+    fn pending(&self) -> Weak<dyn PendingDelta> {
+        let inner: Arc<dyn PendingDelta> = self.0.clone();
+        Arc::downgrade(&inner)
+    }
+}
+
+impl<T: std::fmt::Debug> std::fmt::Debug for LazyDelta<T> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_tuple("LazyDelta").field(&self.0.delta.get()).finish()
+    }
+}
+
+/// The change of a key in map, or an attribute in xml element.
+#[derive(Debug, Clone, PartialEq)]
+pub enum EntryChange {
+    Inserted(Value),
+    /// The old value and the new value
+    Updated(Value, Value),
+    Removed(Value),
+}
+
+macro_rules! impl_event {
+    ($name: ident) => {
+        impl $name {
+            /// The type that changed
+            pub fn target(&self) -> &Value {
+                &self.target
+            }
+
+            /// The origin of the transaction that made the changes
+            pub fn origin(&self) -> Option<&Origin> {
+                self.origin.as_ref()
+            }
+
+            /// Whether the changes are made locally
+            pub fn is_local(&self) -> bool {
+                self.local
+            }
+
+            /// The path from the observed type to the target, it's empty
+            /// unless the event is received by deep observers.
+            pub fn path(&self) -> &[PathSegment] {
+                &self.path
+            }
+        }
+    };
+}
+
+/// The changes of an array in a transaction, like `YArrayEvent` in yjs.
+#[derive(Debug, Clone)]
+pub struct ArrayEvent {
+    target: Value,
+    origin: Option<Origin>,
+    local: bool,
+    path: Vec<PathSegment>,
+    delta: LazyDelta<ArrayDelta>,
+}
+
+impl_event!(ArrayEvent);
+
+impl ArrayEvent {
+    /// The changes of items, it's built on the first call
+    pub fn delta(&self) -> &ArrayDelta {
+        self.delta.get()
+    }
+}
+
+/// The changes of a map or xml hook in a transaction, like `YMapEvent` in
+/// yjs.
+#[derive(Debug, Clone)]
+pub struct MapEvent {
+    target: Value,
+    origin: Option<Origin>,
+    local: bool,
+    path: Vec<PathSegment>,
+    keys_changed: HashMap<String, EntryChange>,
+}
+
+impl_event!(MapEvent);
+
+impl MapEvent {
+    pub fn keys_changed(&self) -> &HashMap<String, EntryChange> {
+        &self.keys_changed
+    }
+}
+
+/// The changes of a text or xml text in a transaction, like `YTextEvent` in
+/// yjs, the lengths in delta are measured in the offset kind of doc.
+#[derive(Debug, Clone)]
+pub struct TextEvent {
+    target: Value,
+    origin: Option<Origin>,
+    local: bool,
+    path: Vec<PathSegment>,
+    delta: LazyDelta<TextDelta>,
+}
+
+impl_event!(TextEvent);
+
+impl TextEvent {
+    /// The changes of text, it's built on the first call
+    pub fn delta(&self) -> &TextDelta {
+        self.delta.get()
+    }
+}
+
+/// The changes of a xml element or fragment in a transaction, like
+/// `YXmlEvent` in yjs.
+#[derive(Debug, Clone)]
+pub struct XMLEvent {
+    target: Value,
+    origin: Option<Origin>,
+    local: bool,
+    path: Vec<PathSegment>,
+    delta: LazyDelta<ArrayDelta>,
+    keys_changed: HashMap<String, EntryChange>,
+}
+
+impl_event!(XMLEvent);
+
+impl XMLEvent {
+    /// The changes of children, it's built on the first call
+    pub fn delta(&self) -> &ArrayDelta {
+        self.delta.get()
+    }
+
+    /// The changes of attributes
+    pub fn keys_changed(&self) -> &HashMap<String, EntryChange> {
+        &self.keys_changed
+    }
+}
+
+#[derive(Debug, Clone)]
+#[allow(clippy::upper_case_acronyms)]
+pub enum Event {
+    Array(ArrayEvent),
+    Map(MapEvent),
+    Text(TextEvent),
+    XML(XMLEvent),
+}
+
+impl Event {
+    pub fn target(&self) -> &Value {
+        match self {
+            Event::Array(event) => event.target(),
+            Event::Map(event) => event.target(),
+            Event::Text(event) => event.target(),
+            Event::XML(event) => event.target(),
+        }
+    }
+
+    pub fn origin(&self) -> Option<&Origin> {
+        match self {
+            Event::Array(event) => event.origin(),
+            Event::Map(event) => event.origin(),
+            Event::Text(event) => event.origin(),
+            Event::XML(event) => event.origin(),
+        }
+    }
+
+    pub fn is_local(&self) -> bool {
+        match self {
+            Event::Array(event) => event.is_local(),
+            Event::Map(event) => event.is_local(),
+            Event::Text(event) => event.is_local(),
+            Event::XML(event) => event.is_local(),
+        }
+    }
+
+    pub fn path(&self) -> &[PathSegment] {
+        match self {
+            Event::Array(event) => event.path(),
+            Event::Map(event) => event.path(),
+            Event::Text(event) => event.path(),
+            Event::XML(event) => event.path(),
+        }
+    }
+
+    /// The delta to build before the doc is changed again
+    fn pending_delta(&self) -> Option<Weak<dyn PendingDelta>> {
+        match self {
+            Event::Array(event) => Some(event.delta.pending()),
+            Event::Map(_) => None,
+            Event::Text(event) => Some(event.delta.pending()),
+            Event::XML(event) => Some(event.delta.pending()),
+        }
+    }
+
+    fn set_path(&mut self, path: Vec<PathSegment>) {
+        match self {
+            Event::Array(event) => event.path = path,
+            Event::Map(event) => event.path = path,
+            Event::Text(event) => event.path = path,
+            Event::XML(event) => event.path = path,
+        }
+    }
+}
+
+/// The events of a committed transaction waiting to be emitted.
+#[derive(Default)]
+pub(crate) struct PendingEvents {
+    events: Vec<(Observers<Event>, Event)>,
+    deep_events: Vec<(Observers<Vec<Event>>, Vec<Event>)>,
+}
+
+impl PendingEvents {
+    /// Call the observers of types, then the deep observers of their
+    /// ancestors, like yjs.
+    pub fn notify(self) {
+        for (observers, event) in self.events {
+            observers.notify(&event);
+        }
+
+        for (observers, events) in self.deep_events {
+            observers.notify(&events);
+        }
+    }
+}
+
+/// Build the events of changed types which have observers or ancestors with
+/// deep observers, the changed types are taken out of the transaction.
+pub(crate) fn collect_events(store: &mut DocStore, transaction: &mut Transaction) -> PendingEvents {
+    let changed = mem::take(&mut transaction.changed);
+    // the builder is kept by the events to build their deltas lazily
+    let mut builder = None;
+
+    let mut pending = PendingEvents::default();
+    // grouped by the ancestors in the order of their first appearance, so the
+    // deep observers are called in a stable order
+    let mut deep_events: Vec<(YTypeRef, Observers<Vec<Event>>, Vec<Event>)> = Vec::new();
+    for (ty_ref, keys) in changed {
+        let observers = ty_ref
+            .ty()
+            .and_then(|ty| ty.observers.clone())
+            .filter(|observers| !observers.is_empty());
+        let deep_observers = deep_observers_of(&ty_ref);
+        if observers.is_none() && deep_observers.is_empty() {
+            continue;
+        }
+
+        let builder = builder.get_or_insert_with(|| Arc::new(EventBuilder::new(store, transaction)));
+        let Some(event) = builder.event(&ty_ref, &keys) else {
+            continue;
+        };
+        store.pending_deltas.extend(event.pending_delta());
+
+        for (ancestor, observers) in deep_observers {
+            let mut event = event.clone();
+            event.set_path(path_to(&ancestor, &ty_ref));
+            match deep_events.iter_mut().find(|(ty_ref, ..)| *ty_ref == ancestor) {
+                Some((.., events)) => events.push(event),
+                None => deep_events.push((ancestor, observers, vec![event])),
+            }
+        }
+
+        if let Some(observers) = observers {
+            pending.events.push((observers, event));
+        }
+    }
+
+    for (_, observers, mut events) in deep_events {
+        events.sort_by_key(|event| event.path().len());
+        pending.deep_events.push((observers, events));
+    }
+
+    pending
+}
+
+/// The deep observers of the type and its ancestors
+fn deep_observers_of(ty_ref: &YTypeRef) -> Vec<(YTypeRef, Observers<Vec<Event>>)> {
+    let mut observers = Vec::new();
+    let mut cur = Some(ty_ref.clone());
+
+    while let Some(ty_ref) = cur {
+        let Some(ty) = ty_ref.ty() else {
+            break;
+        };
+
+        if let Some(deep_observers) = ty.deep_observers.clone().filter(|observers| !observers.is_empty()) {
+            observers.push((ty_ref.clone(), deep_observers));
+        }
+
+        cur = ty.item.get().and_then(|item| match &item.parent {
+            Some(Parent::Type(parent)) => Some(parent.clone()),
+            _ => None,
+        });
+    }
+
+    observers
+}
+
+/// The path from the ancestor to the type, like `getPathTo` in yjs.
+fn path_to(ancestor: &YTypeRef, ty_ref: &YTypeRef) -> Vec<PathSegment> {
+    let mut path = Vec::new();
+    let mut cur = ty_ref.clone();
+
+    while cur != *ancestor {
+        let Some(item) = cur.ty().map(|ty| ty.item.clone()) else {
+            break;
+        };
+        let Some(item) = item.get() else {
+            break;
+        };
+        let Some(Parent::Type(parent)) = &item.parent else {
+            break;
+        };
+
+        if let Some(key) = &item.parent_sub {
+            path.push(PathSegment::Key(key.to_string()));
+        } else if let Some(parent_ty) = parent.ty() {
+            let mut index = 0;
+            for sibling in ItemWalker::new(parent_ty.start.clone()) {
+                let Some(sibling_item) = sibling.get() else {
+                    break;
+                };
+                if sibling_item.id == item.id {
+                    break;
+                }
+                if sibling_item.indexable() {
+                    index += sibling_item.len();
+                }
+            }
+            path.push(PathSegment::Index(index));
+        }
+
+        cur = parent.clone();
+    }
+
+    path.reverse();
+    path
+}
+
+/// The changes of a committed transaction used to build events
+struct EventBuilder {
+    origin: Option<Origin>,
+    local: bool,
+    before_state: StateVector,
+    delete_set: DeleteSet,
+    prev_moved: HashMap<Id, Option<Id>>,
+    offset_kind: OffsetKind,
+}
+
+impl EventBuilder {
+    fn new(store: &DocStore, transaction: &Transaction) -> Self {
+        Self {
+            origin: transaction.origin.clone(),
+            local: transaction.local,
+            before_state: transaction.before_state.clone(),
+            delete_set: transaction.delete_set.clone(),
+            prev_moved: transaction.prev_moved.clone(),
+            offset_kind: store.offset_kind,
+        }
+    }
+
+    fn event(self: &Arc<Self>, ty_ref: &YTypeRef, keys: &[SmolStr]) -> Option<Event> {
+        let target = Value::from(ty_ref.clone());
+        let ty = ty_ref.ty()?;
+
+        // the types created or deleted in this transaction have no events
+        if let Some(item) = ty.item.get()
+            && (item.deleted() || self.adds(item))
+        {
+            return None;
+        }
+
+        let origin = self.origin.clone();
+        let local = self.local;
+        let path = Vec::new();
+        let event = match ty.kind() {
+            YTypeKind::Array => Event::Array(ArrayEvent {
+                target,
+                origin,
+                local,
+                path,
+                delta: self.lazy_delta(ty_ref, Self::list_delta),
+            }),
+            YTypeKind::Map | YTypeKind::XMLHook => Event::Map(MapEvent {
+                target,
+                origin,
+                local,
+                path,
+                keys_changed: self.keys_changed(&ty, keys),
+            }),
+            YTypeKind::Text | YTypeKind::XMLText => Event::Text(TextEvent {
+                target,
+                origin,
+                local,
+                path,
+                delta: self.lazy_delta(ty_ref, Self::text_delta),
+            }),
+            YTypeKind::XMLElement | YTypeKind::XMLFragment => Event::XML(XMLEvent {
+                target,
+                origin,
+                local,
+                path,
+                delta: self.lazy_delta(ty_ref, Self::list_delta),
+                keys_changed: self.keys_changed(&ty, keys),
+            }),
+            YTypeKind::Unknown => return None,
+        };
+
+        Some(event)
+    }
+
+    fn lazy_delta<T: Default + Send + Sync + 'static>(
+        self: &Arc<Self>,
+        ty_ref: &YTypeRef,
+        build: fn(&Self, &YType) -> T,
+    ) -> LazyDelta<T> {
+        let (builder, ty_ref) = (self.clone(), ty_ref.clone());
+        LazyDelta::new(move || ty_ref.ty().map(|ty| build(&builder, &ty)).unwrap_or_default())
+    }
+
+    /// Whether the item is inserted in this transaction
+    fn adds(&self, item: &Item) -> bool {
+        item.id.clock >= self.before_state.get(&item.id.client)
+    }
+
+    /// Whether the item is deleted in this transaction
+    fn deletes(&self, item: &Item) -> bool {
+        self.delete_set.contains(&item.id)
+    }
+
+    fn list_delta(&self, ty: &YType) -> ArrayDelta {
+        let mut delta = Vec::new();
+        self.push_list_range(&mut delta, ty.start.clone(), &Somr::none(), None);
+
+        if let Some(ArrayDeltaOp::Retain(_)) = delta.last() {
+            delta.pop();
+        }
+
+        delta
+    }
+
+    /// Push the changes of the items from `cur` to `end` shown by the move
+    /// `moved`, the items moved in this transaction are inserted at the new
+    /// position and deleted at the old one.
+    fn push_list_range(&self, delta: &mut ArrayDelta, mut cur: ItemRef, end: &ItemRef, moved: Option<Id>) {
+        while cur.is_some() && (end.is_none() || cur != *end) {
+            let Some(item) = cur.get() else {
+                break;
+            };
+
+            let moved_now = item.moved.get().map(|moved| moved.id);
+            let moved_before = self.prev_moved.get(&item.id).copied().unwrap_or(moved_now);
+            let (shown_now, shown_before) = (moved_now == moved, moved_before == moved);
+
+            if item.countable() {
+                match (shown_before, shown_now) {
+                    (true, true) => self.push_list_item(delta, item),
+                    (false, true) if !item.deleted() => Self::push_insert(delta, item),
+                    (true, false) if !self.adds(item) && (!item.deleted() || self.deletes(item)) => {
+                        Self::push_delete(delta, item)
+                    }
+                    _ => {}
+                }
+            }
+
+            // walk into the moves shown here, including the ones deleted in
+            // this transaction to delete the items shown by them
+            if let Content::Move(content) = &item.content
+                && shown_now
+                && (!item.deleted() || self.deletes(item) && !self.adds(item))
+            {
+                self.push_list_range(delta, content.start_item.clone(), &content.end_item, Some(item.id));
+            }
+
+            cur = item.right.clone();
+        }
+    }
+
+    fn push_list_item(&self, delta: &mut ArrayDelta, item: &Item) {
+        if item.deleted() {
+            if self.deletes(item) && !self.adds(item) {
+                Self::push_delete(delta, item);
+            }
+        } else if self.adds(item) {
+            Self::push_insert(delta, item);
+        } else if let Some(ArrayDeltaOp::Retain(len)) = delta.last_mut() {
+            *len += item.len();
+        } else {
+            delta.push(ArrayDeltaOp::Retain(item.len()));
+        }
+    }
+
+    fn push_insert(delta: &mut ArrayDelta, item: &Item) {
+        let values = match &item.content {
+            Content::Any(values) => values.iter().cloned().map(Value::Any).collect(),
+            content => vec![Value::from(content)],
+        };
+        if let Some(ArrayDeltaOp::Insert(inserted)) = delta.last_mut() {
+            inserted.extend(values);
+        } else {
+            delta.push(ArrayDeltaOp::Insert(values));
+        }
+    }
+
+    fn push_delete(delta: &mut ArrayDelta, item: &Item) {
+        if let Some(ArrayDeltaOp::Delete(len)) = delta.last_mut() {
+            *len += item.len();
+        } else {
+            delta.push(ArrayDeltaOp::Delete(item.len()));
+        }
+    }
+
+    fn keys_changed(&self, ty: &YType, keys: &[SmolStr]) -> HashMap<String, EntryChange> {
+        let mut changes = HashMap::new();
+
+        for key in keys {
+            let Some(item) = ty.map.get(key).and_then(|item| item.get()) else {
+                continue;
+            };
+
+            let change = if self.adds(item) {
+                // find the value before this transaction
+                let mut prev = item.left.clone();
+                loop {
+                    let left = match prev.get() {
+                        Some(prev) if self.adds(prev) => prev.left.clone(),
+                        _ => break,
+                    };
+                    prev = left;
+                }
+                let old = prev
+                    .get()
+                    .filter(|prev| self.deletes(prev))
+                    .map(|prev| Value::from(&prev.content));
+
+                match (old, self.deletes(item)) {
+                    (Some(old), true) => EntryChange::Removed(old),
+                    (Some(old), false) => EntryChange::Updated(old, Value::from(&item.content)),
+                    (None, false) => EntryChange::Inserted(Value::from(&item.content)),
+                    (None, true) => continue,
+                }
+            } else if self.deletes(item) {
+                EntryChange::Removed(Value::from(&item.content))
+            } else {
+                continue;
+            };
+
+            changes.insert(key.to_string(), change);
+        }
+
+        changes
+    }
+
+    /// Port of `YTextEvent.delta` in yjs, the lengths are measured in the
+    /// offset kind of doc.
+    fn text_delta(&self, ty: &YType) -> TextDelta {
+        let mut delta = TextDeltaBuilder::default();
+        let mut cur = ty.start.clone();
+
+        while let Some(item) = cur.get() {
+            match &item.content {
+                Content::Format { key, value } => {
+                    let current = delta.current.get(key.as_str()).cloned().unwrap_or(Any::Null);
+                    if self.adds(item) {
+                        if !self.deletes(item) && !attr_eq(&current, value) {
+                            delta.flush_retain();
+                            let old = delta.old.get(key.as_str()).cloned().unwrap_or(Any::Null);
+                            if attr_eq(&old, value) {
+                                delta.attributes.remove(key.as_str());
+                            } else {
+                                delta.attributes.insert(key.to_string(), value.clone());
+                            }
+                        }
+                    } else if self.deletes(item) {
+                        delta.old.insert(key.to_string(), value.clone());
+                        if !attr_eq(&current, value) {
+                            delta.flush_retain();
+                            delta.attributes.insert(key.to_string(), current);
+                        }
+                    } else if !item.deleted() {
+                        delta.old.insert(key.to_string(), value.clone());
+                        if let Some(attr) = delta.attributes.get(key.as_str())
+                            && !attr_eq(attr, value)
+                        {
+                            delta.flush_retain();
+                            if is_nullish(value) {
+                                delta.attributes.remove(key.as_str());
+                            } else {
+                                delta.attributes.insert(key.to_string(), value.clone());
+                            }
+                        }
+                    }
+
+                    if !item.deleted() {
+                        if let Some(TextAction::Insert) = delta.action {
+                            delta.flush();
+                        }
+                        if is_nullish(value) {
+                            delta.current.remove(key.as_str());
+                        } else {
+                            delta.current.insert(key.to_string(), value.clone());
+                        }
+                    }
+                }
+                content if item.countable() => {
+                    let len = match content {
+                        Content::String(text) => self.offset_kind.str_len(text),
+                        _ => item.len(),
+                    };
+
+                    if self.adds(item) {
+                        if !self.deletes(item) {
+                            match content {
+                                Content::String(text) => {
+                                    delta.switch(TextAction::Insert);
+                                    delta.insert.push_str(text);
+                                }
+                                Content::Embed(embed) => delta.push_embed(vec![embed.clone()]),
+                                Content::Any(values) => delta.push_embed(values.clone()),
+                                Content::Binary(value) => delta.push_embed(vec![Any::Binary(value.clone())]),
+                                Content::Json(values) => delta.push_embed(
+                                    values
+                                        .iter()
+                                        .map(|value| value.clone().map(Any::String).unwrap_or(Any::Undefined))
+                                        .collect(),
+                                ),
+                                _ => {}
+                            }
+                        }
+                    } else if self.deletes(item) {
+                        delta.switch(TextAction::Delete);
+                        delta.delete += len;
+                    } else if !item.deleted() {
+                        delta.switch(TextAction::Retain);
+                        delta.retain += len;
+                    }
+                }
+                _ => {}
+            }
+
+            cur = item.right.clone();
+        }
+
+        delta.finish()
+    }
+}
+
+fn is_nullish(value: &Any) -> bool {
+    matches!(value, Any::Null | Any::Undefined)
+}
+
+fn attr_eq(a: &Any, b: &Any) -> bool {
+    a == b || is_nullish(a) && is_nullish(b)
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum TextAction {
+    Insert,
+    Retain,
+    Delete,
+}
+
+#[derive(Default)]
+struct TextDeltaBuilder {
+    ops: TextDelta,
+    action: Option<TextAction>,
+    insert: String,
+    retain: u64,
+    delete: u64,
+    // the attribute changes of retained text
+    attributes: TextAttributes,
+    // the attributes at the cursor after this transaction
+    current: TextAttributes,
+    // the attributes at the cursor before this transaction
+    old: TextAttributes,
+}
+
+impl TextDeltaBuilder {
+    fn switch(&mut self, action: TextAction) {
+        if self.action != Some(action) {
+            self.flush();
+            self.action = Some(action);
+        }
+    }
+
+    fn flush_retain(&mut self) {
+        if let Some(TextAction::Retain) = self.action {
+            self.flush();
+        }
+    }
+
+    fn push_embed(&mut self, embed: Vec<Any>) {
+        self.flush();
+        self.ops.push(TextDeltaOp::Insert {
+            insert: TextInsert::Embed(embed),
+            format: self.current_format(),
+        });
+    }
+
+    fn current_format(&self) -> Option<TextAttributes> {
+        if self.current.is_empty() {
+            None
+        } else {
+            Some(self.current.clone())
+        }
+    }
+
+    fn flush(&mut self) {
+        match self.action.take() {
+            Some(TextAction::Insert) if !self.insert.is_empty() => {
+                let format = self.current_format();
+                self.ops.push(TextDeltaOp::Insert {
+                    insert: TextInsert::Text(mem::take(&mut self.insert)),
+                    format,
+                });
+            }
+            Some(TextAction::Retain) if self.retain > 0 => {
+                self.ops.push(TextDeltaOp::Retain {
+                    retain: mem::take(&mut self.retain),
+                    format: if self.attributes.is_empty() {
+                        None
+                    } else {
+                        Some(self.attributes.clone())
+                    },
+                });
+            }
+            Some(TextAction::Delete) if self.delete > 0 => {
+                self.ops.push(TextDeltaOp::Delete {
+                    delete: mem::take(&mut self.delete),
+                });
+            }
+            _ => {}
+        }
+    }
+
+    fn finish(mut self) -> TextDelta {
+        self.flush();
+
+        // the trailing retains without formatting are meaningless
+        while let Some(TextDeltaOp::Retain { format: None, .. }) = self.ops.last() {
+            self.ops.pop();
+        }
+
+        self.ops
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{Doc, DocOptions, loom_model, sync::Mutex};
+
+    fn collect<E: Clone + Send + 'static>() -> (Arc<Mutex<Vec<E>>>, impl Fn(&E) + Send + Sync + 'static) {
+        let events = Arc::new(Mutex::new(Vec::new()));
+        let events_ref = events.clone();
+
+        (events, move |event: &E| events_ref.lock().unwrap().push(event.clone()))
+    }
+
+    #[test]
+    fn test_array_event() {
+        loom_model!({
+            let doc = DocOptions::new().with_client_id(1).build();
+            let mut array = doc.get_or_create_array("array").unwrap();
+            array.push(1).unwrap();
+            array.push(2).unwrap();
+            array.push(3).unwrap();
+
+            let (events, observer) = collect::<ArrayEvent>();
+            let subscription = array.observe(observer);
+
+            doc.transact(Some("editor".into()), |_| {
+                array.remove(0, 1).unwrap();
+                array.insert(1, "a").unwrap();
+                array.insert(2, "b").unwrap();
+            });
+
+            {
+                let events = events.lock().unwrap();
+                assert_eq!(events.len(), 1);
+                assert_eq!(events[0].target(), &Value::Array(array.clone()));
+                assert_eq!(events[0].origin(), Some(&"editor".into()));
+                assert!(events[0].is_local());
+                assert_eq!(
+                    events[0].delta(),
+                    &vec![
+                        ArrayDeltaOp::Delete(1),
+                        ArrayDeltaOp::Retain(1),
+                        ArrayDeltaOp::Insert(vec![Value::from("a"), Value::from("b")]),
+                    ]
+                );
+            }
+
+            // the events of remote changes
+            let mut remote = DocOptions::new().with_client_id(2).build();
+            remote
+                .apply_update_from_binary_v1(doc.encode_update_v1().unwrap())
+                .unwrap();
+            let mut remote_array = remote.get_or_create_array("array").unwrap();
+            remote_array.push(4).unwrap();
+            let mut doc_ref = doc.clone();
+            doc_ref
+                .apply_update_from_binary_v1(remote.encode_state_as_update_v1(&doc.get_state_vector()).unwrap())
+                .unwrap();
+
+            {
+                let events = events.lock().unwrap();
+                assert_eq!(events.len(), 2);
+                assert!(!events[1].is_local());
+                assert_eq!(
+                    events[1].delta(),
+                    &vec![ArrayDeltaOp::Retain(4), ArrayDeltaOp::Insert(vec![Value::from(4)])]
+                );
+            }
+
+            drop(subscription);
+            array.push(5).unwrap();
+            assert_eq!(events.lock().unwrap().len(), 2);
+        });
+    }
+
+    #[test]
+    fn test_map_event() {
+        loom_model!({
+            let doc = Doc::default();
+            let mut map = doc.get_or_create_map("map").unwrap();
+            map.insert("update".into(), 1).unwrap();
+            map.insert("remove".into(), 2).unwrap();
+
+            let (events, observer) = collect::<MapEvent>();
+            let _subscription = map.observe(observer);
+
+            doc.transact(None, |_| {
+                map.insert("update".into(), "a").unwrap();
+                map.insert("update".into(), "b").unwrap();
+                map.remove("remove");
+                map.insert("insert".into(), 3).unwrap();
+                // inserted and removed in the same transaction
+                map.insert("temp".into(), 4).unwrap();
+                map.remove("temp");
+            });
+
+            let events = events.lock().unwrap();
+            assert_eq!(events.len(), 1);
+            let keys_changed = events[0].keys_changed();
+            assert_eq!(keys_changed.len(), 3);
+            assert_eq!(
+                keys_changed.get("update"),
+                Some(&EntryChange::Updated(Value::from(1), Value::from("b")))
+            );
+            assert_eq!(keys_changed.get("remove"), Some(&EntryChange::Removed(Value::from(2))));
+            assert_eq!(keys_changed.get("insert"), Some(&EntryChange::Inserted(Value::from(3))));
+        });
+    }
+
+    #[test]
+    fn test_text_event() {
+        loom_model!({
+            let doc = Doc::default();
+            let mut text = doc.get_or_create_text("text").unwrap();
+            text.insert(0, "hello world").unwrap();
+
+            let (events, observer) = collect::<TextEvent>();
+            let _subscription = text.observe(observer);
+
+            text.insert(5, " 😀").unwrap();
+            text.remove(0, 1).unwrap();
+            text.format(1, 3, TextAttributes::from([("bold".into(), Any::True)]))
+                .unwrap();
+
+            let events = events.lock().unwrap();
+            assert_eq!(events.len(), 3);
+            assert_eq!(
+                events[0].delta(),
+                &vec![
+                    TextDeltaOp::Retain {
+                        retain: 5,
+                        format: None
+                    },
+                    TextDeltaOp::Insert {
+                        insert: TextInsert::Text(" 😀".into()),
+                        format: None
+                    },
+                ]
+            );
+            assert_eq!(events[1].delta(), &vec![TextDeltaOp::Delete { delete: 1 }]);
+            assert_eq!(
+                events[2].delta(),
+                &vec![
+                    TextDeltaOp::Retain {
+                        retain: 1,
+                        format: None
+                    },
+                    TextDeltaOp::Retain {
+                        retain: 3,
+                        format: Some(TextAttributes::from([("bold".into(), Any::True)]))
+                    },
+                ]
+            );
+        });
+    }
+
+    #[test]
+    fn test_xml_event() {
+        loom_model!({
+            let doc = Doc::default();
+            let mut fragment = doc.get_or_create_xml_fragment("xml").unwrap();
+            let element = doc.create_xml_element("p").unwrap();
+            fragment.push(element).unwrap();
+            let mut element = fragment.get(0).and_then(|value| match value {
+                Value::XMLElement(element) => Some(element),
+                _ => None,
+            });
+            let element = element.as_mut().unwrap();
+
+            let (fragment_events, observer) = collect::<XMLEvent>();
+            let _fragment_subscription = fragment.observe(observer);
+            let (element_events, observer) = collect::<XMLEvent>();
+            let _element_subscription = element.observe(observer);
+
+            doc.transact(None, |_| {
+                element.insert_attribute("class".into(), "title").unwrap();
+                element.push(doc.create_xml_text().unwrap()).unwrap();
+            });
+
+            assert!(fragment_events.lock().unwrap().is_empty());
+            let events = element_events.lock().unwrap();
+            assert_eq!(events.len(), 1);
+            assert_eq!(
+                events[0].keys_changed().get("class"),
+                Some(&EntryChange::Inserted(Value::from("title")))
+            );
+            assert!(matches!(
+                events[0].delta().as_slice(),
+                [ArrayDeltaOp::Insert(values)] if matches!(values.as_slice(), [Value::XMLText(_)])
+            ));
+        });
+    }
+
+    #[test]
+    fn test_event_of_nested_types() {
+        loom_model!({
+            let doc = Doc::default();
+            let mut array = doc.get_or_create_array("array").unwrap();
+
+            let (events, observer) = collect::<ArrayEvent>();
+            let _subscription = array.observe(observer);
+
+            // the types created in the same transaction don't emit events
+            let (nested, observer) = collect::<MapEvent>();
+            let _nested_subscription = doc.transact(None, |_| {
+                array.push(doc.create_map().unwrap()).unwrap();
+                let mut map = array.get(0).and_then(|value| value.to_map()).unwrap();
+                let subscription = map.observe(observer);
+                map.insert("key".into(), 1).unwrap();
+                subscription
+            });
+            assert_eq!(events.lock().unwrap().len(), 1);
+            assert!(nested.lock().unwrap().is_empty());
+
+            let mut map = array.get(0).and_then(|value| value.to_map()).unwrap();
+            let (nested, observer) = collect::<MapEvent>();
+            let _subscription = map.observe(observer);
+            map.insert("key".into(), 2).unwrap();
+            assert_eq!(events.lock().unwrap().len(), 1);
+            assert_eq!(nested.lock().unwrap().len(), 1);
+        });
+    }
+
+    #[test]
+    fn test_observe_deep() {
+        loom_model!({
+            let doc = DocOptions::new().with_client_id(1).build();
+            let mut state = doc.get_or_create_map("state").unwrap();
+            state.insert("list".into(), doc.create_array().unwrap()).unwrap();
+            let mut list = state.get("list").and_then(|value| value.to_array()).unwrap();
+            list.push(1).unwrap();
+            list.push(2).unwrap();
+            list.push(doc.create_map().unwrap()).unwrap();
+            let mut item = list.get(2).and_then(|value| value.to_map()).unwrap();
+
+            let events = Arc::new(Mutex::new(Vec::new()));
+            let events_ref = events.clone();
+            let subscription = state.observe_deep(move |events| {
+                events_ref.lock().unwrap().push(events.to_vec());
+            });
+
+            // the deep observers of nearer ancestors are called first
+            let order = Arc::new(Mutex::new(Vec::new()));
+            let order_ref = order.clone();
+            let _list_subscription = list.observe_deep(move |_| order_ref.lock().unwrap().push("list"));
+            let order_ref = order.clone();
+            let _state_subscription = state.observe_deep(move |_| order_ref.lock().unwrap().push("state"));
+            item.insert("done".into(), false).unwrap();
+            assert_eq!(*order.lock().unwrap(), vec!["list", "state"]);
+            events.lock().unwrap().clear();
+
+            doc.transact(None, |_| {
+                item.insert("done".into(), true).unwrap();
+                list.push(3).unwrap();
+                state.insert("title".into(), "todo").unwrap();
+            });
+
+            {
+                let events = events.lock().unwrap();
+                assert_eq!(events.len(), 1);
+                let events = &events[0];
+                assert_eq!(events.len(), 3);
+                assert!(matches!(&events[0], Event::Map(event) if event.target() == &Value::Map(state.clone())));
+                assert_eq!(events[0].path(), &[]);
+                assert!(matches!(&events[1], Event::Array(_)));
+                assert_eq!(events[1].path(), &[PathSegment::Key("list".into())]);
+                assert!(matches!(&events[2], Event::Map(event) if event.keys_changed().contains_key("done")));
+                assert_eq!(
+                    events[2].path(),
+                    &[PathSegment::Key("list".into()), PathSegment::Index(2)]
+                );
+            }
+
+            // the paths of remote changes
+            let mut remote = DocOptions::new().with_client_id(2).build();
+            remote
+                .apply_update_from_binary_v1(doc.encode_update_v1().unwrap())
+                .unwrap();
+            let remote_list = remote
+                .get_or_create_map("state")
+                .unwrap()
+                .get("list")
+                .and_then(|value| value.to_array())
+                .unwrap();
+            remote_list.clone().remove(0, 1).unwrap();
+            let mut remote_item = remote_list.get(1).and_then(|value| value.to_map()).unwrap();
+            remote_item.insert("done".into(), false).unwrap();
+
+            let mut doc_ref = doc.clone();
+            doc_ref
+                .apply_update_from_binary_v1(remote.encode_state_as_update_v1(&doc.get_state_vector()).unwrap())
+                .unwrap();
+
+            {
+                let events = events.lock().unwrap();
+                assert_eq!(events.len(), 2);
+                let paths = events[1].iter().map(|event| event.path().to_vec()).collect::<Vec<_>>();
+                assert_eq!(
+                    paths,
+                    vec![
+                        vec![PathSegment::Key("list".into())],
+                        vec![PathSegment::Key("list".into()), PathSegment::Index(1)],
+                    ]
+                );
+                assert!(events[1].iter().all(|event| !event.is_local()));
+            }
+
+            drop(subscription);
+            list.push(4).unwrap();
+            assert_eq!(events.lock().unwrap().len(), 2);
+        });
+    }
+}
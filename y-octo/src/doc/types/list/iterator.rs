@@ -2,22 +2,92 @@ use super::*;
 
 pub(crate) struct ListIterator<'a> {
     pub(super) _lock: RwLockReadGuard<'a, YType>,
-    pub(super) cur: Somr<Item>,
+    pub(super) walker: ItemWalker,
 }
 
 impl Iterator for ListIterator<'_> {
     type Item = Somr<Item>;
 
     fn next(&mut self) -> Option<Self::Item> {
-        while let Some(item) = self.cur.clone().get() {
-            let cur = std::mem::replace(&mut self.cur, item.right.clone());
-            if item.deleted() {
+        self.walker
+            .by_ref()
+            .find(|item| item.get().is_some_and(|item| !item.deleted()))
+    }
+}
+
+/// Walk the items of a list in the order they are shown, the items moved by
+/// a [Content::Move] are visited right after the move instead of where they
+/// were inserted. Deleted and uncountable items are visited as well.
+#[derive(Default)]
+pub(crate) struct ItemWalker {
+    cur: ItemRef,
+    // the move whose range is being walked, none for the list itself
+    moved: ItemRef,
+}
+
+impl ItemWalker {
+    pub fn new(start: ItemRef) -> Self {
+        Self {
+            cur: start,
+            moved: Somr::none(),
+        }
+    }
+
+    /// Continue walking from the item next to the given one
+    pub fn after(item: &ItemRef) -> Self {
+        let mut walker = Self {
+            cur: item.clone(),
+            moved: item.get().map(|item| item.moved.clone()).unwrap_or_default(),
+        };
+        walker.next();
+        walker
+    }
+
+    fn reach_end(&self) -> bool {
+        if self.cur.is_none() {
+            return true;
+        }
+
+        match self.moved.get().map(|moved| &moved.content) {
+            Some(Content::Move(content)) => content.end_item.is_some() && self.cur == content.end_item,
+            _ => false,
+        }
+    }
+}
+
+impl Iterator for ItemWalker {
+    type Item = ItemRef;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        loop {
+            if self.reach_end() {
+                // leave the range of the move, and continue after the move
+                let (right, moved) = self
+                    .moved
+                    .get()
+                    .map(|moved| (moved.right.clone(), moved.moved.clone()))?;
+                self.cur = right;
+                self.moved = moved;
+                continue;
+            }
+
+            let cur = self.cur.clone();
+            let item = cur.get()?;
+            self.cur = item.right.clone();
+
+            // the item is shown by another move
+            if item.moved != self.moved {
                 continue;
             }
 
+            if let Content::Move(content) = &item.content
+                && !item.deleted()
+            {
+                self.cur = content.start_item.clone();
+                self.moved = cur.clone();
+            }
+
             return Some(cur);
         }
-
-        None
     }
 }
@@ -1,7 +1,7 @@
 mod iterator;
 mod search_marker;
 
-pub(crate) use iterator::ListIterator;
+pub(crate) use iterator::{ItemWalker, ListIterator};
 pub(crate) use search_marker::MarkerList;
 
 use super::*;
@@ -16,19 +16,6 @@ pub(crate) struct ItemPosition {
 }
 
 impl ItemPosition {
-    pub fn forward(&mut self) {
-        if let Some(right) = self.right.get() {
-            if right.indexable() {
-                self.index += right.len();
-            }
-
-            self.left = self.right.clone();
-            self.right = right.right.clone();
-        } else {
-            // FAIL
-        }
-    }
-
     /// we found a position cursor point in between a splitable item,
     /// we need to split the item by the offset.
     ///
@@ -69,7 +56,7 @@ pub(crate) trait ListType: AsInner<Inner = YTypeRef> {
     fn iter_item(&self) -> ListIterator<'_> {
         let inner = self.as_inner().ty().unwrap();
         ListIterator {
-            cur: inner.start.clone(),
+            walker: ItemWalker::new(inner.start.clone()),
             _lock: inner,
         }
     }
@@ -90,7 +77,8 @@ pub(crate) trait ListType: AsInner<Inner = YTypeRef> {
             return Some(pos);
         }
 
-        if let Some(markers) = &inner.markers
+        if inner.moves == 0
+            && let Some(markers) = &inner.markers
             && let Some(marker) = markers.find_marker(inner, index)
         {
             if marker.index > remaining {
@@ -103,73 +91,98 @@ pub(crate) trait ListType: AsInner<Inner = YTypeRef> {
             pos.right = marker.ptr;
         };
 
+        let mut items = ItemWalker::new(pos.right.clone()).peekable();
+
         // avoid the first item of the list being deleted
-        while let Some(item) = pos.right.get() {
-            if item.deleted() {
-                pos.left = pos.right.clone();
-                pos.right = item.right.clone();
-                continue;
-            } else {
-                break;
-            }
+        while let Some(item) = items.next_if(|item| item.get().is_some_and(|item| item.deleted())) {
+            pos.left = item;
         }
 
         while remaining > 0 {
-            if let Some(item) = pos.right.get() {
-                if item.indexable() {
-                    let content_len = item.len();
-                    if remaining < content_len {
-                        pos.offset = remaining;
-                        remaining = 0;
-                    } else {
-                        pos.index += content_len;
-                        remaining -= content_len;
-                    }
+            let item_ref = items.next()?;
+            if let Some(item) = item_ref.get()
+                && item.indexable()
+            {
+                let content_len = item.len();
+                if remaining < content_len {
+                    pos.offset = remaining;
+                    remaining = 0;
+                } else {
+                    pos.index += content_len;
+                    remaining -= content_len;
                 }
-
-                pos.left = pos.right.clone();
-                pos.right = item.right.clone();
-            } else {
-                return None;
             }
+
+            pos.left = item_ref;
+        }
+
+        // the items shown next to each other may be apart in the list if
+        // they're moved, insert right after the left one
+        if let Some(left) = pos.left.get() {
+            pos.right = left.right.clone();
         }
 
         Some(pos)
     }
 
     fn insert_at(&mut self, index: u64, content: Content) -> JwstCodecResult {
-        if index > self.content_len() {
+        self.insert_contents_at(index, vec![content])
+    }
+
+    /// Insert the contents next to each other in one transaction
+    fn insert_contents_at(&mut self, index: u64, contents: Vec<Content>) -> JwstCodecResult {
+        self.as_inner()
+            .transact(|store, ty| self.insert_contents_in(store, ty, index, contents))
+            .unwrap_or(Err(JwstCodecError::DocReleased))
+    }
+
+    /// Insert the contents next to each other in a running transaction
+    fn insert_contents_in(
+        &self,
+        store: &mut DocStore,
+        ty: &mut YType,
+        index: u64,
+        contents: Vec<Content>,
+    ) -> JwstCodecResult {
+        if index > ty.len {
             return Err(JwstCodecError::IndexOutOfBound(index));
         }
 
-        if let Some((mut store, mut ty)) = self.as_inner().write() {
-            if let Some(mut pos) = self.find_pos(&ty, index) {
-                pos.normalize(&mut store)?;
-                Self::insert_after(&mut ty, &mut store, pos, content)?;
+        if let Some(mut pos) = self.find_pos(ty, index) {
+            pos.normalize(store)?;
+            for content in contents {
+                Self::insert_after(ty, store, &mut pos, content)?;
             }
-        } else {
-            return Err(JwstCodecError::DocReleased);
         }
 
         Ok(())
     }
 
-    fn insert_after(ty: &mut YType, store: &mut DocStore, pos: ItemPosition, content: Content) -> JwstCodecResult {
+    /// Insert the content at the position, and move the position to be
+    /// right after the inserted item.
+    fn insert_after(ty: &mut YType, store: &mut DocStore, pos: &mut ItemPosition, content: Content) -> JwstCodecResult {
+        let len = if content.countable() { content.clock_len() } else { 0 };
         if let Some(markers) = &ty.markers
-            && content.countable()
+            && len > 0
         {
-            markers.update_marker_changes(pos.index, content.clock_len() as i64);
+            markers.update_marker_changes(pos.index, len as i64);
         }
 
         let item = store.create_item(
             content,
             pos.left.clone(),
             pos.right.clone(),
-            Some(Parent::Type(pos.parent)),
+            Some(Parent::Type(pos.parent.clone())),
             None,
         );
 
+        let id = item.get().map(|item| item.id);
         store.integrate(Node::Item(item), 0, Some(ty))?;
+        pos.left = id
+            .and_then(|id| store.get_node(id))
+            .map(|node| node.as_item())
+            .unwrap_or_default();
+        pos.index += len;
 
         Ok(())
     }
@@ -181,15 +194,62 @@ pub(crate) trait ListType: AsInner<Inner = YTypeRef> {
 
         let ty = self.as_inner().ty().unwrap();
 
-        if let Some(pos) = self.find_pos(&ty, index) {
-            if pos.offset == 0 {
-                return Some((pos.right, 0));
-            } else {
-                return Some((pos.left, pos.offset));
+        let pos = self.find_pos(&ty, index)?;
+        if pos.offset > 0 {
+            return Some((pos.left, pos.offset));
+        }
+
+        // the right item may be moved away, find the item shown next to the left one
+        let mut items = if pos.left.is_some() {
+            ItemWalker::after(&pos.left)
+        } else {
+            ItemWalker::new(ty.start.clone())
+        };
+        items
+            .find(|item| item.get().is_some_and(|item| item.indexable()))
+            .map(|item| (item, 0))
+    }
+
+    /// Create a relative position at the index, like
+    /// `createRelativePositionFromTypeIndex` in yjs.
+    fn relative_position_at(&self, mut index: u64, assoc: Assoc) -> JwstCodecResult<RelativePosition> {
+        let ty = self.as_inner().ty().ok_or(JwstCodecError::DocReleased)?;
+        let mut position = RelativePosition {
+            assoc,
+            ..Default::default()
+        };
+        if let Some(item) = ty.item.get() {
+            position.type_id = Some(item.id);
+        } else {
+            position.root_name = ty.root_name.clone();
+        }
+
+        if assoc == Assoc::Before {
+            if index == 0 {
+                return Ok(position);
             }
+            index -= 1;
         }
 
-        None
+        let mut items = ItemWalker::new(ty.start.clone()).peekable();
+        while let Some(item_ref) = items.next() {
+            let Some(item) = item_ref.get() else {
+                break;
+            };
+            if item.indexable() {
+                if item.len() > index {
+                    position.item = Some(Id::new(item.id.client, item.id.clock + index));
+                    return Ok(position);
+                }
+                index -= item.len();
+            }
+            if items.peek().is_none() && assoc == Assoc::Before {
+                position.item = Some(item.last_id());
+                return Ok(position);
+            }
+        }
+
+        Ok(position)
     }
 
     fn remove_at(&mut self, idx: u64, len: u64) -> JwstCodecResult {
@@ -197,21 +257,23 @@ pub(crate) trait ListType: AsInner<Inner = YTypeRef> {
             return Ok(());
         }
 
-        let content_len = self.content_len();
-        if content_len == 0 {
+        self.as_inner()
+            .transact(|store, ty| self.remove_in(store, ty, idx, len))
+            .unwrap_or(Err(JwstCodecError::DocReleased))
+    }
+
+    /// Remove the items in range in a running transaction
+    fn remove_in(&self, store: &mut DocStore, ty: &mut YType, idx: u64, len: u64) -> JwstCodecResult {
+        if len == 0 || ty.len == 0 {
             return Ok(());
         }
 
-        if idx >= content_len {
+        if idx >= ty.len {
             return Err(JwstCodecError::IndexOutOfBound(idx));
         }
 
-        if let Some((mut store, mut ty)) = self.as_inner().write() {
-            if let Some(pos) = self.find_pos(&ty, idx) {
-                Self::remove_after(&mut ty, &mut store, pos, len)?;
-            }
-        } else {
-            return Err(JwstCodecError::DocReleased);
+        if let Some(pos) = self.find_pos(ty, idx) {
+            Self::remove_after(ty, store, pos, len)?;
         }
 
         Ok(())
@@ -221,9 +283,16 @@ pub(crate) trait ListType: AsInner<Inner = YTypeRef> {
         pos.normalize(store)?;
 
         let mut remaining = len;
+        let mut items = if pos.left.is_some() {
+            ItemWalker::after(&pos.left)
+        } else {
+            ItemWalker::new(pos.right.clone())
+        };
 
         while remaining > 0 {
-            let item_ref = pos.right.clone();
+            let Some(item_ref) = items.next() else {
+                break;
+            };
             let Some((indexable, content_len, item_id)) =
                 item_ref.get().map(|item| (item.indexable(), item.len(), item.id))
             else {
@@ -242,8 +311,6 @@ pub(crate) trait ListType: AsInner<Inner = YTypeRef> {
                     store.delete_item(item, Some(ty));
                 }
             }
-
-            pos.forward();
         }
 
         if let Some(markers) = &ty.markers {
@@ -252,4 +319,60 @@ pub(crate) trait ListType: AsInner<Inner = YTypeRef> {
 
         Ok(())
     }
+
+    /// Move the items in `start..end` to the index `target`, the index is
+    /// counted before moving.
+    fn move_at(&mut self, start: u64, end: u64, target: u64) -> JwstCodecResult {
+        let content_len = self.content_len();
+        if start >= end {
+            return Err(JwstCodecError::IndexOutOfBound(start));
+        }
+        if end > content_len {
+            return Err(JwstCodecError::IndexOutOfBound(end - 1));
+        }
+        if target > content_len {
+            return Err(JwstCodecError::IndexOutOfBound(target));
+        }
+        // moving the items next to themselves changes nothing
+        if (start..=end).contains(&target) {
+            return Ok(());
+        }
+
+        self.as_inner()
+            .transact(|store, ty| {
+                let (start, end) = self.range_ids(ty, store, start, end)?;
+                let content = Move::new(start, Assoc::After, end, Assoc::Before);
+
+                let mut pos = self
+                    .find_pos(ty, target)
+                    .ok_or(JwstCodecError::IndexOutOfBound(target))?;
+                pos.normalize(store)?;
+                Self::insert_after(ty, store, &mut pos, Content::Move(Box::new(content)))
+            })
+            .unwrap_or(Err(JwstCodecError::DocReleased))
+    }
+
+    /// The ids of the first and the last element in `start..end`, the items
+    /// are split at the boundaries of the range.
+    fn range_ids(&self, ty: &YType, store: &mut DocStore, start: u64, end: u64) -> JwstCodecResult<(Id, Id)> {
+        let mut pos = self.find_pos(ty, start).ok_or(JwstCodecError::IndexOutOfBound(start))?;
+        pos.normalize(store)?;
+        let first = if pos.left.is_some() {
+            ItemWalker::after(&pos.left)
+        } else {
+            ItemWalker::new(pos.right)
+        }
+        .find_map(|item| item.get().filter(|item| item.indexable()).map(|item| item.id))
+        .ok_or(JwstCodecError::IndexOutOfBound(start))?;
+
+        let mut pos = self.find_pos(ty, end).ok_or(JwstCodecError::IndexOutOfBound(end - 1))?;
+        pos.normalize(store)?;
+        let last = pos
+            .left
+            .get()
+            .map(|item| item.last_id())
+            .ok_or(JwstCodecError::IndexOutOfBound(end - 1))?;
+
+        Ok((first, last))
+    }
 }
@@ -2,7 +2,7 @@ use std::{collections::hash_map::Iter, rc::Rc};
 
 use super::*;
 use crate::{
-    JwstCodecResult,
+    JwstCodecError, JwstCodecResult,
     doc::{AsInner, Node, Parent, YTypeRef},
     impl_type,
 };
@@ -15,20 +15,20 @@ pub(crate) trait MapType: AsInner<Inner = YTypeRef> {
     }
 
     fn _insert<V: Into<Value>>(&mut self, key: String, value: V) -> JwstCodecResult {
-        if let Some((mut store, mut ty)) = self.as_inner().write() {
-            let left = ty.map.get(&SmolStr::new(&key)).cloned();
-
-            let item = store.create_item(
-                value.into().into(),
-                left.unwrap_or(Somr::none()),
-                Somr::none(),
-                Some(Parent::Type(self.as_inner().clone())),
-                Some(SmolStr::new(key)),
-            );
-            store.integrate(Node::Item(item), 0, Some(&mut ty))?;
-        }
-
-        Ok(())
+        self.as_inner()
+            .transact(|store, ty| {
+                let left = ty.map.get(&SmolStr::new(&key)).cloned();
+
+                let item = store.create_item(
+                    value.into().into(),
+                    left.unwrap_or(Somr::none()),
+                    Somr::none(),
+                    Some(Parent::Type(self.as_inner().clone())),
+                    Some(SmolStr::new(key)),
+                );
+                store.integrate(Node::Item(item), 0, Some(ty))
+            })
+            .unwrap_or(Ok(()))
     }
 
     fn _get(&self, key: &str) -> Option<Value> {
@@ -47,6 +47,33 @@ pub(crate) trait MapType: AsInner<Inner = YTypeRef> {
         })
     }
 
+    /// Get the nested type at the key, or create it if the key is empty, in
+    /// one transaction so concurrent callers get the same type.
+    fn _get_or_insert(&mut self, key: &str, kind: YTypeKind) -> JwstCodecResult<Value> {
+        self.as_inner()
+            .transact(|store, ty| {
+                let left = ty.map.get(key).cloned().unwrap_or_default();
+                if let Some(item) = left.get()
+                    && !item.deleted()
+                {
+                    return Ok(Value::from(&item.content));
+                }
+
+                let nested = self.as_inner().create_empty(store, kind, None);
+                let item = store.create_item(
+                    Content::Type(nested.clone()),
+                    left,
+                    Somr::none(),
+                    Some(Parent::Type(self.as_inner().clone())),
+                    Some(SmolStr::new(key)),
+                );
+                store.integrate(Node::Item(item), 0, Some(ty))?;
+
+                Ok(Value::from(nested))
+            })
+            .unwrap_or(Err(JwstCodecError::DocReleased))
+    }
+
     fn _contains_key(&self, key: &str) -> bool {
         if let Some(ty) = self.as_inner().ty() {
             ty.map
@@ -59,12 +86,26 @@ pub(crate) trait MapType: AsInner<Inner = YTypeRef> {
     }
 
     fn _remove(&mut self, key: &str) {
-        if let Some((mut store, mut ty)) = self.as_inner().write()
-            && let Some(item) = ty.map.get(key).cloned()
-            && let Some(item) = item.get()
-        {
-            store.delete_item(item, Some(&mut ty));
-        }
+        self.as_inner().transact(|store, ty| {
+            if let Some(item) = ty.map.get(key).cloned()
+                && let Some(item) = item.get()
+            {
+                store.delete_item(item, Some(ty));
+            }
+        });
+    }
+
+    fn _clear(&mut self) {
+        self.as_inner().transact(|store, ty| {
+            let items = ty.map.values().cloned().collect::<Vec<_>>();
+            for item in items {
+                if let Some(item) = item.get()
+                    && !item.deleted()
+                {
+                    store.delete_item(item, Some(ty));
+                }
+            }
+        });
     }
 
     fn _len(&self) -> u64 {
@@ -168,11 +209,127 @@ impl Map {
         self._insert(key, value)
     }
 
+    /// Insert the value and create the nested types in it recursively,
+    /// returns the inserted value.
+    ///
+    /// ```
+    /// use y_octo::{AHashMap, Any, Doc, Prelim};
+    ///
+    /// let doc = Doc::default();
+    /// let mut map = doc.get_or_create_map("map").unwrap();
+    /// let page = Prelim::Map(AHashMap::from_iter([
+    ///     ("list".into(), Prelim::from(Any::Array(vec![1.into(), 2.into()]))),
+    ///     ("title".into(), Prelim::Text("hello".into())),
+    /// ]));
+    /// map.insert_prelim("page".into(), page).unwrap();
+    ///
+    /// let page = map.get("page").unwrap().to_map().unwrap();
+    /// let mut list = page.get("list").unwrap().to_array().unwrap();
+    /// list.push(3).unwrap();
+    /// assert_eq!(list.len(), 3);
+    /// assert_eq!(page.get("title").unwrap().to_text().unwrap().to_string(), "hello");
+    /// ```
+    pub fn insert_prelim<P: Into<Prelim>>(&mut self, key: String, prelim: P) -> JwstCodecResult<Value> {
+        let mut map = self.clone();
+        prelim.into().integrate(&self.0.store, |value| map.insert(key, value))
+    }
+
     #[inline(always)]
     pub fn get(&self, key: &str) -> Option<Value> {
         self._get(key)
     }
 
+    /// Get the value at the key, [JwstCodecError::KeyNotFound] if the key
+    /// doesn't exist.
+    pub fn try_get(&self, key: &str) -> JwstCodecResult<Value> {
+        self._get(key)
+            .ok_or_else(|| JwstCodecError::KeyNotFound(key.to_string()))
+    }
+
+    fn get_any<T: TryFrom<Any, Error = JwstCodecError>>(&self, key: &str) -> JwstCodecResult<T> {
+        // the shared types can't be converted into plain values
+        T::try_from(self.try_get(key)?.to_any().unwrap_or(Any::Undefined))
+    }
+
+    /// Get the string at the key, [JwstCodecError::KeyNotFound] if the key
+    /// doesn't exist, and [JwstCodecError::UnexpectedType] if the value is
+    /// not a string. The other typed getters work in the same way.
+    ///
+    /// ```
+    /// use y_octo::{Doc, JwstCodecError};
+    ///
+    /// let doc = Doc::default();
+    /// let mut map = doc.get_or_create_map("map").unwrap();
+    /// map.insert("title".into(), "hello").unwrap();
+    ///
+    /// assert_eq!(map.get_string("title").unwrap(), "hello");
+    /// assert_eq!(map.get_i64("title"), Err(JwstCodecError::UnexpectedType("Integer")));
+    /// assert_eq!(map.get_string("body"), Err(JwstCodecError::KeyNotFound("body".into())));
+    /// ```
+    pub fn get_string(&self, key: &str) -> JwstCodecResult<String> {
+        self.get_any(key)
+    }
+
+    /// Get the integer at the key, the integral floats are converted as well
+    /// since yjs encodes the integers out of the range of i32 as float64
+    pub fn get_i64(&self, key: &str) -> JwstCodecResult<i64> {
+        self.get_any(key)
+    }
+
+    /// Get the number at the key, integers are converted as well
+    pub fn get_f64(&self, key: &str) -> JwstCodecResult<f64> {
+        self.get_any(key)
+    }
+
+    pub fn get_bool(&self, key: &str) -> JwstCodecResult<bool> {
+        self.get_any(key)
+    }
+
+    pub fn get_map(&self, key: &str) -> JwstCodecResult<Map> {
+        self.try_get(key)?.to_map().ok_or(JwstCodecError::UnexpectedType("Map"))
+    }
+
+    pub fn get_array(&self, key: &str) -> JwstCodecResult<Array> {
+        self.try_get(key)?
+            .to_array()
+            .ok_or(JwstCodecError::UnexpectedType("Array"))
+    }
+
+    pub fn get_text(&self, key: &str) -> JwstCodecResult<Text> {
+        self.try_get(key)?
+            .to_text()
+            .ok_or(JwstCodecError::UnexpectedType("Text"))
+    }
+
+    /// Get the map at the key, or insert an empty map if the key doesn't
+    /// exist. The lookup and the insertion happen in one transaction, so
+    /// the map is never inserted twice locally.
+    ///
+    /// ```
+    /// use y_octo::Doc;
+    ///
+    /// let doc = Doc::default();
+    /// let mut map = doc.get_or_create_map("map").unwrap();
+    /// let mut meta = map.get_or_insert_map("meta").unwrap();
+    /// meta.insert("title".into(), "hello").unwrap();
+    ///
+    /// let meta = map.get_or_insert_map("meta").unwrap();
+    /// assert_eq!(meta.get_string("title").unwrap(), "hello");
+    /// ```
+    pub fn get_or_insert_map(&mut self, key: &str) -> JwstCodecResult<Map> {
+        self._get_or_insert(key, YTypeKind::Map)?
+            .to_map()
+            .ok_or(JwstCodecError::UnexpectedType("Map"))
+    }
+
+    /// Get the array at the key, or insert an empty array if the key doesn't
+    /// exist, see [Map::get_or_insert_map].
+    pub fn get_or_insert_array(&mut self, key: &str) -> JwstCodecResult<Array> {
+        self._get_or_insert(key, YTypeKind::Array)?
+            .to_array()
+            .ok_or(JwstCodecError::UnexpectedType("Array"))
+    }
+
     #[inline(always)]
     pub fn contains_key(&self, key: &str) -> bool {
         self._contains_key(key)
@@ -183,6 +340,12 @@ impl Map {
         self._remove(key)
     }
 
+    /// Remove all entries of the map
+    #[inline(always)]
+    pub fn clear(&mut self) {
+        self._clear()
+    }
+
     #[inline(always)]
     pub fn len(&self) -> u64 {
         self._len()
@@ -193,6 +356,37 @@ impl Map {
         self.len() == 0
     }
 
+    /// Observe the changes of map, the observer is removed when the returned
+    /// [Subscription] is dropped.
+    pub fn observe(&self, f: impl Fn(&MapEvent) + Send + Sync + 'static) -> Subscription {
+        self.0.observe(Arc::new(move |event| {
+            if let Event::Map(event) = event {
+                f(event)
+            }
+        }))
+    }
+
+    /// Observe the changes of this map and all nested types, the events of a
+    /// transaction are received at once with their paths from this map.
+    ///
+    /// ```
+    /// use y_octo::{Doc, PathSegment};
+    ///
+    /// let doc = Doc::default();
+    /// let mut state = doc.get_or_create_map("state").unwrap();
+    /// state.insert("list".into(), doc.create_array().unwrap()).unwrap();
+    /// let mut list = state.get("list").and_then(|value| value.to_array()).unwrap();
+    ///
+    /// let _subscription = state.observe_deep(|events| {
+    ///     assert_eq!(events.len(), 1);
+    ///     assert_eq!(events[0].path(), &[PathSegment::Key("list".into())]);
+    /// });
+    /// list.push("item").unwrap();
+    /// ```
+    pub fn observe_deep(&self, f: impl Fn(&[Event]) + Send + Sync + 'static) -> Subscription {
+        self.0.observe_deep(Arc::new(move |events: &Vec<Event>| f(events)))
+    }
+
     #[inline(always)]
     pub fn iter(&self) -> EntriesIterator<'_> {
         self._entries()
@@ -229,7 +423,7 @@ impl serde::Serialize for Map {
 #[cfg(test)]
 mod tests {
     use super::*;
-    use crate::{Any, Doc, loom_model};
+    use crate::{Any, Doc, DocOptions, loom_model};
 
     #[test]
     fn test_map_basic() {
@@ -317,4 +511,75 @@ mod tests {
             )
         });
     }
+
+    #[test]
+    fn test_map_typed_getters() {
+        loom_model!({
+            let doc = Doc::new();
+            let mut map = doc.get_or_create_map("map").unwrap();
+            map.insert("string".into(), "value").unwrap();
+            map.insert("int".into(), 1).unwrap();
+            map.insert("big".into(), i64::MAX).unwrap();
+            map.insert("float".into(), 1.5).unwrap();
+            map.insert("timestamp".into(), Any::Float64(1.7e12.into())).unwrap();
+            map.insert("huge".into(), Any::Float64(1e20.into())).unwrap();
+            map.insert("bool".into(), true).unwrap();
+            map.insert("text".into(), doc.create_text().unwrap()).unwrap();
+
+            assert_eq!(map.get_string("string").unwrap(), "value");
+            assert_eq!(map.get_i64("int").unwrap(), 1);
+            assert_eq!(map.get_i64("big").unwrap(), i64::MAX);
+            assert_eq!(map.get_f64("float").unwrap(), 1.5);
+            assert_eq!(map.get_f64("int").unwrap(), 1.0);
+            // the integral floats from yjs are accepted as integers
+            assert_eq!(map.get_i64("timestamp").unwrap(), 1_700_000_000_000);
+            assert!(map.get_bool("bool").unwrap());
+            assert!(map.get_text("text").is_ok());
+
+            assert_eq!(map.get_i64("float"), Err(JwstCodecError::UnexpectedType("Integer")));
+            assert_eq!(map.get_i64("huge"), Err(JwstCodecError::UnexpectedType("Integer")));
+            assert_eq!(map.get_bool("string"), Err(JwstCodecError::UnexpectedType("Boolean")));
+            assert_eq!(map.get_string("text"), Err(JwstCodecError::UnexpectedType("String")));
+            assert_eq!(map.get_map("text").err(), Some(JwstCodecError::UnexpectedType("Map")));
+            assert_eq!(
+                map.get_array("missing").err(),
+                Some(JwstCodecError::KeyNotFound("missing".into()))
+            );
+
+            map.clear();
+            assert!(map.is_empty());
+            assert_eq!(
+                map.get_string("string"),
+                Err(JwstCodecError::KeyNotFound("string".into()))
+            );
+        });
+    }
+
+    #[test]
+    fn test_map_get_or_insert() {
+        loom_model!({
+            let doc = DocOptions::new().with_client_id(1).build();
+            let mut map = doc.get_or_create_map("map").unwrap();
+
+            let mut meta = map.get_or_insert_map("meta").unwrap();
+            meta.insert("title".into(), "hello").unwrap();
+            assert_eq!(map.get_or_insert_map("meta").unwrap(), meta);
+            assert_eq!(map.len(), 1);
+
+            let mut list = map.get_or_insert_array("list").unwrap();
+            list.push(1).unwrap();
+            assert_eq!(map.get_array("list").unwrap().len(), 1);
+            assert_eq!(
+                map.get_or_insert_map("list").err(),
+                Some(JwstCodecError::UnexpectedType("Map"))
+            );
+
+            // the entry is created again after it's removed
+            map.remove("meta");
+            assert!(map.get_or_insert_map("meta").unwrap().is_empty());
+
+            let remote = Doc::try_from_binary_v1(doc.encode_update_v1().unwrap()).unwrap();
+            assert_eq!(remote.to_json(), doc.to_json());
+        });
+    }
 }
@@ -1,6 +1,9 @@
 mod array;
+mod event;
 mod list;
 mod map;
+mod path;
+mod prelim;
 mod text;
 mod value;
 mod xml;
@@ -12,8 +15,12 @@ use std::{
 };
 
 pub use array::*;
+pub use event::*;
+pub(crate) use list::ItemWalker;
 use list::*;
 pub use map::*;
+pub use path::*;
+pub use prelim::*;
 pub use text::*;
 pub use value::*;
 pub use xml::*;
@@ -33,12 +40,21 @@ pub(crate) struct YType {
     pub item: Somr<Item>,
     pub map: HashMap<SmolStr, Somr<Item>>,
     pub len: u64,
+    /// The length measured in the offset kind of store, it only differs from
+    /// `len` for the strings of text.
+    pub offset_len: u64,
     /// The tag name of XMLElement and XMLHook type
     pub name: Option<String>,
     /// The name of the type that directly belongs the store.
     pub root_name: Option<String>,
     kind: YTypeKind,
     pub markers: Option<MarkerList>,
+    /// The number of moves not deleted in the list, the search markers are
+    /// not used while there are moves since they don't follow the moved
+    /// items, the positions are found by walking the items instead.
+    pub moves: u64,
+    pub observers: Option<Observers<Event>>,
+    pub deep_observers: Option<Observers<Vec<Event>>>,
 }
 
 #[derive(Debug, Default, Clone)]
@@ -115,7 +131,6 @@ impl YTypeRef {
         self.inner.get().and_then(|ty| ty.write().ok())
     }
 
-    #[allow(dead_code)]
     pub fn store<'a>(&self) -> Option<RwLockReadGuard<'a, DocStore>> {
         if let Some(store) = self.store.upgrade() {
             let ptr = unsafe { &*Arc::as_ptr(&store) };
@@ -144,6 +159,71 @@ impl YTypeRef {
     pub fn write(&self) -> Option<(RwLockWriteGuard<'_, DocStore>, RwLockWriteGuard<'_, YType>)> {
         self.store_mut().zip(self.ty_mut())
     }
+
+    /// Make local changes to this type, the changes are recorded in the
+    /// transaction in progress, or a new transaction committed right after
+    /// `f` returns.
+    pub fn transact<R>(&self, f: impl FnOnce(&mut DocStore, &mut YType) -> R) -> Option<R> {
+        let store_ref = self.store.upgrade()?;
+        let mut store = lock_store(&store_ref);
+        let mut ty = self.ty_mut()?;
+
+        let scope = store.begin_transaction(None, true);
+        let ret = f(&mut store, &mut ty);
+        drop(ty);
+        commit_transaction(store, scope, false);
+
+        Some(ret)
+    }
+
+    /// Observe the events of this type, the observer is removed when the
+    /// returned [Subscription] is dropped.
+    pub fn observe(&self, observer: Observer<Event>) -> Subscription {
+        let observers = self
+            .ty_mut()
+            .map(|mut ty| ty.observers.get_or_insert_with(Observers::default).clone())
+            .unwrap_or_default();
+
+        Subscription::new(&observers, observer)
+    }
+
+    /// Observe the events of this type and all nested types, the events of a
+    /// transaction are received at once.
+    pub fn observe_deep(&self, observer: Observer<Vec<Event>>) -> Subscription {
+        let observers = self
+            .ty_mut()
+            .map(|mut ty| ty.deep_observers.get_or_insert_with(Observers::default).clone())
+            .unwrap_or_default();
+
+        Subscription::new(&observers, observer)
+    }
+
+    /// Create an empty type with the same kind and tag name in the store,
+    /// like `_copy` in yjs.
+    pub fn copy_empty(&self, store: &mut DocStore) -> Option<YTypeRef> {
+        let ty = self.ty()?;
+        Some(self.create_empty(store, ty.kind, ty.name.clone()))
+    }
+
+    /// Create an empty type in the store of this type, it's used to create
+    /// nested types while the store is locked.
+    pub fn create_empty(&self, store: &mut DocStore, kind: YTypeKind, name: Option<String>) -> YTypeRef {
+        let inner = Somr::new(RwLock::new(YType {
+            kind,
+            name,
+            markers: YTypeBuilder::markers(kind),
+            ..Default::default()
+        }));
+
+        let ty = YTypeRef {
+            store: self.store.clone(),
+            inner,
+        };
+        let ty_ref = ty.clone();
+        store.dangling_types.insert(ty.inner.ptr().as_ptr() as usize, ty);
+
+        ty_ref
+    }
 }
 
 pub(crate) struct YTypeBuilder {
@@ -177,7 +257,6 @@ impl YTypeBuilder {
         self
     }
 
-    #[allow(dead_code)]
     pub fn set_tag_name(mut self, tag_name: String) -> Self {
         self.name = Some(tag_name);
 
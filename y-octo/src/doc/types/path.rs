@@ -0,0 +1,309 @@
+use std::fmt::Display;
+
+use super::*;
+
+/// A segment of the path to a nested type, the key in map or the index in
+/// array.
+///
+/// The segments are resolved by the type they're applied to, so numeric keys
+/// can be used as indexes of arrays, and indexes can be used as keys of maps.
+#[derive(Debug, Clone, PartialEq, Eq, Hash, serde::Serialize, serde::Deserialize)]
+#[serde(untagged)]
+pub enum PathSegment {
+    Key(String),
+    Index(u64),
+}
+
+impl PathSegment {
+    fn key(&self) -> String {
+        match self {
+            PathSegment::Key(key) => key.clone(),
+            PathSegment::Index(index) => index.to_string(),
+        }
+    }
+
+    fn index(&self) -> Option<u64> {
+        match self {
+            PathSegment::Key(key) => key.parse().ok(),
+            PathSegment::Index(index) => Some(*index),
+        }
+    }
+}
+
+impl Display for PathSegment {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            PathSegment::Key(key) => write!(f, "{key}"),
+            PathSegment::Index(index) => write!(f, "{index}"),
+        }
+    }
+}
+
+impl From<&str> for PathSegment {
+    fn from(value: &str) -> Self {
+        PathSegment::Key(value.to_string())
+    }
+}
+
+impl From<String> for PathSegment {
+    fn from(value: String) -> Self {
+        PathSegment::Key(value)
+    }
+}
+
+impl From<&String> for PathSegment {
+    fn from(value: &String) -> Self {
+        PathSegment::Key(value.clone())
+    }
+}
+
+impl From<u64> for PathSegment {
+    fn from(value: u64) -> Self {
+        PathSegment::Index(value)
+    }
+}
+
+impl From<&PathSegment> for PathSegment {
+    fn from(value: &PathSegment) -> Self {
+        value.clone()
+    }
+}
+
+/// The path to a value in nested types, the first segment is the name of the
+/// root type when it's used with [Doc::get_path], see [Value::at].
+///
+/// It's serialized as a list of strings and numbers, like `["blocks", 0]`.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Hash, serde::Serialize, serde::Deserialize)]
+#[serde(transparent)]
+pub struct Path(Vec<PathSegment>);
+
+impl Path {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn push<S: Into<PathSegment>>(&mut self, segment: S) {
+        self.0.push(segment.into());
+    }
+}
+
+impl std::ops::Deref for Path {
+    type Target = [PathSegment];
+
+    fn deref(&self) -> &Self::Target {
+        &self.0
+    }
+}
+
+impl Display for Path {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        for (i, segment) in self.0.iter().enumerate() {
+            if i > 0 {
+                write!(f, "/")?;
+            }
+            write!(f, "{segment}")?;
+        }
+
+        Ok(())
+    }
+}
+
+impl<S: Into<PathSegment>> FromIterator<S> for Path {
+    fn from_iter<I: IntoIterator<Item = S>>(iter: I) -> Self {
+        Self(iter.into_iter().map(Into::into).collect())
+    }
+}
+
+impl<S: Into<PathSegment>> From<Vec<S>> for Path {
+    fn from(value: Vec<S>) -> Self {
+        value.into_iter().collect()
+    }
+}
+
+impl<S: Into<PathSegment> + Clone> From<&[S]> for Path {
+    fn from(value: &[S]) -> Self {
+        value.iter().cloned().collect()
+    }
+}
+
+impl<S: Into<PathSegment> + Clone, const N: usize> From<&[S; N]> for Path {
+    fn from(value: &[S; N]) -> Self {
+        value.iter().cloned().collect()
+    }
+}
+
+impl From<&Path> for Path {
+    fn from(value: &Path) -> Self {
+        value.clone()
+    }
+}
+
+impl Value {
+    /// Get the nested value at the path, maps are accessed by keys and arrays
+    /// are accessed by indexes. [None] if any segment doesn't exist.
+    ///
+    /// ```
+    /// use y_octo::{Any, Doc, Value};
+    ///
+    /// let doc = Doc::from_json("page", serde_json::from_str(r#"{ "blocks": [{ "title": "hello" }] }"#).unwrap())
+    ///     .unwrap();
+    /// let page = Value::Map(doc.get_or_create_map("page").unwrap());
+    /// assert_eq!(page.at(&["blocks", "0", "title"]), Some(Value::from("hello")));
+    /// assert_eq!(page.at(&["blocks", "1"]), None);
+    /// ```
+    pub fn at<P: Into<Path>>(&self, path: P) -> Option<Value> {
+        path.into()
+            .iter()
+            .try_fold(self.clone(), |value, segment| value.child(segment))
+    }
+
+    fn child(&self, segment: &PathSegment) -> Option<Value> {
+        match self {
+            Value::Map(map) => map.get(&segment.key()),
+            Value::Array(array) => array.get(segment.index()?),
+            Value::Any(Any::Object(object)) => object.get(&segment.key()).cloned().map(Value::Any),
+            Value::Any(Any::Array(values)) => values.get(segment.index()? as usize).cloned().map(Value::Any),
+            _ => None,
+        }
+    }
+
+    /// The map or array at the segment for writing, the missing keys of maps
+    /// are filled with empty maps.
+    fn child_mut(&self, segment: &PathSegment) -> JwstCodecResult<Value> {
+        let child = match self {
+            Value::Map(map) => match map.get(&segment.key()) {
+                Some(child) => child,
+                None => Value::Map(map.clone().get_or_insert_map(&segment.key())?),
+            },
+            Value::Array(array) => {
+                let index = segment.index().ok_or(JwstCodecError::UnexpectedType("Index"))?;
+                array.get(index).ok_or(JwstCodecError::IndexOutOfBound(index))?
+            }
+            _ => return Err(JwstCodecError::UnexpectedType("Map or Array")),
+        };
+
+        match child {
+            Value::Map(_) | Value::Array(_) => Ok(child),
+            _ => Err(JwstCodecError::UnexpectedType("Map or Array")),
+        }
+    }
+
+    /// Set the value at the path, the missing intermediate keys are created
+    /// as maps, see [Doc::set_path].
+    pub(crate) fn set_at(&self, path: &[PathSegment], value: Value) -> JwstCodecResult {
+        let Some((last, path)) = path.split_last() else {
+            return Err(JwstCodecError::UnexpectedType("Map or Array"));
+        };
+        let parent = path
+            .iter()
+            .try_fold(self.clone(), |value, segment| value.child_mut(segment))?;
+
+        match parent {
+            Value::Map(mut map) => map.insert(last.key(), value),
+            Value::Array(mut array) => {
+                let index = last.index().ok_or(JwstCodecError::UnexpectedType("Index"))?;
+                if index > array.len() {
+                    return Err(JwstCodecError::IndexOutOfBound(index));
+                }
+                // replace the value at the index, or append to the array
+                if index < array.len() {
+                    array.remove(index, 1)?;
+                }
+                array.insert(index, value)
+            }
+            _ => Err(JwstCodecError::UnexpectedType("Map or Array")),
+        }
+    }
+
+    /// Delete the value at the path, returns whether the value existed.
+    pub(crate) fn delete_at(&self, path: &[PathSegment]) -> JwstCodecResult<bool> {
+        let Some((last, path)) = path.split_last() else {
+            return Ok(false);
+        };
+
+        match self.at(path) {
+            Some(Value::Map(mut map)) => {
+                let key = last.key();
+                let exists = map.contains_key(&key);
+                map.remove(&key);
+                Ok(exists)
+            }
+            Some(Value::Array(mut array)) => match last.index() {
+                Some(index) if index < array.len() => array.remove(index, 1).map(|_| true),
+                _ => Ok(false),
+            },
+            _ => Ok(false),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::loom_model;
+
+    #[test]
+    fn test_path_serde() {
+        let path = Path::from(vec![PathSegment::from("blocks"), PathSegment::from(3)]);
+        assert_eq!(path.to_string(), "blocks/3");
+
+        let json = serde_json::to_string(&path).unwrap();
+        assert_eq!(json, r#"["blocks",3]"#);
+        assert_eq!(serde_json::from_str::<Path>(&json).unwrap(), path);
+    }
+
+    #[test]
+    fn test_doc_path() {
+        loom_model!({
+            let doc = DocOptions::new().with_client_id(1).build();
+            doc.set_path(&["blocks", "abc", "title"], "hello").unwrap();
+            doc.set_path(&["blocks", "abc", "children"], doc.create_array().unwrap())
+                .unwrap();
+            for i in 0..3 {
+                doc.set_path(&["blocks", "abc", "children", &i.to_string()], i).unwrap();
+            }
+
+            assert_eq!(doc.get_path(&["blocks", "abc", "title"]), Some(Value::from("hello")));
+            assert_eq!(doc.get_path(&["blocks", "abc", "children", "2"]), Some(Value::from(2)));
+            assert_eq!(doc.get_path(&["blocks", "abc", "missing"]), None);
+            assert_eq!(doc.get_path(&["missing"]), None);
+
+            // indexes are replaced in place
+            let path = Path::from_iter([PathSegment::from("blocks"), "abc".into(), "children".into(), 1.into()]);
+            doc.set_path(&path, "one").unwrap();
+            assert_eq!(doc.get_path(&path), Some(Value::from("one")));
+            let children = doc.get_path(&["blocks", "abc", "children"]).unwrap();
+            assert_eq!(children.to_array().unwrap().len(), 3);
+            assert_eq!(
+                doc.set_path(&["blocks", "abc", "children", "4"], 4),
+                Err(JwstCodecError::IndexOutOfBound(4))
+            );
+            assert_eq!(
+                doc.set_path(&["blocks", "abc", "title", "text"], 4),
+                Err(JwstCodecError::UnexpectedType("Map or Array"))
+            );
+
+            // the root types can't be set and no empty root is left behind
+            assert_eq!(
+                doc.set_path(&["title"], "hello"),
+                Err(JwstCodecError::RootSetNotSupport)
+            );
+            assert_eq!(
+                doc.set_path(&[] as &[&str], "hello"),
+                Err(JwstCodecError::RootSetNotSupport)
+            );
+            assert_eq!(doc.get_path(&["title"]), None);
+            assert!(!doc.store.read().unwrap().types.contains_key("title"));
+
+            assert!(doc.delete_path(&path).unwrap());
+            assert_eq!(children.to_array().unwrap().len(), 2);
+            assert!(doc.delete_path(&["blocks", "abc", "title"]).unwrap());
+            assert!(!doc.delete_path(&["blocks", "abc", "title"]).unwrap());
+            assert!(!doc.delete_path(&["blocks", "missing", "title"]).unwrap());
+            assert_eq!(
+                doc.to_json(),
+                serde_json::from_str::<Any>(r#"{ "blocks": { "abc": { "children": [0, 2] } } }"#).unwrap()
+            );
+        });
+    }
+}
@@ -0,0 +1,186 @@
+use super::*;
+
+/// A value to be inserted into a shared type, nested maps, arrays and texts
+/// are created as shared types recursively, so every level of the value is
+/// collaboratively editable instead of being stored as an opaque [Any].
+///
+/// [Any::Object] and [Any::Array] are converted into [Prelim::Map] and
+/// [Prelim::Array], while strings are kept as [Any] unless they're wrapped
+/// in [Prelim::Text].
+#[derive(Debug, Clone, PartialEq)]
+pub enum Prelim {
+    Any(Any),
+    Map(HashMap<String, Prelim>),
+    Array(Vec<Prelim>),
+    Text(String),
+}
+
+impl From<Any> for Prelim {
+    fn from(value: Any) -> Self {
+        match value {
+            Any::Object(entries) => Prelim::Map(
+                entries
+                    .into_iter()
+                    .map(|(key, value)| (key, Prelim::from(value)))
+                    .collect(),
+            ),
+            Any::Array(values) => Prelim::Array(values.into_iter().map(Prelim::from).collect()),
+            value => Prelim::Any(value),
+        }
+    }
+}
+
+impl Prelim {
+    /// Insert the prelim with `insert`, the nested types are filled after
+    /// they're integrated, all in the same transaction.
+    pub(crate) fn integrate(
+        self,
+        store: &WeakStoreRef,
+        insert: impl FnOnce(Value) -> JwstCodecResult,
+    ) -> JwstCodecResult<Value> {
+        let store = store.upgrade().ok_or(JwstCodecError::DocReleased)?;
+        let _transaction = TransactionMut::new(store.clone(), None, true);
+
+        let builder = YTypeBuilder::new(store);
+        let value = match &self {
+            Prelim::Any(any) => Value::Any(any.clone()),
+            Prelim::Map(_) => Value::Map(builder.with_kind(YTypeKind::Map).build()?),
+            Prelim::Array(_) => Value::Array(builder.with_kind(YTypeKind::Array).build()?),
+            Prelim::Text(_) => Value::Text(builder.with_kind(YTypeKind::Text).build()?),
+        };
+        insert(value.clone())?;
+        self.fill(&value)?;
+
+        Ok(value)
+    }
+
+    /// Fill the content of the prelim into the created type
+    pub(crate) fn fill(self, value: &Value) -> JwstCodecResult {
+        match (self, value) {
+            (Prelim::Map(entries), Value::Map(map)) => {
+                let mut map = map.clone();
+                // keep the order of items stable
+                let mut entries = entries.into_iter().collect::<Vec<_>>();
+                entries.sort_by(|(a, _), (b, _)| a.cmp(b));
+                for (key, value) in entries {
+                    map.insert_prelim(key, value)?;
+                }
+            }
+            (Prelim::Array(values), Value::Array(array)) => {
+                let mut array = array.clone();
+                // the consecutive plain values are stored in a single item
+                let mut plain = Vec::new();
+                for value in values {
+                    match value {
+                        Prelim::Any(any) => plain.push(Value::Any(any)),
+                        value => {
+                            if !plain.is_empty() {
+                                array.insert_range(array.len(), std::mem::take(&mut plain))?;
+                            }
+                            array.push_prelim(value)?;
+                        }
+                    }
+                }
+                if !plain.is_empty() {
+                    array.insert_range(array.len(), plain)?;
+                }
+            }
+            (Prelim::Text(string), Value::Text(text)) => {
+                text.clone().insert(0, string)?;
+            }
+            _ => {}
+        }
+
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{loom_model, sync::Mutex};
+
+    fn json_like() -> Any {
+        Any::Object(HashMap::from_iter([
+            ("title".into(), Any::String("page".into())),
+            (
+                "blocks".into(),
+                Any::Array(vec![
+                    Any::Object(HashMap::from_iter([("type".into(), Any::String("paragraph".into()))])),
+                    Any::Integer(1),
+                ]),
+            ),
+        ]))
+    }
+
+    #[test]
+    fn test_insert_prelim() {
+        loom_model!({
+            let doc = DocOptions::new().with_client_id(1).build();
+            let mut map = doc.get_or_create_map("map").unwrap();
+
+            let updates = Arc::new(Mutex::new(0));
+            let updates_ref = updates.clone();
+            let _subscription = doc.observe_update_v1(move |_, _| *updates_ref.lock().unwrap() += 1);
+
+            let page = map.insert_prelim("page".into(), json_like()).unwrap();
+            // all nested types are created in one transaction
+            assert_eq!(*updates.lock().unwrap(), 1);
+            assert_eq!(map.get("page"), Some(page.clone()));
+
+            let page = page.to_map().unwrap();
+            assert_eq!(page.get("title"), Some(Value::from("page")));
+            let mut blocks = page.get("blocks").unwrap().to_array().unwrap();
+            assert_eq!(blocks.get(1), Some(Value::from(1)));
+            let mut block = blocks.get(0).unwrap().to_map().unwrap();
+            block.insert("text".into(), "hello").unwrap();
+
+            let text = blocks.push_prelim(Prelim::Text("world".into())).unwrap();
+            assert_eq!(blocks.len(), 3);
+            assert_eq!(text.to_text().unwrap().to_string(), "world");
+
+            let remote = Doc::try_from_binary_v1(doc.encode_update_v1().unwrap()).unwrap();
+            assert_eq!(remote.to_json(), doc.to_json());
+        });
+    }
+
+    #[test]
+    fn test_doc_from_json() {
+        loom_model!({
+            let doc = Doc::from_json("page", json_like()).unwrap();
+            let page = doc.get_or_create_map("page").unwrap();
+            assert_eq!(page.len(), 2);
+            assert_eq!(Value::Map(page).to_json(), json_like());
+
+            let doc = Doc::from_json("list", Any::Array(vec![Any::Array(vec![Any::True])])).unwrap();
+            let list = doc.get_or_create_array("list").unwrap();
+            let mut nested = list.get(0).unwrap().to_array().unwrap();
+            nested.push(false).unwrap();
+            assert_eq!(
+                Value::Array(list).to_json(),
+                Any::Array(vec![Any::Array(vec![Any::True, Any::False])])
+            );
+
+            // the consecutive plain values are stored in a single item
+            let values = Any::Array(vec![
+                Any::Integer(1),
+                Any::Integer(2),
+                Any::Object(HashMap::from_iter([("key".into(), Any::True)])),
+                Any::Integer(3),
+                Any::Integer(4),
+            ]);
+            let doc = Doc::from_json("list", values.clone()).unwrap();
+            let list = doc.get_or_create_array("list").unwrap();
+            assert_eq!(list.iter_item().count(), 3);
+            assert_eq!(Value::Array(list).to_json(), values);
+
+            let doc = Doc::from_json("text", Any::String("hello".into())).unwrap();
+            assert_eq!(doc.get_or_create_text("text").unwrap().to_string(), "hello");
+
+            assert_eq!(
+                Doc::from_json("number", Any::Integer(1)).err(),
+                Some(JwstCodecError::UnexpectedType("Object, Array or String"))
+            );
+        });
+    }
+}
@@ -1,10 +1,12 @@
 use std::{collections::BTreeMap, fmt::Display};
 
-use super::{AsInner, list::ListType};
+use super::{AsInner, Event, TextEvent, list::ListType};
 use crate::{
-    Any, Content, JwstCodecError, JwstCodecResult,
-    doc::{DocStore, ItemRef, Node, Parent, Somr, YType, YTypeRef},
+    Any, Assoc, Client, Content, Id, Item, JwstCodecError, JwstCodecResult, RelativePosition, Snapshot, Subscription,
+    TransactionMut,
+    doc::{DiffOp, DocStore, HashMap, ItemRef, Node, Parent, Somr, YType, YTypeRef, diff},
     impl_type,
+    sync::Arc,
 };
 
 impl_type!(Text);
@@ -39,10 +41,71 @@ pub enum TextDeltaOp {
 pub type TextDelta = Vec<TextDeltaOp>;
 pub type TextAttributes = BTreeMap<String, Any>;
 
+/// The unit used to measure indexes and lengths of [Text].
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum OffsetKind {
+    /// UTF-16 code units, the same as javascript strings and yjs.
+    #[default]
+    Utf16,
+    /// Unicode scalar values, the same as [str::chars].
+    Unicode,
+    /// UTF-8 bytes, the same as rust string indexes.
+    Bytes,
+}
+
+impl OffsetKind {
+    fn char_len(&self, c: char) -> u64 {
+        (match self {
+            OffsetKind::Utf16 => c.len_utf16(),
+            OffsetKind::Unicode => 1,
+            OffsetKind::Bytes => c.len_utf8(),
+        }) as u64
+    }
+
+    /// The length of string measured in this kind
+    pub fn str_len(&self, s: &str) -> u64 {
+        match self {
+            OffsetKind::Utf16 => s.chars().map(|c| c.len_utf16() as u64).sum(),
+            OffsetKind::Unicode => s.chars().count() as u64,
+            OffsetKind::Bytes => s.len() as u64,
+        }
+    }
+
+    /// The length of countable content measured in this kind
+    pub(crate) fn content_len(&self, content: &Content) -> u64 {
+        match content {
+            Content::String(str) => self.str_len(str),
+            content => content.clock_len(),
+        }
+    }
+
+    /// Convert the offset of string measured in this kind to `to` kind, the
+    /// offset pointing into the middle of a character is moved to the end of
+    /// the character.
+    pub fn convert(&self, s: &str, offset: u64, to: OffsetKind) -> u64 {
+        if *self == to {
+            return offset;
+        }
+
+        let mut from_offset = 0;
+        let mut to_offset = 0;
+        for c in s.chars() {
+            if from_offset >= offset {
+                break;
+            }
+            from_offset += self.char_len(c);
+            to_offset += to.char_len(c);
+        }
+
+        to_offset
+    }
+}
+
 impl Text {
+    /// The length of text measured in [Text::offset_kind]
     #[inline]
     pub fn len(&self) -> u64 {
-        self.content_len()
+        self.as_inner().ty().map(|ty| ty.offset_len).unwrap_or_default()
     }
 
     #[inline]
@@ -50,100 +113,316 @@ impl Text {
         self.len() == 0
     }
 
-    #[inline]
+    /// The unit of indexes and lengths of text, configured by
+    /// [DocOptions::with_offset_kind](crate::DocOptions::with_offset_kind)
+    pub fn offset_kind(&self) -> OffsetKind {
+        self.as_inner()
+            .store()
+            .map(|store| store.offset_kind)
+            .unwrap_or_default()
+    }
+
+    /// Convert the offset of text measured in `from` kind to `to` kind
+    pub fn convert_offset(&self, offset: u64, from: OffsetKind, to: OffsetKind) -> JwstCodecResult<u64> {
+        let ty = self.as_inner().ty().ok_or(JwstCodecError::DocReleased)?;
+
+        convert_text_offset(&ty, offset, from, to)
+    }
+
     pub fn insert<T: ToString>(&mut self, char_index: u64, str: T) -> JwstCodecResult {
-        self.insert_at(char_index, Content::String(str.to_string()))
+        self.as_inner()
+            .transact(|store, ty| {
+                let index = convert_text_offset(ty, char_index, store.offset_kind, OffsetKind::Utf16)?;
+                self.insert_contents_in(store, ty, index, vec![Content::String(str.to_string())])
+            })
+            .unwrap_or(Err(JwstCodecError::DocReleased))
     }
 
-    #[inline]
     pub fn remove(&mut self, char_index: u64, len: u64) -> JwstCodecResult {
-        self.remove_at(char_index, len)
+        self.as_inner()
+            .transact(|store, ty| {
+                let (index, len) = utf16_range(ty, store.offset_kind, char_index, len)?;
+                self.remove_in(store, ty, index, len)
+            })
+            .unwrap_or(Err(JwstCodecError::DocReleased))
+    }
+
+    /// Insert text with given attributes, the attributes inherited from the
+    /// left side will be cleared if they are not listed in `attrs`.
+    pub fn insert_with_attributes<T: ToString>(
+        &mut self,
+        char_index: u64,
+        str: T,
+        attrs: TextAttributes,
+    ) -> JwstCodecResult {
+        self.insert_content(char_index, Content::String(str.to_string()), attrs)
+    }
+
+    pub fn insert_embed(&mut self, char_index: u64, embed: Any, attrs: TextAttributes) -> JwstCodecResult {
+        self.insert_content(char_index, Content::Embed(embed), attrs)
+    }
+
+    /// Apply attributes to the text in range, a `null` attribute value
+    /// removes the attribute.
+    pub fn format(&mut self, char_index: u64, len: u64, attrs: TextAttributes) -> JwstCodecResult {
+        self.as_inner()
+            .transact(|store, ty| {
+                let mut pos = TextPosition::new(self.as_inner().clone(), ty.start.clone());
+                let index = convert_text_offset(ty, char_index, store.offset_kind, OffsetKind::Utf16)?;
+
+                advance_text_position(store, &mut pos, index)?;
+                let len = utf16_len_after(&pos.right, store.offset_kind, len);
+                format_text(store, ty, &mut pos, len, attrs)
+            })
+            .unwrap_or(Err(JwstCodecError::DocReleased))
+    }
+
+    /// Delete the format items that don't change the effective attributes,
+    /// returns the count of deleted items.
+    /// The gaps touched by `format` and `apply_delta` are cleaned
+    /// automatically, but the redundant formats may still be produced by
+    /// concurrent editing.
+    pub fn cleanup_formatting(&mut self) -> JwstCodecResult<u64> {
+        self.as_inner()
+            .transact(cleanup_formatting)
+            .ok_or(JwstCodecError::DocReleased)
+    }
+
+    fn insert_content(&mut self, char_index: u64, content: Content, attrs: TextAttributes) -> JwstCodecResult {
+        self.as_inner()
+            .transact(|store, ty| {
+                let mut pos = TextPosition::new(self.as_inner().clone(), ty.start.clone());
+                let index = convert_text_offset(ty, char_index, store.offset_kind, OffsetKind::Utf16)?;
+
+                advance_text_position(store, &mut pos, index)?;
+                insert_text_content(store, ty, &mut pos, content, attrs)
+            })
+            .unwrap_or(Err(JwstCodecError::DocReleased))
+    }
+
+    /// Create a position sticks to the character at the index measured in
+    /// [Text::offset_kind], see [RelativePosition].
+    pub fn relative_position(&self, char_index: u64, assoc: Assoc) -> JwstCodecResult<RelativePosition> {
+        let index = self.convert_offset(char_index, self.offset_kind(), OffsetKind::Utf16)?;
+
+        self.relative_position_at(index, assoc)
+    }
+
+    /// Observe the changes of text, the observer is removed when the returned
+    /// [Subscription] is dropped.
+    pub fn observe(&self, f: impl Fn(&TextEvent) + Send + Sync + 'static) -> Subscription {
+        self.0.observe(Arc::new(move |event| {
+            if let Event::Text(event) = event {
+                f(event)
+            }
+        }))
+    }
+
+    /// Observe the changes of this text and all nested types, the events of a
+    /// transaction are received at once with their paths from this text.
+    pub fn observe_deep(&self, f: impl Fn(&[Event]) + Send + Sync + 'static) -> Subscription {
+        self.0.observe_deep(Arc::new(move |events: &Vec<Event>| f(events)))
     }
 
     pub fn to_delta(&self) -> TextDelta {
+        self.to_delta_with_snapshots(None, None)
+    }
+
+    /// Get the delta of text. With snapshots given, the text is rendered as
+    /// it was in `snapshot`, and the parts that differ from `prev_snapshot`
+    /// are marked with a `ychange` attribute of
+    /// `{ type: "added" | "removed", user: <client id> }`, just like yjs
+    /// `toDelta(snapshot, prevSnapshot, computeYChange)`.
+    /// The doc must be created with `auto_gc(false)` to render snapshots.
+    pub fn to_delta_with_snapshots(&self, snapshot: Option<&Snapshot>, prev_snapshot: Option<&Snapshot>) -> TextDelta {
         let mut ops = Vec::new();
         let mut attrs = TextAttributes::new();
 
-        for item_ref in self.iter_item() {
-            if let Some(item) = item_ref.get() {
-                match &item.content {
-                    Content::Format { key, value } => {
-                        if is_nullish(value) {
+        let inner = self.as_inner().ty().unwrap();
+        let mut cur = inner.start.clone();
+
+        while let Some(item) = cur.get() {
+            for (id, content) in split_by_snapshots(item, &[snapshot, prev_snapshot]) {
+                let visible = snapshot.map(|s| s.is_visible(&id)).unwrap_or(!item.deleted());
+                let prev_visible = prev_snapshot.map(|s| s.is_visible(&id));
+                if !visible && prev_visible != Some(true) {
+                    continue;
+                }
+
+                match content {
+                    Content::Format { key, value } if visible => {
+                        if is_nullish(&value) {
                             attrs.remove(key.as_str());
                         } else {
-                            attrs.insert(key.to_string(), value.clone());
+                            attrs.insert(key.to_string(), value);
                         }
                     }
                     Content::String(text) => {
-                        push_insert(&mut ops, TextInsert::Text(text.clone()), &attrs);
+                        if snapshot.is_some() && !visible {
+                            attrs.insert(YCHANGE_KEY.into(), ychange("removed", id.client));
+                        } else if prev_visible == Some(false) {
+                            attrs.insert(YCHANGE_KEY.into(), ychange("added", id.client));
+                        } else {
+                            attrs.remove(YCHANGE_KEY);
+                        }
+                        push_insert(&mut ops, TextInsert::Text(text), &attrs);
                     }
                     Content::Embed(embed) => {
-                        push_insert(&mut ops, TextInsert::Embed(vec![embed.clone()]), &attrs);
+                        push_insert(&mut ops, TextInsert::Embed(vec![embed]), &attrs);
                     }
                     Content::Any(any) => {
-                        push_insert(&mut ops, TextInsert::Embed(any.clone()), &attrs);
+                        push_insert(&mut ops, TextInsert::Embed(any), &attrs);
                     }
                     Content::Json(values) => {
                         let converted = values
-                            .iter()
-                            .map(|value| value.as_ref().map(|s| Any::String(s.clone())).unwrap_or(Any::Undefined))
+                            .into_iter()
+                            .map(|value| value.map(Any::String).unwrap_or(Any::Undefined))
                             .collect::<Vec<_>>();
                         push_insert(&mut ops, TextInsert::Embed(converted), &attrs);
                     }
                     Content::Binary(value) => {
-                        push_insert(&mut ops, TextInsert::Embed(vec![Any::Binary(value.clone())]), &attrs);
+                        push_insert(&mut ops, TextInsert::Embed(vec![Any::Binary(value)]), &attrs);
                     }
                     _ => {}
                 }
             }
+
+            cur = item.right.clone();
         }
 
         ops
     }
 
-    pub fn apply_delta(&mut self, delta: &[TextDeltaOp]) -> JwstCodecResult {
-        let (mut store, mut ty) = self.as_inner().write().ok_or(JwstCodecError::DocReleased)?;
-        let parent = self.as_inner().clone();
-
-        let mut pos = TextPosition::new(parent, ty.start.clone());
-
-        for op in delta {
-            match op {
-                TextDeltaOp::Insert { insert, format } => {
-                    let attrs = format.clone().unwrap_or_default();
-                    match insert {
-                        TextInsert::Text(text) => {
-                            insert_text_content(&mut store, &mut ty, &mut pos, Content::String(text.clone()), attrs)?;
-                        }
-                        TextInsert::Embed(values) => {
-                            for value in values {
-                                insert_text_content(
-                                    &mut store,
-                                    &mut ty,
-                                    &mut pos,
-                                    Content::Embed(value.clone()),
-                                    attrs.clone(),
-                                )?;
-                            }
-                        }
-                    }
+    /// Replace the content of text with `new_str` by the minimal inserts and
+    /// deletes computed from a character diff, so the unchanged parts keep
+    /// their formatting, and the concurrent edits and relative positions
+    /// around them are kept as well.
+    ///
+    /// The inserted text is formatted like the character before it, or the
+    /// first character if it's inserted at the start. The embeds are removed
+    /// since they can't be represented in a string.
+    ///
+    /// ```
+    /// use y_octo::Doc;
+    ///
+    /// let doc = Doc::default();
+    /// let mut text = doc.get_or_create_text("text").unwrap();
+    /// text.insert(0, "hello world").unwrap();
+    ///
+    /// text.replace_with_diff("hello, brave world").unwrap();
+    /// assert_eq!(text.to_string(), "hello, brave world");
+    /// ```
+    pub fn replace_with_diff(&mut self, new_str: &str) -> JwstCodecResult {
+        let kind = self.offset_kind();
+        // the characters with their formats, embeds are never equal to
+        // characters
+        let mut old = Vec::new();
+        let mut formats = Vec::new();
+        for op in self.to_delta() {
+            if let TextDeltaOp::Insert { insert, format } = op {
+                let at = formats.len();
+                match insert {
+                    TextInsert::Text(text) => old.extend(text.chars().map(|c| (Some(c), at))),
+                    TextInsert::Embed(values) => old.extend(values.iter().map(|_| (None, at))),
                 }
-                TextDeltaOp::Retain { retain, format } => {
-                    let attrs = format.clone().unwrap_or_default();
-                    if attrs.is_empty() {
-                        advance_text_position(&mut store, &mut pos, *retain)?;
-                    } else {
-                        format_text(&mut store, &mut ty, &mut pos, *retain, attrs)?;
-                    }
+                formats.push(format.unwrap_or_default());
+            }
+        }
+        let (old, old_formats): (Vec<_>, Vec<_>) = old.into_iter().unzip();
+        let new = new_str.chars().map(Some).collect::<Vec<_>>();
+        let len = |chars: &[Option<char>]| chars.iter().map(|c| c.map_or(1, |c| kind.char_len(c))).sum::<u64>();
+
+        // the edits as (index, delete len, insert, format of insert), indexes
+        // are measured in offset kind
+        let mut edits: Vec<(u64, u64, String, usize)> = Vec::new();
+        let (mut old_index, mut index) = (0, 0);
+        for op in diff(&old, &new) {
+            // the deleted length and count of characters, and the inserted text
+            let (deleted, removed, inserted) = match op {
+                DiffOp::Equal(n) => {
+                    index += len(&old[old_index..old_index + n]);
+                    old_index += n;
+                    continue;
                 }
-                TextDeltaOp::Delete { delete } => {
-                    delete_text(&mut store, &mut ty, &mut pos, *delete)?;
+                DiffOp::Delete(n) => (len(&old[old_index..old_index + n]), n, String::new()),
+                DiffOp::Insert(range) => (0, 0, new[range].iter().flatten().collect()),
+            };
+
+            match edits.last_mut() {
+                Some((last, delete, insert, _)) if *last + *delete == index => {
+                    *delete += deleted;
+                    insert.push_str(&inserted);
                 }
+                // the inserted text is formatted like the character before it
+                _ => edits.push((index, deleted, inserted, old_index.saturating_sub(1))),
+            }
+            index += deleted;
+            old_index += removed;
+        }
+
+        let store = self.as_inner().store.upgrade().ok_or(JwstCodecError::DocReleased)?;
+        let _transaction = TransactionMut::new(store, None, true);
+        // apply from the end, so the indexes of the former edits are kept
+        for (index, delete, insert, at) in edits.into_iter().rev() {
+            if delete > 0 {
+                self.remove(index, delete)?;
+            }
+            if !insert.is_empty() {
+                let attrs = old_formats.get(at).map(|&format| formats[format].clone());
+                self.insert_with_attributes(index, insert, attrs.unwrap_or_default())?;
             }
         }
 
         Ok(())
     }
+
+    pub fn apply_delta(&mut self, delta: &[TextDeltaOp]) -> JwstCodecResult {
+        self.as_inner()
+            .transact(|store, ty| {
+                let mut pos = TextPosition::new(self.as_inner().clone(), ty.start.clone());
+                let kind = store.offset_kind;
+
+                for op in delta {
+                    match op {
+                        TextDeltaOp::Insert { insert, format } => {
+                            let attrs = format.clone().unwrap_or_default();
+                            match insert {
+                                TextInsert::Text(text) => {
+                                    insert_text_content(store, ty, &mut pos, Content::String(text.clone()), attrs)?;
+                                }
+                                TextInsert::Embed(values) => {
+                                    for value in values {
+                                        insert_text_content(
+                                            store,
+                                            ty,
+                                            &mut pos,
+                                            Content::Embed(value.clone()),
+                                            attrs.clone(),
+                                        )?;
+                                    }
+                                }
+                            }
+                        }
+                        TextDeltaOp::Retain { retain, format } => {
+                            let attrs = format.clone().unwrap_or_default();
+                            let len = utf16_len_after(&pos.right, kind, *retain);
+                            if attrs.is_empty() {
+                                advance_text_position(store, &mut pos, len)?;
+                            } else {
+                                format_text(store, ty, &mut pos, len, attrs)?;
+                            }
+                        }
+                        TextDeltaOp::Delete { delete } => {
+                            let len = utf16_len_after(&pos.right, kind, *delete);
+                            delete_text(store, ty, &mut pos, len)?;
+                        }
+                    }
+                }
+
+                Ok(())
+            })
+            .unwrap_or(Err(JwstCodecError::DocReleased))
+    }
 }
 
 impl Display for Text {
@@ -206,6 +485,131 @@ impl TextPosition {
     }
 }
 
+const YCHANGE_KEY: &str = "ychange";
+
+fn ychange(ty: &str, client: Client) -> Any {
+    Any::Object(HashMap::from_iter([
+        ("type".to_string(), Any::from(ty)),
+        ("user".to_string(), Any::from(client)),
+    ]))
+}
+
+/// Split the content of item at the boundaries of snapshots, so that every
+/// part has the same visibility in each snapshot.
+fn split_by_snapshots(item: &Item, snapshots: &[Option<&Snapshot>]) -> Vec<(Id, Content)> {
+    let Id { client, clock } = item.id;
+    let end = clock + item.len();
+
+    let mut boundaries = Vec::new();
+    if item.content.splittable() {
+        for snapshot in snapshots.iter().flatten() {
+            boundaries.push(snapshot.state_vector.get(&client));
+            if let Some(deletes) = snapshot.delete_set.get(&client) {
+                for range in deletes {
+                    boundaries.push(range.start);
+                    boundaries.push(range.end);
+                }
+            }
+        }
+    }
+    boundaries.retain(|boundary| *boundary > clock && *boundary < end);
+    boundaries.sort_unstable();
+    boundaries.dedup();
+
+    let mut parts = Vec::with_capacity(boundaries.len() + 1);
+    let mut content = item.content.clone();
+    let mut start = clock;
+    for boundary in boundaries {
+        if let Ok((left, right)) = content.split(boundary - start) {
+            parts.push((Id::new(client, start), left));
+            content = right;
+            start = boundary;
+        }
+    }
+    parts.push((Id::new(client, start), content));
+
+    parts
+}
+
+/// Convert the offset of text measured in `from` kind to `to` kind, only the
+/// content of strings is measured differently in kinds.
+fn convert_text_offset(ty: &YType, offset: u64, from: OffsetKind, to: OffsetKind) -> JwstCodecResult<u64> {
+    if from == to {
+        return Ok(offset);
+    }
+
+    let mut remaining = offset;
+    let mut converted = 0;
+    let mut cur = ty.start.clone();
+
+    while remaining > 0 {
+        let Some(item) = cur.get() else {
+            return Err(JwstCodecError::IndexOutOfBound(offset));
+        };
+
+        if !item.deleted() && item.countable() {
+            if let Content::String(str) = &item.content {
+                let len = from.str_len(str);
+                if remaining < len {
+                    converted += from.convert(str, remaining, to);
+                    remaining = 0;
+                } else {
+                    converted += to.str_len(str);
+                    remaining -= len;
+                }
+            } else {
+                let len = remaining.min(item.len());
+                converted += len;
+                remaining -= len;
+            }
+        }
+
+        cur = item.right.clone();
+    }
+
+    Ok(converted)
+}
+
+/// Convert the range of text measured in `kind` to utf-16 range of
+/// `(index, len)`, the end of range is clamped to the end of text.
+fn utf16_range(ty: &YType, kind: OffsetKind, index: u64, len: u64) -> JwstCodecResult<(u64, u64)> {
+    let start = convert_text_offset(ty, index, kind, OffsetKind::Utf16)?;
+    let end = convert_text_offset(ty, index + len, kind, OffsetKind::Utf16).unwrap_or(ty.len);
+
+    Ok((start, end.saturating_sub(start)))
+}
+
+/// Convert the length of text starting from `start` measured in `kind` to
+/// utf-16 length, the length is clamped to the end of text.
+fn utf16_len_after(start: &ItemRef, kind: OffsetKind, len: u64) -> u64 {
+    let mut remaining = len;
+    let mut converted = 0;
+    let mut cur = start.clone();
+
+    while remaining > 0
+        && let Some(item) = cur.get()
+    {
+        if !item.deleted() && item.countable() {
+            let item_len = kind.content_len(&item.content);
+            if remaining < item_len {
+                if let Content::String(str) = &item.content {
+                    converted += kind.convert(str, remaining, OffsetKind::Utf16);
+                } else {
+                    converted += remaining;
+                }
+                remaining = 0;
+            } else {
+                converted += item.len();
+                remaining -= item_len;
+            }
+        }
+
+        cur = item.right.clone();
+    }
+
+    converted
+}
+
 fn is_nullish(value: &Any) -> bool {
     matches!(value, Any::Null | Any::Undefined)
 }
@@ -406,6 +810,8 @@ fn format_text(
         return Ok(());
     }
 
+    let start = (pos.left.clone(), pos.attrs.clone());
+
     minimize_attribute_changes(pos, &attrs);
     let mut negated = insert_attributes(store, ty, pos, &attrs)?;
 
@@ -446,17 +852,123 @@ fn format_text(
         }
     }
 
+    let end = (pos.left.clone(), pos.attrs.clone());
     insert_negated_attributes(store, ty, pos, negated)?;
 
+    // only the gaps around the start and the end of range may contain
+    // redundant formats, the ones inside the range are deleted above
+    for (left, attrs) in [start, end] {
+        cleanup_formatting_gap_after(store, ty, &left, attrs);
+    }
+
     Ok(())
 }
 
+/// Mirrors `cleanupFormattingGap` of yjs, only cleans the gap of deleted and
+/// format items right after `left`, `attrs` are the attributes in effect at
+/// `left`.
+fn cleanup_formatting_gap_after(
+    store: &mut DocStore,
+    ty: &mut YType,
+    left: &ItemRef,
+    mut attrs: TextAttributes,
+) -> u64 {
+    let mut gap = Vec::new();
+    let mut cur = match left.get() {
+        Some(left) => left.right.clone(),
+        None => ty.start.clone(),
+    };
+
+    while let Some(item) = cur.get()
+        && (item.deleted() || !item.countable())
+    {
+        if !item.deleted() && matches!(item.content, Content::Format { .. }) {
+            gap.push(cur.clone());
+        }
+        cur = item.right.clone();
+    }
+
+    cleanup_formatting_gap(store, ty, &mut attrs, &gap)
+}
+
+/// Mirrors `cleanupYTextFormatting` of yjs, the format items between two
+/// countable items are redundant if they are overwritten by a later format
+/// item of the same key in the gap, or they don't change the attribute.
+fn cleanup_formatting(store: &mut DocStore, ty: &mut YType) -> u64 {
+    let mut cleanups = 0;
+    let mut attrs = TextAttributes::new();
+    let mut gap = Vec::new();
+    let mut cur = ty.start.clone();
+
+    loop {
+        let item = cur.get();
+        if let Some(item) = item
+            && (item.deleted() || !item.countable())
+        {
+            if !item.deleted() && matches!(item.content, Content::Format { .. }) {
+                gap.push(cur.clone());
+            }
+            cur = item.right.clone();
+            continue;
+        }
+
+        cleanups += cleanup_formatting_gap(store, ty, &mut attrs, &gap);
+        gap.clear();
+
+        match item {
+            Some(item) => cur = item.right.clone(),
+            None => break,
+        }
+    }
+
+    cleanups
+}
+
+fn cleanup_formatting_gap(store: &mut DocStore, ty: &mut YType, attrs: &mut TextAttributes, gap: &[ItemRef]) -> u64 {
+    let mut cleanups = 0;
+
+    for (i, item_ref) in gap.iter().enumerate() {
+        let Some(item) = item_ref.get() else {
+            continue;
+        };
+        let Content::Format { key, value } = &item.content else {
+            continue;
+        };
+
+        let overwritten = gap[i + 1..].iter().any(|next| {
+            next.get()
+                .is_some_and(|next| matches!(&next.content, Content::Format { key: next_key, .. } if next_key == key))
+        });
+        let current = attrs.get(key.as_str()).cloned().unwrap_or(Any::Null);
+
+        if overwritten || current == *value || (is_nullish(&current) && is_nullish(value)) {
+            store.delete_item(item, Some(ty));
+            cleanups += 1;
+        }
+    }
+
+    for item in gap.iter().filter_map(|item| item.get()) {
+        if let Content::Format { key, value } = &item.content
+            && !item.deleted()
+        {
+            if is_nullish(value) {
+                attrs.remove(key.as_str());
+            } else {
+                attrs.insert(key.to_string(), value.clone());
+            }
+        }
+    }
+
+    cleanups
+}
+
 fn delete_text(store: &mut DocStore, ty: &mut YType, pos: &mut TextPosition, mut remaining: u64) -> JwstCodecResult {
     if remaining == 0 {
         return Ok(());
     }
 
     let start = remaining;
+    let (left, attrs) = (pos.left.clone(), pos.attrs.clone());
 
     while remaining > 0 {
         let item_ref = pos.right.clone();
@@ -485,6 +997,9 @@ fn delete_text(store: &mut DocStore, ty: &mut YType, pos: &mut TextPosition, mut
         markers.update_marker_changes(pos.index, -((start - remaining) as i64));
     }
 
+    // the deleted text joins the gap of formats around it
+    cleanup_formatting_gap_after(store, ty, &left, attrs);
+
     Ok(())
 }
 
@@ -492,12 +1007,12 @@ fn delete_text(store: &mut DocStore, ty: &mut YType, pos: &mut TextPosition, mut
 mod tests {
     use rand::{Rng, SeedableRng};
     use rand_chacha::ChaCha20Rng;
-    use yrs::{Options, Text, Transact};
+    use yrs::{GetString, Options, Text, Transact, updates::decoder::Decode};
 
-    use super::{TextAttributes, TextDeltaOp, TextInsert};
+    use super::{OffsetKind, TextAttributes, TextDeltaOp, TextInsert};
     #[cfg(not(loom))]
     use crate::sync::{Arc, AtomicUsize, Ordering};
-    use crate::{Any, Doc, loom_model, sync::thread};
+    use crate::{Any, Assoc, Doc, DocOptions, Snapshot, StateVector, doc::DeleteSet, loom_model, sync::thread};
 
     #[test]
     fn test_manipulate_text() {
@@ -762,6 +1277,154 @@ mod tests {
         });
     }
 
+    #[test]
+    fn test_text_offset_kind() {
+        loom_model!({
+            for (kind, index, len) in [
+                (OffsetKind::Utf16, 2, 8),
+                (OffsetKind::Unicode, 2, 6),
+                (OffsetKind::Bytes, 6, 20),
+            ] {
+                let doc = DocOptions::new().with_offset_kind(kind).build();
+                let mut text = doc.get_or_create_text("text").unwrap();
+
+                text.insert(0, "你好世界").unwrap();
+                text.insert(index, "👋🏻").unwrap();
+                assert_eq!(text.to_string(), "你好👋🏻世界");
+                assert_eq!(text.len(), len);
+                assert_eq!(text.offset_kind(), kind);
+
+                let emoji_len = kind.str_len("👋🏻");
+                text.format(index, emoji_len, TextAttributes::from([("bold".into(), Any::True)]))
+                    .unwrap();
+                assert_eq!(
+                    text.to_delta()[1],
+                    TextDeltaOp::Insert {
+                        insert: TextInsert::Text("👋🏻".into()),
+                        format: Some(TextAttributes::from([("bold".into(), Any::True)])),
+                    }
+                );
+
+                text.remove(index, emoji_len).unwrap();
+                assert_eq!(text.to_string(), "你好世界");
+                assert_eq!(text.len(), kind.str_len("你好世界"));
+
+                text.apply_delta(&[
+                    TextDeltaOp::Retain {
+                        retain: kind.str_len("你"),
+                        format: None,
+                    },
+                    TextDeltaOp::Delete {
+                        delete: kind.str_len("好"),
+                    },
+                    TextDeltaOp::Retain {
+                        retain: kind.str_len("世"),
+                        format: None,
+                    },
+                    TextDeltaOp::Insert {
+                        insert: TextInsert::Text("😀".into()),
+                        format: None,
+                    },
+                ])
+                .unwrap();
+                assert_eq!(text.to_string(), "你世😀界");
+            }
+        });
+    }
+
+    #[test]
+    fn test_text_convert_offset() {
+        loom_model!({
+            let doc = Doc::new();
+            let mut text = doc.get_or_create_text("text").unwrap();
+            text.insert(0, "a你👋🏻b").unwrap();
+
+            assert_eq!(text.convert_offset(3, OffsetKind::Unicode, OffsetKind::Utf16), Ok(4));
+            assert_eq!(text.convert_offset(4, OffsetKind::Utf16, OffsetKind::Bytes), Ok(8));
+            assert_eq!(text.convert_offset(13, OffsetKind::Bytes, OffsetKind::Unicode), Ok(5));
+            assert!(text.convert_offset(14, OffsetKind::Bytes, OffsetKind::Unicode).is_err());
+
+            // offset in the middle of a character is moved to the end of it
+            assert_eq!(OffsetKind::Bytes.convert("你好", 1, OffsetKind::Utf16), 1);
+            assert_eq!(OffsetKind::Utf16.convert("👋🏻", 1, OffsetKind::Unicode), 1);
+            assert_eq!(OffsetKind::Unicode.str_len("👋🏻"), 2);
+        });
+    }
+
+    #[test]
+    #[cfg(not(loom))]
+    fn test_text_offset_kind_converge() {
+        let mut docs = [OffsetKind::Utf16, OffsetKind::Unicode, OffsetKind::Bytes]
+            .into_iter()
+            .enumerate()
+            .map(|(i, kind)| {
+                DocOptions::new()
+                    .with_client_id(i as u64 + 1)
+                    .with_offset_kind(kind)
+                    .build()
+            })
+            .collect::<Vec<_>>();
+        let mut text = docs[0].get_or_create_text("text").unwrap();
+        text.insert(0, "你好世界").unwrap();
+        let update = docs[0].encode_update_v1().unwrap();
+        for doc in docs.iter_mut().skip(1) {
+            doc.apply_update_from_binary_v1(&update).unwrap();
+        }
+
+        // insert emoji after "你好" concurrently
+        for (i, (doc, index)) in docs.iter_mut().zip([2, 2, 6]).enumerate() {
+            doc.get_or_create_text("text")
+                .unwrap()
+                .insert(index, ["👋🏻", "😀", "🎉"][i])
+                .unwrap();
+        }
+
+        let updates = docs
+            .iter()
+            .map(|doc| doc.encode_update_v1().unwrap())
+            .collect::<Vec<_>>();
+        for doc in docs.iter_mut() {
+            for update in &updates {
+                doc.apply_update_from_binary_v1(update).unwrap();
+            }
+        }
+
+        let result = docs[0].get_or_create_text("text").unwrap().to_string();
+        assert_eq!(result.chars().count(), 8);
+        for doc in &docs {
+            assert_eq!(doc.get_or_create_text("text").unwrap().to_string(), result);
+        }
+    }
+
+    #[test]
+    #[cfg_attr(any(miri, loom), ignore)]
+    fn test_text_offset_kind_compatible_with_yrs() {
+        let yrs_doc = yrs::Doc::new();
+        let yrs_text = yrs_doc.get_or_insert_text("text");
+        let binary = {
+            let mut trx = yrs_doc.transact_mut();
+            yrs_text.insert(&mut trx, 0, "你好世界");
+            yrs_text.insert(&mut trx, 2, "👋🏻");
+            trx.encode_update_v1()
+        };
+
+        let mut doc = DocOptions::new()
+            .with_client_id(rand::random())
+            .with_offset_kind(OffsetKind::Unicode)
+            .build();
+        doc.apply_update_from_binary_v1(&binary).unwrap();
+        let mut text = doc.get_or_create_text("text").unwrap();
+        assert_eq!(text.len(), 6);
+        text.insert(4, "!").unwrap();
+        text.remove(5, 1).unwrap();
+
+        let mut trx = yrs_doc.transact_mut();
+        trx.apply_update(yrs::Update::decode_v1(&doc.encode_update_v1().unwrap()).unwrap())
+            .unwrap();
+        assert_eq!(yrs_text.get_string(&trx), "你好👋🏻!界");
+        assert_eq!(text.to_string(), "你好👋🏻!界");
+    }
+
     #[test]
     fn test_text_delta_insert_format() {
         loom_model!({
@@ -825,6 +1488,122 @@ mod tests {
         });
     }
 
+    #[test]
+    fn test_text_format() {
+        loom_model!({
+            let doc = Doc::new();
+            let mut text = doc.get_or_create_text("text").unwrap();
+            let bold = TextAttributes::from([("bold".to_string(), Any::True)]);
+
+            text.insert(0, "hello world").unwrap();
+            text.format(0, 5, bold.clone()).unwrap();
+            // formatting the same range again should not produce new format items
+            let update = doc.encode_update_v1().unwrap();
+            text.format(0, 5, bold.clone()).unwrap();
+            assert_eq!(doc.encode_update_v1().unwrap(), update);
+
+            text.format(3, 4, TextAttributes::from([("bold".to_string(), Any::Null)]))
+                .unwrap();
+
+            assert_eq!(
+                text.to_delta(),
+                vec![
+                    TextDeltaOp::Insert {
+                        insert: TextInsert::Text("hel".to_string()),
+                        format: Some(bold),
+                    },
+                    TextDeltaOp::Insert {
+                        insert: TextInsert::Text("lo world".to_string()),
+                        format: None,
+                    }
+                ]
+            );
+            assert_eq!(
+                text.format(12, 1, TextAttributes::new()),
+                Err(crate::JwstCodecError::IndexOutOfBound(12))
+            );
+        });
+    }
+
+    #[test]
+    fn test_text_insert_with_attributes() {
+        loom_model!({
+            let doc = Doc::new();
+            let mut text = doc.get_or_create_text("text").unwrap();
+            let bold = TextAttributes::from([("bold".to_string(), Any::True)]);
+            let link = TextAttributes::from([("link".to_string(), Any::from("https://yjs.dev"))]);
+
+            text.insert_with_attributes(0, "hello", bold.clone()).unwrap();
+            text.insert_with_attributes(5, " world", link.clone()).unwrap();
+            text.insert_embed(5, Any::from("image"), TextAttributes::new()).unwrap();
+
+            assert_eq!(text.len(), 12);
+            assert_eq!(
+                text.to_delta(),
+                vec![
+                    TextDeltaOp::Insert {
+                        insert: TextInsert::Text("hello".to_string()),
+                        format: Some(bold),
+                    },
+                    TextDeltaOp::Insert {
+                        insert: TextInsert::Embed(vec![Any::from("image")]),
+                        format: None,
+                    },
+                    TextDeltaOp::Insert {
+                        insert: TextInsert::Text(" world".to_string()),
+                        format: Some(link),
+                    }
+                ]
+            );
+        });
+    }
+
+    #[test]
+    fn test_text_cleanup_formatting() {
+        loom_model!({
+            let mut doc1 = Doc::with_client(1);
+            let mut doc2 = Doc::with_client(2);
+            let mut text1 = doc1.get_or_create_text("text").unwrap();
+            let mut text2 = doc2.get_or_create_text("text").unwrap();
+            let bold = TextAttributes::from([("bold".to_string(), Any::True)]);
+
+            text1.insert(0, "hello world").unwrap();
+            doc2.apply_update_from_binary_v1(doc1.encode_update_v1().unwrap())
+                .unwrap();
+
+            // concurrent formatting produces duplicated format items
+            text1.format(0, 5, bold.clone()).unwrap();
+            text2.format(0, 5, bold.clone()).unwrap();
+            doc1.apply_update_from_binary_v1(doc2.encode_update_v1().unwrap())
+                .unwrap();
+            doc2.apply_update_from_binary_v1(doc1.encode_update_v1().unwrap())
+                .unwrap();
+
+            let delta = text1.to_delta();
+            assert_eq!(text1.cleanup_formatting().unwrap(), 2);
+            assert_eq!(text1.cleanup_formatting().unwrap(), 0);
+            assert_eq!(text1.to_delta(), delta);
+
+            doc2.apply_update_from_binary_v1(doc1.encode_update_v1().unwrap())
+                .unwrap();
+            assert_eq!(text2.to_delta(), delta);
+            assert_eq!(text2.cleanup_formatting().unwrap(), 0);
+
+            // the gaps touched by local edits are cleaned automatically
+            text2.format(5, 3, bold.clone()).unwrap();
+            assert_eq!(text2.cleanup_formatting().unwrap(), 0);
+            text2.apply_delta(&[TextDeltaOp::Delete { delete: 8 }]).unwrap();
+            assert_eq!(text2.cleanup_formatting().unwrap(), 0);
+            assert_eq!(
+                text2.to_delta(),
+                vec![TextDeltaOp::Insert {
+                    insert: TextInsert::Text("rld".to_string()),
+                    format: None,
+                }]
+            );
+        });
+    }
+
     #[test]
     fn test_text_delta_utf16_retain() {
         loom_model!({
@@ -855,4 +1634,118 @@ mod tests {
             );
         });
     }
+
+    #[test]
+    fn test_text_delta_with_snapshots() {
+        loom_model!({
+            let doc = DocOptions::new().with_client_id(1).auto_gc(false).build();
+            let mut text = doc.get_or_create_text("text").unwrap();
+
+            text.insert(0, "hello world").unwrap();
+            let snapshot1 = doc.snapshot();
+
+            text.remove(0, 6).unwrap();
+            text.insert(5, "!").unwrap();
+            let snapshot2 = doc.snapshot();
+
+            let ychange = |ty: &str| {
+                let mut attrs = TextAttributes::new();
+                attrs.insert("ychange".to_string(), super::ychange(ty, 1));
+                Some(attrs)
+            };
+
+            assert_eq!(
+                text.to_delta_with_snapshots(Some(&snapshot2), Some(&snapshot1)),
+                vec![
+                    TextDeltaOp::Insert {
+                        insert: TextInsert::Text("hello ".to_string()),
+                        format: ychange("removed"),
+                    },
+                    TextDeltaOp::Insert {
+                        insert: TextInsert::Text("world".to_string()),
+                        format: None,
+                    },
+                    TextDeltaOp::Insert {
+                        insert: TextInsert::Text("!".to_string()),
+                        format: ychange("added"),
+                    },
+                ]
+            );
+
+            // render the old version only
+            assert_eq!(
+                text.to_delta_with_snapshots(Some(&snapshot1), None),
+                vec![TextDeltaOp::Insert {
+                    insert: TextInsert::Text("hello world".to_string()),
+                    format: None,
+                }]
+            );
+
+            // split in the middle of an item
+            let snapshot = Snapshot::new(StateVector::from([(1, 5)]), DeleteSet::default());
+            assert_eq!(
+                text.to_delta_with_snapshots(Some(&snapshot1), Some(&snapshot)),
+                vec![
+                    TextDeltaOp::Insert {
+                        insert: TextInsert::Text("hello".to_string()),
+                        format: None,
+                    },
+                    TextDeltaOp::Insert {
+                        insert: TextInsert::Text(" world".to_string()),
+                        format: ychange("added"),
+                    },
+                ]
+            );
+        });
+    }
+
+    #[test]
+    fn test_text_replace_with_diff() {
+        loom_model!({
+            let doc = DocOptions::new().with_client_id(1).build();
+            let mut text = doc.get_or_create_text("text").unwrap();
+            let bold = TextAttributes::from([("bold".to_string(), Any::True)]);
+            text.insert(0, "hello 🦀 world").unwrap();
+            text.format(0, 5, bold.clone()).unwrap();
+            let cursor = text.relative_position(11, Assoc::After).unwrap();
+
+            let mut remote = DocOptions::new().with_client_id(2).build();
+            remote
+                .apply_update_from_binary_v1(doc.encode_update_v1().unwrap())
+                .unwrap();
+            let mut remote_text = remote.get_or_create_text("text").unwrap();
+            remote_text.insert(remote_text.len(), "!").unwrap();
+
+            text.replace_with_diff("help 🦀 wide world").unwrap();
+            assert_eq!(text.to_string(), "help 🦀 wide world");
+            assert_eq!(
+                text.to_delta(),
+                vec![
+                    TextDeltaOp::Insert {
+                        insert: TextInsert::Text("help".to_string()),
+                        format: Some(bold),
+                    },
+                    TextDeltaOp::Insert {
+                        insert: TextInsert::Text(" 🦀 wide world".to_string()),
+                        format: None,
+                    }
+                ]
+            );
+            // the unchanged characters are kept
+            assert_eq!(doc.absolute_position(&cursor).unwrap().index, 15);
+
+            // concurrent edits are merged instead of being overwritten
+            remote
+                .apply_update_from_binary_v1(doc.encode_update_v1().unwrap())
+                .unwrap();
+            doc.clone()
+                .apply_update_from_binary_v1(remote.encode_update_v1().unwrap())
+                .unwrap();
+            assert_eq!(text.to_string(), "help 🦀 wide world!");
+            assert_eq!(remote_text.to_string(), text.to_string());
+
+            text.replace_with_diff("").unwrap();
+            assert!(text.is_empty());
+        });
+    }
 }
@@ -1,6 +1,7 @@
 use std::fmt::Display;
 
 use super::*;
+use crate::sync::OnceLock;
 
 #[derive(Debug, Clone, PartialEq)]
 pub enum Value {
@@ -44,6 +45,43 @@ impl Value {
         }
     }
 
+    pub(crate) fn as_type_ref(&self) -> Option<&YTypeRef> {
+        match self {
+            Value::Array(v) => Some(&v.0),
+            Value::Map(v) => Some(&v.0),
+            Value::Text(v) => Some(&v.0),
+            Value::XMLElement(v) => Some(&v.0),
+            Value::XMLFragment(v) => Some(&v.0),
+            Value::XMLHook(v) => Some(&v.0),
+            Value::XMLText(v) => Some(&v.0),
+            Value::Any(_) | Value::Doc(_) => None,
+        }
+    }
+
+    /// Convert the value into [Any] recursively, it works like `toJSON` of
+    /// yjs: texts and xml types are converted into strings, and subdocs are
+    /// referenced by their guids.
+    pub fn to_json(&self) -> Any {
+        match self {
+            Value::Any(any) => any.clone(),
+            Value::Doc(doc) => Any::String(doc.guid().to_owned()),
+            Value::Array(array) => Any::Array(array.iter().map(|value| value.to_json()).collect()),
+            Value::Map(map) => Any::Object(
+                map.iter()
+                    .map(|(key, value)| (key.to_owned(), value.to_json()))
+                    .collect(),
+            ),
+            Value::XMLHook(hook) => Any::Object(
+                hook._entries()
+                    .map(|(key, value)| (key.to_owned(), value.to_json()))
+                    .collect(),
+            ),
+            Value::Text(_) | Value::XMLElement(_) | Value::XMLFragment(_) | Value::XMLText(_) => {
+                Any::String(self.to_string())
+            }
+        }
+    }
+
     pub fn from_vec<T: Into<Any>>(el: Vec<T>) -> Self {
         Value::Any(Any::Array(el.into_iter().map(|item| item.into()).collect::<Vec<_>>()))
     }
@@ -71,25 +109,28 @@ impl From<&Content> for Value {
             )),
             Content::Binary(buf) => Value::Any(Any::Binary(buf.clone())),
             Content::Embed(v) => Value::Any(v.clone()),
-            Content::Type(ty) => match ty.ty().unwrap().kind {
-                YTypeKind::Array => Value::Array(Array::from_unchecked(ty.clone())),
-                YTypeKind::Map => Value::Map(Map::from_unchecked(ty.clone())),
-                YTypeKind::Text => Value::Text(Text::from_unchecked(ty.clone())),
-                YTypeKind::XMLElement => Value::XMLElement(XMLElement::from_unchecked(ty.clone())),
-                YTypeKind::XMLFragment => Value::XMLFragment(XMLFragment::from_unchecked(ty.clone())),
-                YTypeKind::XMLHook => Value::XMLHook(XMLHook::from_unchecked(ty.clone())),
-                YTypeKind::XMLText => Value::XMLText(XMLText::from_unchecked(ty.clone())),
-                // actually unreachable
-                YTypeKind::Unknown => Value::Any(Any::Undefined),
-            },
-            Content::Doc { guid: _, opts } => Value::Doc(
-                DocOptions::try_from(opts.clone())
-                    .expect("Failed to parse doc options")
-                    .build(),
-            ),
+            Content::Type(ty) => Value::from(ty.clone()),
+            Content::Doc { .. } => Value::Doc(value.subdoc().unwrap()),
             Content::Format { .. } => unimplemented!(),
             // actually unreachable
-            Content::Deleted(_) => Value::Any(Any::Undefined),
+            Content::Deleted(_) | Content::Move(_) => Value::Any(Any::Undefined),
+        }
+    }
+}
+
+impl From<YTypeRef> for Value {
+    fn from(ty: YTypeRef) -> Value {
+        let kind = ty.ty().unwrap().kind;
+        match kind {
+            YTypeKind::Array => Value::Array(Array::from_unchecked(ty)),
+            YTypeKind::Map => Value::Map(Map::from_unchecked(ty)),
+            YTypeKind::Text => Value::Text(Text::from_unchecked(ty)),
+            YTypeKind::XMLElement => Value::XMLElement(XMLElement::from_unchecked(ty)),
+            YTypeKind::XMLFragment => Value::XMLFragment(XMLFragment::from_unchecked(ty)),
+            YTypeKind::XMLHook => Value::XMLHook(XMLHook::from_unchecked(ty)),
+            YTypeKind::XMLText => Value::XMLText(XMLText::from_unchecked(ty)),
+            // actually unreachable
+            YTypeKind::Unknown => Value::Any(Any::Undefined),
         }
     }
 }
@@ -101,6 +142,7 @@ impl From<Value> for Content {
             Value::Doc(doc) => Content::Doc {
                 guid: doc.guid().to_owned(),
                 opts: Any::from(doc.options().clone()),
+                doc: Arc::new(OnceLock::from(doc)),
             },
             Value::Array(v) => Content::Type(v.0),
             Value::Map(v) => Content::Type(v.0),
@@ -130,6 +172,9 @@ impl Display for Value {
         match self {
             Value::Any(any) => write!(f, "{any}"),
             Value::Text(text) => write!(f, "{text}"),
+            Value::XMLElement(element) => write!(f, "{element}"),
+            Value::XMLFragment(fragment) => write!(f, "{fragment}"),
+            Value::XMLText(text) => write!(f, "{text}"),
             _ => write!(f, ""),
         }
     }
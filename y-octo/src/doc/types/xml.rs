@@ -1,8 +1,10 @@
-use super::list::ListType;
-use crate::impl_type;
+use std::fmt::Display;
+
+use super::*;
 
 impl_type!(XMLElement);
 impl ListType for XMLElement {}
+impl MapType for XMLElement {}
 
 impl_type!(XMLFragment);
 impl ListType for XMLFragment {}
@@ -12,3 +14,486 @@ impl ListType for XMLText {}
 
 impl_type!(XMLHook);
 impl ListType for XMLHook {}
+impl MapType for XMLHook {}
+
+pub struct XMLChildren<'a>(ListIterator<'a>);
+
+impl Iterator for XMLChildren<'_> {
+    type Item = Value;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        for item in self.0.by_ref() {
+            if let Some(item) = item.get()
+                && item.countable()
+            {
+                return Some(Value::from(&item.content));
+            }
+        }
+
+        None
+    }
+}
+
+/// Only xml types can be the children of XMLFragment and XMLElement.
+fn check_xml_child(child: &Value) -> JwstCodecResult {
+    match child {
+        Value::XMLElement(_) | Value::XMLText(_) | Value::XMLHook(_) => Ok(()),
+        _ => Err(JwstCodecError::UnexpectedType("XMLElement, XMLText or XMLHook")),
+    }
+}
+
+/// Stringify a value the way javascript `String(value)` does, used by the
+/// xml serialization of attributes.
+fn js_string(value: &Any) -> String {
+    match value {
+        Any::String(s) => s.clone(),
+        Any::Null => "null".into(),
+        Any::Undefined => "undefined".into(),
+        Any::Object(_) => "[object Object]".into(),
+        Any::Array(values) => values.iter().map(js_string).collect::<Vec<_>>().join(","),
+        _ => value.to_string(),
+    }
+}
+
+macro_rules! impl_xml_children {
+    ($name: ident) => {
+        impl $name {
+            #[inline]
+            pub fn len(&self) -> u64 {
+                self.content_len()
+            }
+
+            #[inline]
+            pub fn is_empty(&self) -> bool {
+                self.len() == 0
+            }
+
+            pub fn get(&self, index: u64) -> Option<Value> {
+                let (item, _) = self.get_item_at(index)?;
+
+                item.get().map(|item| Value::from(&item.content))
+            }
+
+            pub fn children(&self) -> XMLChildren<'_> {
+                XMLChildren(self.iter_item())
+            }
+
+            pub fn insert<V: Into<Value>>(&mut self, index: u64, child: V) -> JwstCodecResult {
+                let child = child.into();
+                check_xml_child(&child)?;
+
+                self.insert_at(index, child.into())
+            }
+
+            pub fn push<V: Into<Value>>(&mut self, child: V) -> JwstCodecResult {
+                self.insert(self.len(), child)
+            }
+
+            pub fn remove(&mut self, index: u64, len: u64) -> JwstCodecResult {
+                self.remove_at(index, len)
+            }
+        }
+    };
+}
+
+impl_xml_children!(XMLFragment);
+impl_xml_children!(XMLElement);
+
+impl XMLElement {
+    #[inline(always)]
+    pub fn id(&self) -> Option<Id> {
+        ListType::_id(self)
+    }
+
+    /// Observe the changes of children and attributes of element, the
+    /// observer is removed when the returned [Subscription] is dropped.
+    pub fn observe(&self, f: impl Fn(&XMLEvent) + Send + Sync + 'static) -> Subscription {
+        self.0.observe(Arc::new(move |event| {
+            if let Event::XML(event) = event {
+                f(event)
+            }
+        }))
+    }
+
+    /// Observe the changes of this element and all nested types, the events
+    /// of a transaction are received at once with their paths from this
+    /// element.
+    pub fn observe_deep(&self, f: impl Fn(&[Event]) + Send + Sync + 'static) -> Subscription {
+        self.0.observe_deep(Arc::new(move |events: &Vec<Event>| f(events)))
+    }
+
+    /// The tag name of element, e.g. `paragraph` of `<paragraph></paragraph>`
+    pub fn tag(&self) -> String {
+        self.as_inner().ty().and_then(|ty| ty.name.clone()).unwrap_or_default()
+    }
+
+    #[inline(always)]
+    pub fn get_attribute(&self, key: &str) -> Option<Value> {
+        self._get(key)
+    }
+
+    #[inline(always)]
+    pub fn insert_attribute<V: Into<Value>>(&mut self, key: String, value: V) -> JwstCodecResult {
+        self._insert(key, value)
+    }
+
+    #[inline(always)]
+    pub fn remove_attribute(&mut self, key: &str) {
+        self._remove(key)
+    }
+
+    #[inline(always)]
+    pub fn attributes(&self) -> EntriesIterator<'_> {
+        self._entries()
+    }
+}
+
+impl XMLFragment {
+    #[inline(always)]
+    pub fn id(&self) -> Option<Id> {
+        self._id()
+    }
+
+    /// Observe the changes of children of fragment, the observer is removed
+    /// when the returned [Subscription] is dropped.
+    pub fn observe(&self, f: impl Fn(&XMLEvent) + Send + Sync + 'static) -> Subscription {
+        self.0.observe(Arc::new(move |event| {
+            if let Event::XML(event) = event {
+                f(event)
+            }
+        }))
+    }
+
+    /// Observe the changes of this fragment and all nested types, the events
+    /// of a transaction are received at once with their paths from this
+    /// fragment.
+    pub fn observe_deep(&self, f: impl Fn(&[Event]) + Send + Sync + 'static) -> Subscription {
+        self.0.observe_deep(Arc::new(move |events: &Vec<Event>| f(events)))
+    }
+}
+
+impl XMLText {
+    #[inline(always)]
+    pub fn id(&self) -> Option<Id> {
+        self._id()
+    }
+
+    /// Observe the changes of text, the observer is removed when the
+    /// returned [Subscription] is dropped.
+    pub fn observe(&self, f: impl Fn(&TextEvent) + Send + Sync + 'static) -> Subscription {
+        self.0.observe(Arc::new(move |event| {
+            if let Event::Text(event) = event {
+                f(event)
+            }
+        }))
+    }
+
+    /// Observe the changes of this text and all embedded types, the events
+    /// of a transaction are received at once with their paths from this
+    /// text.
+    pub fn observe_deep(&self, f: impl Fn(&[Event]) + Send + Sync + 'static) -> Subscription {
+        self.0.observe_deep(Arc::new(move |events: &Vec<Event>| f(events)))
+    }
+
+    /// XMLText shares the same structure with Text, only differs in the
+    /// xml serialization
+    fn as_text(&self) -> Text {
+        Text::from_unchecked(self.0.clone())
+    }
+
+    #[inline]
+    pub fn len(&self) -> u64 {
+        self.as_text().len()
+    }
+
+    #[inline]
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    #[inline]
+    pub fn insert<T: ToString>(&mut self, char_index: u64, str: T) -> JwstCodecResult {
+        self.as_text().insert(char_index, str)
+    }
+
+    #[inline]
+    pub fn remove(&mut self, char_index: u64, len: u64) -> JwstCodecResult {
+        self.as_text().remove(char_index, len)
+    }
+
+    #[inline]
+    pub fn insert_with_attributes<T: ToString>(
+        &mut self,
+        char_index: u64,
+        str: T,
+        attrs: TextAttributes,
+    ) -> JwstCodecResult {
+        self.as_text().insert_with_attributes(char_index, str, attrs)
+    }
+
+    /// Apply formatting attributes to the text in range, the attributes are
+    /// rendered as nested tags in xml serialization
+    #[inline]
+    pub fn format(&mut self, char_index: u64, len: u64, attrs: TextAttributes) -> JwstCodecResult {
+        self.as_text().format(char_index, len, attrs)
+    }
+
+    #[inline]
+    pub fn to_delta(&self) -> TextDelta {
+        self.as_text().to_delta()
+    }
+
+    #[inline]
+    pub fn apply_delta(&mut self, delta: &[TextDeltaOp]) -> JwstCodecResult {
+        self.as_text().apply_delta(delta)
+    }
+}
+
+impl XMLHook {
+    #[inline(always)]
+    pub fn id(&self) -> Option<Id> {
+        ListType::_id(self)
+    }
+
+    /// Observe the changes of entries of hook, the observer is removed when
+    /// the returned [Subscription] is dropped.
+    pub fn observe(&self, f: impl Fn(&MapEvent) + Send + Sync + 'static) -> Subscription {
+        self.0.observe(Arc::new(move |event| {
+            if let Event::Map(event) = event {
+                f(event)
+            }
+        }))
+    }
+
+    /// Observe the changes of this hook and all nested types, the events of
+    /// a transaction are received at once with their paths from this hook.
+    pub fn observe_deep(&self, f: impl Fn(&[Event]) + Send + Sync + 'static) -> Subscription {
+        self.0.observe_deep(Arc::new(move |events: &Vec<Event>| f(events)))
+    }
+
+    pub fn hook_name(&self) -> String {
+        self.as_inner().ty().and_then(|ty| ty.name.clone()).unwrap_or_default()
+    }
+
+    #[inline(always)]
+    pub fn insert<V: Into<Value>>(&mut self, key: String, value: V) -> JwstCodecResult {
+        self._insert(key, value)
+    }
+
+    #[inline(always)]
+    pub fn get(&self, key: &str) -> Option<Value> {
+        self._get(key)
+    }
+
+    #[inline(always)]
+    pub fn contains_key(&self, key: &str) -> bool {
+        self._contains_key(key)
+    }
+
+    #[inline(always)]
+    pub fn remove(&mut self, key: &str) {
+        self._remove(key)
+    }
+
+    #[inline(always)]
+    pub fn len(&self) -> u64 {
+        self._len()
+    }
+
+    #[inline(always)]
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    #[inline(always)]
+    pub fn iter(&self) -> EntriesIterator<'_> {
+        self._entries()
+    }
+}
+
+impl Display for XMLFragment {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        self.children().try_for_each(|child| write!(f, "{child}"))
+    }
+}
+
+impl Display for XMLElement {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let tag = self.tag().to_lowercase();
+        let mut attrs = self
+            .attributes()
+            .map(|(key, value)| (key.to_string(), value))
+            .collect::<Vec<_>>();
+        attrs.sort_by(|(a, _), (b, _)| a.cmp(b));
+
+        write!(f, "<{tag}")?;
+        for (key, value) in attrs {
+            match value {
+                Value::Any(any) => write!(f, " {key}=\"{}\"", js_string(&any))?,
+                value => write!(f, " {key}=\"{value}\"")?,
+            }
+        }
+        write!(f, ">")?;
+        self.children().try_for_each(|child| write!(f, "{child}"))?;
+        write!(f, "</{tag}>")
+    }
+}
+
+impl Display for XMLText {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        for op in self.to_delta() {
+            let TextDeltaOp::Insert { insert, format } = op else {
+                continue;
+            };
+            let format = format.unwrap_or_default();
+
+            // formats are already sorted by name
+            for (name, value) in &format {
+                write!(f, "<{name}")?;
+                if let Any::Object(attrs) = value {
+                    let mut attrs = attrs.iter().collect::<Vec<_>>();
+                    attrs.sort_by_key(|(key, _)| *key);
+                    for (key, value) in attrs {
+                        write!(f, " {key}=\"{}\"", js_string(value))?;
+                    }
+                }
+                write!(f, ">")?;
+            }
+
+            match insert {
+                TextInsert::Text(text) => write!(f, "{text}")?,
+                TextInsert::Embed(values) => {
+                    for value in values {
+                        write!(f, "{}", js_string(&value))?;
+                    }
+                }
+            }
+
+            for name in format.keys().rev() {
+                write!(f, "</{name}>")?;
+            }
+        }
+
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{Doc, loom_model};
+
+    #[test]
+    fn test_xml_element() {
+        loom_model!({
+            let doc = Doc::default();
+            let mut fragment = doc.get_or_create_xml_fragment("prosemirror").unwrap();
+
+            let mut paragraph = doc.create_xml_element("paragraph").unwrap();
+            fragment.push(paragraph.clone()).unwrap();
+            assert_eq!(paragraph.tag(), "paragraph");
+
+            paragraph.insert_attribute("indent".into(), 1).unwrap();
+            paragraph.insert_attribute("align".into(), "left").unwrap();
+            paragraph.remove_attribute("indent");
+            assert_eq!(paragraph.get_attribute("align"), Some(Value::from("left")));
+            assert_eq!(paragraph.get_attribute("indent"), None);
+            assert_eq!(paragraph.attributes().count(), 1);
+
+            let mut text = doc.create_xml_text().unwrap();
+            paragraph.push(text.clone()).unwrap();
+            text.insert(0, "hello").unwrap();
+            let mut heading = doc.create_xml_element("heading").unwrap();
+            fragment.insert(0, heading.clone()).unwrap();
+            heading.insert_attribute("level".into(), 2).unwrap();
+
+            assert_eq!(fragment.len(), 2);
+            assert!(matches!(fragment.get(0), Some(Value::XMLElement(el)) if el.tag() == "heading"));
+            assert_eq!(paragraph.children().collect::<Vec<_>>(), vec![Value::XMLText(text)]);
+            assert_eq!(
+                fragment.to_string(),
+                r#"<heading level="2"></heading><paragraph align="left">hello</paragraph>"#
+            );
+            assert_eq!(
+                fragment.push(Value::from("plain")),
+                Err(JwstCodecError::UnexpectedType("XMLElement, XMLText or XMLHook"))
+            );
+
+            fragment.remove(0, 1).unwrap();
+            assert_eq!(fragment.to_string(), r#"<paragraph align="left">hello</paragraph>"#);
+        });
+    }
+
+    #[test]
+    fn test_xml_text_format() {
+        loom_model!({
+            let doc = Doc::default();
+            let mut fragment = doc.get_or_create_xml_fragment("prosemirror").unwrap();
+            let mut text = doc.create_xml_text().unwrap();
+            fragment.push(text.clone()).unwrap();
+
+            text.insert(0, "hello world").unwrap();
+            text.format(0, 5, TextAttributes::from([("bold".into(), Any::True)]))
+                .unwrap();
+            text.format(
+                6,
+                5,
+                TextAttributes::from([(
+                    "link".into(),
+                    Any::Object(HashMap::from_iter([("href".into(), Any::from("https://yjs.dev"))])),
+                )]),
+            )
+            .unwrap();
+
+            assert_eq!(text.len(), 11);
+            assert_eq!(
+                fragment.to_string(),
+                r#"<bold>hello</bold> <link href="https://yjs.dev">world</link>"#
+            );
+        });
+    }
+
+    #[test]
+    fn test_xml_hook() {
+        loom_model!({
+            let doc = Doc::default();
+            let mut fragment = doc.get_or_create_xml_fragment("prosemirror").unwrap();
+            let mut hook: XMLHook = YTypeBuilder::new(doc.store.clone())
+                .with_kind(YTypeKind::XMLHook)
+                .set_tag_name("image".into())
+                .build()
+                .unwrap();
+            fragment.push(hook.clone()).unwrap();
+
+            hook.insert("src".into(), "a.png").unwrap();
+            assert_eq!(hook.hook_name(), "image");
+            assert!(hook.contains_key("src"));
+            assert_eq!(hook.len(), 1);
+            hook.remove("src");
+            assert!(hook.is_empty());
+        });
+    }
+
+    #[test]
+    fn test_xml_codec() {
+        loom_model!({
+            let doc = Doc::default();
+            let mut fragment = doc.get_or_create_xml_fragment("prosemirror").unwrap();
+            let mut paragraph = doc.create_xml_element("paragraph").unwrap();
+            fragment.push(paragraph.clone()).unwrap();
+            paragraph.insert_attribute("align".into(), "left").unwrap();
+            let mut text = doc.create_xml_text().unwrap();
+            paragraph.push(text.clone()).unwrap();
+            text.insert(0, "hello").unwrap();
+
+            let doc = Doc::try_from_binary_v1(doc.encode_update_v1().unwrap()).unwrap();
+            let fragment = doc.get_or_create_xml_fragment("prosemirror").unwrap();
+            let Some(Value::XMLElement(paragraph)) = fragment.get(0) else {
+                panic!("expect xml element");
+            };
+
+            assert_eq!(paragraph.tag(), "paragraph");
+            assert_eq!(fragment.to_string(), r#"<paragraph align="left">hello</paragraph>"#);
+        });
+    }
+}
@@ -0,0 +1,894 @@
+use std::{
+    collections::BTreeMap,
+    sync::atomic::{AtomicU64, Ordering},
+    time::{Duration, Instant},
+};
+
+use super::{store::StoreRef, *};
+use crate::sync::{Arc, Mutex};
+
+/// [UndoManagerOptions] used to create a new [UndoManager]
+///
+/// ```
+/// use std::time::Duration;
+///
+/// use y_octo::{Doc, UndoManager, UndoManagerOptions};
+///
+/// let doc = Doc::new();
+/// let text = doc.get_or_create_text("text").unwrap();
+/// let options = UndoManagerOptions::new()
+///     .with_capture_timeout(Duration::from_millis(1000))
+///     .with_tracked_origin("editor".into());
+///
+/// let undo_manager = UndoManager::with_options(&doc, text, options).unwrap();
+/// assert!(!undo_manager.can_undo());
+/// ```
+#[derive(Clone, Debug)]
+pub struct UndoManagerOptions {
+    /// The changes made within the timeout are merged into one stack item.
+    pub capture_timeout: Duration,
+    /// The origins of transactions to track, the transactions without an
+    /// origin are tracked only if no origin is given.
+    pub tracked_origins: HashSet<Origin>,
+}
+
+impl Default for UndoManagerOptions {
+    fn default() -> Self {
+        Self {
+            capture_timeout: Duration::from_millis(500),
+            tracked_origins: HashSet::default(),
+        }
+    }
+}
+
+impl UndoManagerOptions {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn with_capture_timeout(mut self, capture_timeout: Duration) -> Self {
+        self.capture_timeout = capture_timeout;
+        self
+    }
+
+    pub fn with_tracked_origin(mut self, origin: Origin) -> Self {
+        self.tracked_origins.insert(origin);
+        self
+    }
+}
+
+/// The changes to revert by one undo or redo
+#[derive(Debug, Clone, Default)]
+struct StackItem {
+    insertions: DeleteSet,
+    deletions: DeleteSet,
+}
+
+struct UndoState {
+    scope: Vec<YTypeRef>,
+    options: UndoManagerOptions,
+    origin: Origin,
+    undo_stack: Vec<StackItem>,
+    redo_stack: Vec<StackItem>,
+    undoing: bool,
+    redoing: bool,
+    last_change: Option<Instant>,
+    // deleted item ranges that have been recreated: client => clock => (len, id of the new item)
+    redone: HashMap<Client, BTreeMap<Clock, (Clock, Id)>>,
+}
+
+unsafe impl Send for UndoState {}
+
+// the id of the next manager, it makes the origin of every manager unique
+static NEXT_MANAGER_ID: AtomicU64 = AtomicU64::new(0);
+
+/// Undo and redo the local changes made to the types in scope, like
+/// `Y.UndoManager`.
+///
+/// The changes are recorded as stack items of deleted items and inserted
+/// item ranges. Undoing a stack item deletes the inserted items and recreates
+/// the deleted ones, which produces normal updates that can be synced to
+/// other peers.
+pub struct UndoManager {
+    store: StoreRef,
+    state: Arc<Mutex<UndoState>>,
+    _subscription: Subscription,
+}
+
+impl UndoManager {
+    pub fn new<T: Into<Value>>(doc: &Doc, scope: T) -> JwstCodecResult<Self> {
+        Self::with_options(doc, scope, UndoManagerOptions::default())
+    }
+
+    pub fn with_options<T: Into<Value>>(doc: &Doc, scope: T, options: UndoManagerOptions) -> JwstCodecResult<Self> {
+        let scope = scope_type(scope)?;
+        // the changes made by undo and redo are marked with the manager's own origin
+        let id = NEXT_MANAGER_ID.fetch_add(1, Ordering::Relaxed);
+        let state = Arc::new(Mutex::new(UndoState {
+            scope: vec![scope],
+            options,
+            origin: Origin::from(format!("y-octo:undo-manager:{id}")),
+            undo_stack: Vec::new(),
+            redo_stack: Vec::new(),
+            undoing: false,
+            redoing: false,
+            last_change: None,
+            redone: HashMap::default(),
+        }));
+
+        let weak_state = Arc::downgrade(&state);
+        let weak_store = Arc::downgrade(&doc.store);
+        let subscription = Subscription::new(
+            &doc.store.read().unwrap().transaction_observers,
+            Arc::new(move |transaction| {
+                if let (Some(state), Some(store)) = (weak_state.upgrade(), weak_store.upgrade()) {
+                    let store = store.read().unwrap();
+                    state.lock().unwrap().record(&store, transaction);
+                }
+            }),
+        );
+
+        Ok(Self {
+            store: doc.store.clone(),
+            state,
+            _subscription: subscription,
+        })
+    }
+
+    /// The origin of transactions made by [UndoManager::undo] and
+    /// [UndoManager::redo].
+    pub fn origin(&self) -> Origin {
+        self.state.lock().unwrap().origin.clone()
+    }
+
+    /// Track the changes of another type.
+    pub fn expand_scope<T: Into<Value>>(&self, scope: T) -> JwstCodecResult {
+        let scope = scope_type(scope)?;
+        let mut state = self.state.lock().unwrap();
+        if !state.scope.contains(&scope) {
+            state.scope.push(scope);
+        }
+
+        Ok(())
+    }
+
+    pub fn include_origin(&self, origin: Origin) {
+        self.state.lock().unwrap().options.tracked_origins.insert(origin);
+    }
+
+    pub fn exclude_origin(&self, origin: &Origin) {
+        self.state.lock().unwrap().options.tracked_origins.remove(origin);
+    }
+
+    /// Revert the last stack item on the undo stack, returns whether anything
+    /// has been changed.
+    pub fn undo(&self) -> JwstCodecResult<bool> {
+        self.pop_stack_item(true)
+    }
+
+    /// Revert the last stack item on the redo stack, returns whether anything
+    /// has been changed.
+    pub fn redo(&self) -> JwstCodecResult<bool> {
+        self.pop_stack_item(false)
+    }
+
+    pub fn can_undo(&self) -> bool {
+        !self.state.lock().unwrap().undo_stack.is_empty()
+    }
+
+    pub fn can_redo(&self) -> bool {
+        !self.state.lock().unwrap().redo_stack.is_empty()
+    }
+
+    /// Make the next change a new stack item instead of merging it into the
+    /// last one.
+    pub fn stop_capturing(&self) {
+        self.state.lock().unwrap().last_change = None;
+    }
+
+    /// Clear both the undo and redo stacks.
+    pub fn clear(&self) {
+        let store = self.store.read().unwrap();
+        let mut state = self.state.lock().unwrap();
+        let undo_stack = std::mem::take(&mut state.undo_stack);
+        let redo_stack = std::mem::take(&mut state.redo_stack);
+        state.release(&store, undo_stack.iter().chain(redo_stack.iter()));
+    }
+
+    fn pop_stack_item(&self, undo: bool) -> JwstCodecResult<bool> {
+        let mut store = lock_store(&self.store);
+        let mut state = self.state.lock().unwrap();
+        let scope = store.begin_transaction(Some(state.origin.clone()), true);
+        if undo {
+            state.undoing = true;
+        } else {
+            state.redoing = true;
+        }
+
+        let mut result = Ok(false);
+        while let Some(item) = if undo {
+            state.undo_stack.pop()
+        } else {
+            state.redo_stack.pop()
+        } {
+            match state.apply(&mut store, &item) {
+                Ok(false) => continue,
+                ret => {
+                    result = ret;
+                    break;
+                }
+            }
+        }
+
+        // the transaction observers lock the state to record the changes
+        drop(state);
+        commit_transaction(store, scope, false);
+
+        let mut state = self.state.lock().unwrap();
+        state.undoing = false;
+        state.redoing = false;
+
+        result
+    }
+}
+
+impl UndoState {
+    fn is_tracked(&self, transaction: &Transaction) -> bool {
+        transaction.local
+            && match &transaction.origin {
+                Some(origin) => *origin == self.origin || self.options.tracked_origins.contains(origin),
+                None => self.options.tracked_origins.is_empty(),
+            }
+    }
+
+    fn in_scope(&self, item: &Item) -> bool {
+        self.scope.iter().any(|ty| is_parent_of(ty, item))
+    }
+
+    fn record(&mut self, store: &DocStore, transaction: &Transaction) {
+        if !self.is_tracked(transaction) {
+            return;
+        }
+
+        let insertions = transaction.insertions();
+        let deleted_items = collect_items(store, &transaction.delete_set);
+        let changed = collect_items(store, &insertions)
+            .iter()
+            .chain(deleted_items.iter())
+            .any(|item| item.get().is_some_and(|item| self.in_scope(item)));
+        if !changed {
+            return;
+        }
+
+        if self.undoing {
+            // the next undo should not be merged into the redo stack item
+            self.last_change = None;
+        } else if !self.redoing {
+            let redo_stack = std::mem::take(&mut self.redo_stack);
+            self.release(store, redo_stack.iter());
+        }
+
+        let now = Instant::now();
+        let merge = !self.undoing
+            && !self.redoing
+            && self
+                .last_change
+                .is_some_and(|last| now.duration_since(last) < self.options.capture_timeout);
+        let stack = if self.undoing {
+            &mut self.redo_stack
+        } else {
+            &mut self.undo_stack
+        };
+
+        match stack.last_mut() {
+            Some(last) if merge => {
+                last.insertions.merge(&insertions);
+                last.deletions.merge(&transaction.delete_set);
+            }
+            _ => stack.push(StackItem {
+                insertions,
+                deletions: transaction.delete_set.clone(),
+            }),
+        }
+
+        if !self.undoing && !self.redoing {
+            self.last_change = Some(now);
+        }
+
+        // keep the deleted items from being gc'd, they may be recreated later
+        for item in &deleted_items {
+            if let Some(item) = item.get()
+                && self.in_scope(item)
+            {
+                keep_item(item, true);
+            }
+        }
+    }
+
+    /// Allow the deleted items of dropped stack items to be gc'd
+    fn release<'a>(&self, store: &DocStore, stack: impl Iterator<Item = &'a StackItem>) {
+        for stack_item in stack {
+            for item in collect_items(store, &stack_item.deletions) {
+                if let Some(item) = item.get()
+                    && self.in_scope(item)
+                {
+                    keep_item(item, false);
+                }
+            }
+        }
+    }
+
+    fn redone(&self, id: Id) -> Option<Id> {
+        let (start, (len, redone)) = self.redone.get(&id.client)?.range(..=id.clock).next_back()?;
+
+        (id.clock < start + len).then(|| Id::new(redone.client, redone.clock + id.clock - start))
+    }
+
+    /// Find the item that finally recreates the given one
+    fn follow_redone(&self, id: Id) -> Option<Id> {
+        let mut redone = self.redone(id)?;
+        while let Some(next) = self.redone(redone) {
+            redone = next;
+        }
+
+        Some(redone)
+    }
+
+    fn apply(&mut self, store: &mut DocStore, stack_item: &StackItem) -> JwstCodecResult<bool> {
+        let mut changed_types = HashSet::default();
+
+        let mut items_to_delete = Vec::new();
+        for mut item_ref in split_items(store, &stack_item.insertions)? {
+            if let Some(redone) = item_ref.get().and_then(|item| self.follow_redone(item.id)) {
+                item_ref = get_item_clean_start(store, redone)?;
+            }
+
+            if let Some(item) = item_ref.get()
+                && !item.deleted()
+                && self.in_scope(item)
+            {
+                items_to_delete.push(item_ref.clone());
+            }
+        }
+
+        let mut items_to_redo = Vec::new();
+        let mut redo_ids = HashSet::default();
+        for item_ref in split_items(store, &stack_item.deletions)? {
+            // items created and deleted in the same stack item are never redone
+            if let Some(item) = item_ref.get()
+                && self.in_scope(item)
+                && !stack_item.insertions.contains(&item.id)
+            {
+                redo_ids.insert(item.id);
+                items_to_redo.push(item_ref.clone());
+            }
+        }
+
+        let mut performed = false;
+        for item_ref in &items_to_redo {
+            if let Some(redone) = self.redo_item(store, item_ref, &redo_ids, &stack_item.insertions)? {
+                if let Some(Parent::Type(ty)) = redone.get().and_then(|item| item.parent.clone()) {
+                    changed_types.insert(ty);
+                }
+                performed = true;
+            }
+        }
+
+        // delete in reverse order so children are deleted before their parents
+        for item_ref in items_to_delete.iter().rev() {
+            if let Some(item) = item_ref.get()
+                && !item.deleted()
+            {
+                if let Some(Parent::Type(ty)) = &item.parent {
+                    changed_types.insert(ty.clone());
+                }
+                store.delete_item(item, None);
+                performed = true;
+            }
+        }
+
+        // items are changed without updating the search markers
+        for ty in changed_types {
+            if let Some(ty) = ty.ty()
+                && let Some(markers) = &ty.markers
+            {
+                markers.borrow_mut().clear();
+            }
+        }
+
+        Ok(performed)
+    }
+
+    /// Recreate a deleted item at its original position, like `redoItem` in
+    /// yjs.
+    fn redo_item(
+        &mut self,
+        store: &mut DocStore,
+        item_ref: &ItemRef,
+        redo_ids: &HashSet<Id>,
+        insertions: &DeleteSet,
+    ) -> JwstCodecResult<Option<ItemRef>> {
+        let Some(item) = item_ref.get() else {
+            return Ok(None);
+        };
+
+        if let Some(redone) = self.follow_redone(item.id) {
+            return get_item_clean_start(store, redone).map(Some);
+        }
+
+        let Some(Parent::Type(parent_ty)) = &item.parent else {
+            return Ok(None);
+        };
+
+        // make sure the parent is recreated
+        let mut parent_item = parent_item(item);
+        if let Some((parent_id, true)) = parent_item.get().map(|parent| (parent.id, parent.deleted())) {
+            if self.follow_redone(parent_id).is_none()
+                && (!redo_ids.contains(&parent_id)
+                    || self
+                        .redo_item(store, &parent_item.clone(), redo_ids, insertions)?
+                        .is_none())
+            {
+                return Ok(None);
+            }
+
+            if let Some(redone) = self.follow_redone(parent_id) {
+                parent_item = get_item_clean_start(store, redone)?;
+            }
+        }
+
+        let parent_ty = match parent_item.get() {
+            Some(parent) => match &parent.content {
+                Content::Type(ty) => ty.clone(),
+                _ => return Ok(None),
+            },
+            None => parent_ty.clone(),
+        };
+        let parent_id = parent_item.get().map(|parent| parent.id);
+
+        let (left, right) = if let Some(parent_sub) = &item.parent_sub {
+            let mut left = item_ref.clone();
+            if item.right.is_some() {
+                // the values set after this item must be going to be reverted,
+                // otherwise it conflicts with changes from others
+                loop {
+                    let right = left.get().map(|left| left.right.clone()).unwrap_or_default();
+                    let Some(right_id) = right.get().map(|right| right.id) else {
+                        break;
+                    };
+
+                    if insertions.contains(&right_id) || self.follow_redone(right_id).is_some() {
+                        left = match self.follow_redone(right_id) {
+                            Some(redone) => get_item_clean_start(store, redone)?,
+                            None => right,
+                        };
+                    } else {
+                        return Ok(None);
+                    }
+                }
+            } else {
+                left = parent_ty
+                    .ty()
+                    .and_then(|ty| ty.map.get(parent_sub).cloned())
+                    .unwrap_or_default();
+            }
+
+            (left, Somr::none())
+        } else {
+            (
+                self.trace_sibling(store, item.left.clone(), parent_id, |item| item.left.clone())?,
+                self.trace_sibling(store, item_ref.clone(), parent_id, |item| item.right.clone())?,
+            )
+        };
+
+        let Some(content) = copy_content(store, &item.content) else {
+            return Ok(None);
+        };
+
+        let new_item = store.create_item(
+            content,
+            left,
+            right,
+            Some(Parent::Type(parent_ty)),
+            item.parent_sub.clone(),
+        );
+        let new_ref = new_item.clone();
+        if let Some(new) = new_ref.get() {
+            self.redone
+                .entry(item.id.client)
+                .or_default()
+                .insert(item.id.clock, (item.len(), new.id));
+            keep_item(new, true);
+        }
+        store.integrate(Node::Item(new_item), 0, None)?;
+
+        Ok(Some(new_ref))
+    }
+
+    /// Find the nearest sibling, or its recreated one, which belongs to the
+    /// parent.
+    fn trace_sibling(
+        &self,
+        store: &mut DocStore,
+        mut cur: ItemRef,
+        parent_id: Option<Id>,
+        next: impl Fn(&Item) -> ItemRef,
+    ) -> JwstCodecResult<ItemRef> {
+        while let Some(item) = cur.get() {
+            let mut trace = cur.clone();
+            while let Some(traced) = trace.get()
+                && parent_item(traced).get().map(|parent| parent.id) != parent_id
+            {
+                trace = match self.follow_redone(traced.id) {
+                    Some(redone) => get_item_clean_start(store, redone)?,
+                    None => Somr::none(),
+                };
+            }
+
+            if trace.is_some() {
+                return Ok(trace);
+            }
+
+            cur = next(item);
+        }
+
+        Ok(Somr::none())
+    }
+}
+
+fn scope_type<T: Into<Value>>(scope: T) -> JwstCodecResult<YTypeRef> {
+    scope
+        .into()
+        .as_type_ref()
+        .cloned()
+        .ok_or(JwstCodecError::UnexpectedType("Text, Array, Map or XML types"))
+}
+
+fn parent_item(item: &Item) -> ItemRef {
+    match &item.parent {
+        Some(Parent::Type(ty)) => ty.ty().map(|ty| ty.item.clone()).unwrap_or_default(),
+        _ => Somr::none(),
+    }
+}
+
+fn is_parent_of(parent: &YTypeRef, item: &Item) -> bool {
+    match &item.parent {
+        Some(Parent::Type(ty)) if ty == parent => true,
+        _ => parent_item(item).get().is_some_and(|item| is_parent_of(parent, item)),
+    }
+}
+
+fn keep_item(item: &Item, keep: bool) {
+    if item.keep() == keep {
+        return;
+    }
+
+    if keep {
+        item.flags.set_keep();
+    } else {
+        item.flags.clear_keep();
+    }
+
+    if let Some(parent) = parent_item(item).get() {
+        keep_item(parent, keep);
+    }
+}
+
+fn copy_content(store: &mut DocStore, content: &Content) -> Option<Content> {
+    match content {
+        // gc'd, not able to recreate
+        Content::Deleted(_) => None,
+        Content::Type(ty) => ty.copy_empty(store).map(Content::Type),
+        // the priority is adapted again once it's redone
+        Content::Move(content) => Some(Content::Move(Box::new(Move::new(
+            content.start,
+            content.start_assoc,
+            content.end,
+            content.end_assoc,
+        )))),
+        content => Some(content.clone()),
+    }
+}
+
+fn get_item_clean_start(store: &mut DocStore, id: Id) -> JwstCodecResult<ItemRef> {
+    Ok(store.split_at_and_get_right(id)?.as_item())
+}
+
+/// Collect the items overlapped with the ranges
+fn collect_items(store: &DocStore, ranges: &DeleteSet) -> Vec<ItemRef> {
+    let mut items = Vec::new();
+    for (client, ranges) in ranges.iter() {
+        let Some(nodes) = store.items.get(client) else {
+            continue;
+        };
+
+        for range in ranges {
+            if let Some(idx) = DocStore::get_node_index(nodes, range.start) {
+                items.extend(
+                    nodes
+                        .iter()
+                        .skip(idx)
+                        .take_while(|node| node.clock() < range.end)
+                        .filter(|node| node.is_item())
+                        .map(|node| node.as_item()),
+                );
+            }
+        }
+    }
+
+    items
+}
+
+/// Collect the items in the ranges, the items on the boundaries are split
+fn split_items(store: &mut DocStore, ranges: &DeleteSet) -> JwstCodecResult<Vec<ItemRef>> {
+    for (client, ranges) in ranges.iter() {
+        let state = store.get_state(*client);
+        for range in ranges {
+            let end = range.end.min(state);
+            if range.start < end {
+                store.split_at_and_get_right(Id::new(*client, range.start))?;
+                store.split_at_and_get_left(Id::new(*client, end - 1))?;
+            }
+        }
+    }
+
+    Ok(collect_items(store, ranges))
+}
+
+#[cfg(test)]
+mod tests {
+    use yrs::{GetString, Options, Transact, updates::decoder::Decode};
+
+    use super::*;
+
+    #[test]
+    fn test_undo_text() {
+        loom_model!({
+            let doc = Doc::new();
+            let mut text = doc.get_or_create_text("text").unwrap();
+            let undo_manager = UndoManager::new(&doc, text.clone()).unwrap();
+
+            text.insert(0, "hello").unwrap();
+            undo_manager.stop_capturing();
+            text.insert(5, " world").unwrap();
+            undo_manager.stop_capturing();
+            text.remove(0, 6).unwrap();
+            assert_eq!(text.to_string(), "world");
+
+            assert!(undo_manager.undo().unwrap());
+            assert_eq!(text.to_string(), "hello world");
+            assert!(undo_manager.undo().unwrap());
+            assert_eq!(text.to_string(), "hello");
+            assert!(undo_manager.undo().unwrap());
+            assert_eq!(text.to_string(), "");
+            assert!(!undo_manager.can_undo());
+            assert!(!undo_manager.undo().unwrap());
+
+            assert!(undo_manager.redo().unwrap());
+            assert_eq!(text.to_string(), "hello");
+            assert!(undo_manager.redo().unwrap());
+            assert_eq!(text.to_string(), "hello world");
+            assert!(undo_manager.redo().unwrap());
+            assert_eq!(text.to_string(), "world");
+            assert!(!undo_manager.can_redo());
+
+            // new changes clear the redo stack
+            undo_manager.undo().unwrap();
+            assert!(undo_manager.can_redo());
+            text.insert(0, "!").unwrap();
+            assert!(!undo_manager.can_redo());
+            assert_eq!(text.to_string(), "!hello world");
+        });
+    }
+
+    #[test]
+    fn test_undo_capture_timeout() {
+        loom_model!({
+            let doc = Doc::new();
+            let mut text = doc.get_or_create_text("text").unwrap();
+
+            let undo_manager = UndoManager::new(&doc, text.clone()).unwrap();
+            text.insert(0, "a").unwrap();
+            text.insert(1, "b").unwrap();
+            text.format(0, 2, TextAttributes::from([("bold".into(), true.into())]))
+                .unwrap();
+            // merged into one stack item
+            undo_manager.undo().unwrap();
+            assert_eq!(text.to_string(), "");
+            assert!(!undo_manager.can_undo());
+            drop(undo_manager);
+
+            let undo_manager = UndoManager::with_options(
+                &doc,
+                text.clone(),
+                UndoManagerOptions::new().with_capture_timeout(Duration::ZERO),
+            )
+            .unwrap();
+            text.insert(0, "a").unwrap();
+            text.insert(1, "b").unwrap();
+            undo_manager.undo().unwrap();
+            assert_eq!(text.to_string(), "a");
+            undo_manager.undo().unwrap();
+            assert_eq!(text.to_string(), "");
+        });
+    }
+
+    #[test]
+    fn test_undo_array_and_map() {
+        loom_model!({
+            let doc = Doc::new();
+            let mut array = doc.get_or_create_array("array").unwrap();
+            let mut map = doc.get_or_create_map("map").unwrap();
+            let undo_manager = UndoManager::new(&doc, array.clone()).unwrap();
+            undo_manager.expand_scope(map.clone()).unwrap();
+
+            array.push(1).unwrap();
+            array.push(2).unwrap();
+            undo_manager.stop_capturing();
+            array.remove(0, 1).unwrap();
+            undo_manager.stop_capturing();
+            map.insert("key".into(), "a").unwrap();
+            undo_manager.stop_capturing();
+            map.insert("key".into(), "b").unwrap();
+            undo_manager.stop_capturing();
+            map.remove("key");
+
+            undo_manager.undo().unwrap();
+            assert_eq!(map.get("key"), Some(Value::from("b")));
+            undo_manager.undo().unwrap();
+            assert_eq!(map.get("key"), Some(Value::from("a")));
+            undo_manager.undo().unwrap();
+            assert!(map.get("key").is_none());
+            undo_manager.undo().unwrap();
+            assert_eq!(array.len(), 2);
+            assert_eq!(array.get(0), Some(Value::from(1)));
+            undo_manager.undo().unwrap();
+            assert_eq!(array.len(), 0);
+
+            for _ in 0..5 {
+                undo_manager.redo().unwrap();
+            }
+            assert_eq!(array.len(), 1);
+            assert_eq!(array.get(0), Some(Value::from(2)));
+            assert!(map.get("key").is_none());
+
+            undo_manager.undo().unwrap();
+            assert_eq!(map.get("key"), Some(Value::from("b")));
+        });
+    }
+
+    #[test]
+    fn test_undo_nested_types() {
+        loom_model!({
+            let doc = Doc::new();
+            let mut array = doc.get_or_create_array("array").unwrap();
+            let undo_manager = UndoManager::new(&doc, array.clone()).unwrap();
+
+            let text = doc.create_text().unwrap();
+            array.push(text).unwrap();
+            let mut text = array.get(0).unwrap().to_text().unwrap();
+            text.insert(0, "hello").unwrap();
+            undo_manager.stop_capturing();
+
+            array.remove(0, 1).unwrap();
+            assert_eq!(array.len(), 0);
+            doc.gc().unwrap();
+
+            undo_manager.undo().unwrap();
+            assert_eq!(array.len(), 1);
+            assert_eq!(array.get(0).unwrap().to_text().unwrap().to_string(), "hello");
+
+            undo_manager.undo().unwrap();
+            assert_eq!(array.len(), 0);
+        });
+    }
+
+    #[test]
+    fn test_undo_tracked_origins() {
+        loom_model!({
+            let doc = Doc::new();
+            let mut text = doc.get_or_create_text("text").unwrap();
+            let untracked = doc.get_or_create_text("untracked").unwrap();
+            let undo_manager = UndoManager::with_options(
+                &doc,
+                text.clone(),
+                UndoManagerOptions::new().with_tracked_origin("editor".into()),
+            )
+            .unwrap();
+
+            // changes out of scope or without tracked origin are ignored
+            untracked.clone().insert(0, "untracked").unwrap();
+            text.insert(0, "remote ").unwrap();
+            doc.transact(Some("sync".into()), |_| text.insert(0, "sync ").unwrap());
+            assert!(!undo_manager.can_undo());
+
+            doc.transact(Some("editor".into()), |_| {
+                text.insert(0, "hello ").unwrap();
+                text.remove(6, 5).unwrap();
+            });
+            assert!(undo_manager.can_undo());
+            assert_eq!(text.to_string(), "hello remote ");
+
+            undo_manager.undo().unwrap();
+            assert_eq!(text.to_string(), "sync remote ");
+            assert_eq!(untracked.to_string(), "untracked");
+
+            undo_manager.exclude_origin(&"editor".into());
+            doc.transact(Some("editor".into()), |_| text.insert(0, "hello ").unwrap());
+            assert!(!undo_manager.can_undo());
+
+            // every manager marks its changes with an origin of its own
+            let another = UndoManager::new(&doc, untracked).unwrap();
+            assert_ne!(another.origin(), undo_manager.origin());
+        });
+    }
+
+    #[test]
+    fn test_undo_sync() {
+        loom_model!({
+            let mut doc = DocOptions::new().with_client_id(1).build();
+            let mut text = doc.get_or_create_text("text").unwrap();
+            let mut remote = DocOptions::new().with_client_id(2).build();
+            let undo_manager = UndoManager::new(&doc, text.clone()).unwrap();
+
+            text.insert(0, "hello").unwrap();
+            remote
+                .apply_update_from_binary_v1(doc.encode_update_v1().unwrap())
+                .unwrap();
+
+            // remote changes are not tracked
+            let mut remote_text = remote.get_or_create_text("text").unwrap();
+            remote_text.insert(5, " world").unwrap();
+            doc.apply_update_from_binary_v1(remote.encode_update_v1().unwrap())
+                .unwrap();
+            assert_eq!(text.to_string(), "hello world");
+
+            undo_manager.undo().unwrap();
+            assert_eq!(text.to_string(), " world");
+
+            remote
+                .apply_update_from_binary_v1(doc.encode_update_v1().unwrap())
+                .unwrap();
+            assert_eq!(remote_text.to_string(), " world");
+
+            undo_manager.redo().unwrap();
+            remote
+                .apply_update_from_binary_v1(doc.encode_update_v1().unwrap())
+                .unwrap();
+            assert_eq!(remote_text.to_string(), "hello world");
+        });
+    }
+
+    #[test]
+    #[cfg_attr(miri, ignore)]
+    fn test_undo_converge_with_yrs() {
+        loom_model!({
+            let doc = DocOptions::new().with_client_id(1).build();
+            let mut text = doc.get_or_create_text("text").unwrap();
+            let undo_manager = UndoManager::new(&doc, text.clone()).unwrap();
+
+            text.insert(0, "hello world").unwrap();
+            undo_manager.stop_capturing();
+            text.remove(0, 6).unwrap();
+            undo_manager.undo().unwrap();
+            undo_manager.stop_capturing();
+            text.insert(11, "!").unwrap();
+            undo_manager.undo().unwrap();
+            undo_manager.undo().unwrap();
+            undo_manager.redo().unwrap();
+            assert_eq!(text.to_string(), "hello world");
+
+            let yrs_doc = yrs::Doc::with_options(Options {
+                client_id: 2,
+                ..Default::default()
+            });
+            let yrs_text = yrs_doc.get_or_insert_text("text");
+            yrs_doc
+                .transact_mut()
+                .apply_update(yrs::Update::decode_v1(&doc.encode_update_v1().unwrap()).unwrap())
+                .unwrap();
+
+            assert_eq!(yrs_text.get_string(&yrs_doc.transact()), "hello world");
+        });
+    }
+}
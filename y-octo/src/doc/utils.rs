@@ -25,6 +25,18 @@ pub fn merge_updates_v1<V: AsRef<[u8]>, I: IntoIterator<Item = V>>(updates: I) -
     Ok(Update::merge(updates))
 }
 
+/// Convert an update from v1 to v2 binary format without applying it to a
+/// doc, pending structs and delete set are kept as is.
+pub fn convert_update_v1_to_v2<T: AsRef<[u8]>>(update: T) -> JwstCodecResult<Vec<u8>> {
+    Update::decode_v1(update)?.encode_v2()
+}
+
+/// Convert an update from v2 to v1 binary format without applying it to a
+/// doc, pending structs and delete set are kept as is.
+pub fn convert_update_v2_to_v1<T: AsRef<[u8]>>(update: T) -> JwstCodecResult<Vec<u8>> {
+    Update::decode_v2(update)?.encode_v1()
+}
+
 /// It tends to generate small numbers.
 /// Since the client id will be included in all crdt items, the
 /// small client helps to reduce the binary size.
@@ -42,3 +54,46 @@ pub fn prefer_small_random() -> u64 {
 
     (v * scale_factor) as u64
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    #[cfg_attr(any(miri, loom), ignore)]
+    fn test_convert_update_format() {
+        let updates = [
+            include_bytes!("../fixtures/basic.bin").to_vec(),
+            include_bytes!("../fixtures/database.bin").to_vec(),
+            include_bytes!("../fixtures/large.bin").to_vec(),
+            include_bytes!("../fixtures/with-subdoc.bin").to_vec(),
+            include_bytes!("../fixtures/edge-case-left-right-same-node.bin").to_vec(),
+        ];
+
+        for update in updates {
+            let v2 = convert_update_v1_to_v2(&update).unwrap();
+            let v1 = convert_update_v2_to_v1(&v2).unwrap();
+
+            assert_eq!(v1, Update::decode_v1(&update).unwrap().encode_v1().unwrap());
+            assert_eq!(v2, convert_update_v1_to_v2(&v1).unwrap());
+        }
+    }
+
+    #[test]
+    fn test_convert_update_with_pending_structs() {
+        loom_model!({
+            let doc = Doc::new();
+            let mut text = doc.get_or_create_text("text").unwrap();
+            text.insert(0, "hello").unwrap();
+            let sv = doc.get_state_vector();
+            text.insert(5, " world").unwrap();
+            text.remove(0, 1).unwrap();
+
+            // depends on the first insertion, can not be integrated on its own
+            let update = doc.encode_state_as_update_v1(&sv).unwrap();
+
+            let v2 = convert_update_v1_to_v2(&update).unwrap();
+            assert_eq!(convert_update_v2_to_v1(v2).unwrap(), update);
+        });
+    }
+}
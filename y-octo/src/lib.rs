@@ -6,10 +6,14 @@ mod sync;
 
 pub use codec::*;
 pub use doc::{
-    Any, Array, Awareness, AwarenessEvent, Batch, Client, ClientMap, Clock, CrdtRead, CrdtReader, CrdtWrite,
-    CrdtWriter, Doc, DocOptions, HashMap as AHashMap, HashMapExt, History, HistoryOptions, Id, Map, RawDecoder,
-    RawEncoder, StateVector, StoreHistory, Text, TextAttributes, TextDelta, TextDeltaOp, TextInsert, Update, Value,
-    batch_commit, encode_awareness_as_message, encode_update_as_message, merge_updates_v1,
+    AbsolutePosition, Any, AnySerializer, Array, ArrayDelta, ArrayDeltaOp, ArrayEvent, Assoc, Awareness,
+    AwarenessEvent, Batch, Client, ClientMap, Clock, CrdtRead, CrdtReader, CrdtWrite, CrdtWriter, Doc, DocOptions,
+    EntryChange, Event, HashMap as AHashMap, HashMapExt, History, HistoryOptions, Id, Map, MapEvent, OffsetKind,
+    Origin, Path, PathSegment, Prelim, RawDecoder, RawDecoderV2, RawEncoder, RawEncoderV2, RelativePosition, Snapshot,
+    StateVector, StoreHistory, SubdocsEvent, Subscription, Text, TextAttributes, TextDelta, TextDeltaOp, TextEvent,
+    TextInsert, Transaction, TransactionMut, UndoManager, UndoManagerOptions, Update, Value, XMLChildren, XMLElement,
+    XMLEvent, XMLFragment, XMLHook, XMLText, batch_commit, convert_update_v1_to_v2, convert_update_v2_to_v1,
+    encode_awareness_as_message, encode_update_as_message, from_any, from_value, merge_updates_v1, to_value,
 };
 pub(crate) use doc::{Content, Item};
 use log::{debug, warn};
@@ -57,10 +61,22 @@ pub enum JwstCodecError {
     RootStructNotFound(String),
     #[error("Index {0} out of bound")]
     IndexOutOfBound(u64),
+    #[error("Key {0} not found")]
+    KeyNotFound(String),
     #[error("Document has been released")]
     DocReleased,
     #[error("Unexpected type, expect {0}")]
     UnexpectedType(&'static str),
+    #[error("Garbage collection must be disabled to restore snapshot")]
+    SnapshotGcEnabled,
+    #[error("Invalid relative position")]
+    InvalidRelativePosition,
+    #[error("Can not set a root type, the path needs a key or an index after the root name")]
+    RootSetNotSupport,
+    #[error("Can not insert a doc into itself or its subdocs")]
+    InvalidSubdoc,
+    #[error("Failed to serialize or deserialize: {0}")]
+    Serde(String),
 }
 
 pub type JwstCodecResult<T = ()> = Result<T, JwstCodecError>;
@@ -1,20 +1,20 @@
-pub use std::sync::{Arc, Weak};
+pub use std::sync::{Arc, OnceLock, Weak};
+#[cfg(all(test, not(loom)))]
+pub(crate) use std::sync::{MutexGuard, atomic::AtomicUsize};
 #[allow(unused)]
 #[cfg(not(loom))]
-pub(crate) use std::sync::{
-    Mutex, RwLock, RwLockReadGuard, RwLockWriteGuard,
-    atomic::{AtomicBool, AtomicU8, AtomicU16, AtomicU32, Ordering},
-};
-#[cfg(all(test, not(loom)))]
 pub(crate) use std::{
-    sync::{MutexGuard, atomic::AtomicUsize},
+    sync::{
+        Condvar, Mutex, RwLock, RwLockReadGuard, RwLockWriteGuard,
+        atomic::{AtomicBool, AtomicU8, AtomicU16, AtomicU32, Ordering},
+    },
     thread,
 };
 
 #[cfg(loom)]
 pub(crate) use loom::{
     sync::{
-        Mutex, MutexGuard, RwLock, RwLockReadGuard, RwLockWriteGuard,
+        Condvar, Mutex, MutexGuard, RwLock, RwLockReadGuard, RwLockWriteGuard,
         atomic::{AtomicBool, AtomicU8, AtomicU16, AtomicU32, AtomicUsize, Ordering},
     },
     thread,